@@ -0,0 +1,136 @@
+use serde::Deserialize;
+use urlencoding::encode;
+
+use crate::api::get_json;
+use crate::coverart::types::CoverArtQuery;
+
+const BASE_URL: &str = "https://api.deezer.com";
+
+#[derive(Deserialize)]
+struct SearchResponse<T> {
+    #[serde(default = "Vec::new")]
+    data: Vec<T>,
+    /// Set instead of the data when the request was rejected, with a 200 status.
+    error: Option<Error>,
+}
+
+#[derive(Deserialize)]
+struct Error {
+    message: String,
+}
+
+#[derive(Deserialize)]
+struct Album {
+    /// The largest cover, 1000px.
+    cover_xl: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct Track {
+    album: Album,
+}
+
+pub struct Deezer {
+    pub base_url: String,
+}
+
+impl Default for Deezer {
+    fn default() -> Self {
+        Self {
+            base_url: BASE_URL.to_string(),
+        }
+    }
+}
+
+impl Deezer {
+    pub async fn get_cover_art(&self, query: &CoverArtQuery) -> Result<Option<String>, String> {
+        if let Some(title) = &query.title {
+            let q = format!("artist:\"{}\" track:\"{}\"", query.artist, title);
+            let url = format!("{}/search/track?q={}&limit=1", self.base_url, encode(&q));
+            let tracks = Self::search::<Track>(&url).await?;
+            return Ok(tracks.into_iter().find_map(|t| t.album.cover_xl));
+        }
+
+        let Some(album) = &query.album else {
+            return Ok(None);
+        };
+        let q = format!("artist:\"{}\" album:\"{}\"", query.artist, album);
+        let url = format!("{}/search/album?q={}&limit=1", self.base_url, encode(&q));
+        let albums = Self::search::<Album>(&url).await?;
+        Ok(albums.into_iter().find_map(|a| a.cover_xl))
+    }
+
+    async fn search<T: serde::de::DeserializeOwned>(url: &str) -> Result<Vec<T>, String> {
+        let response = get_json::<SearchResponse<T>>(url).await?;
+        match response.error {
+            Some(error) => Err(error.message),
+            None => Ok(response.data),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::test_server::{lookup, query, Route};
+
+    const COVER: &str = "https://cdn-images.dzcdn.net/images/cover/2e018122cb56986277102d2041a592c8/1000x1000-000000-80-0-0.jpg";
+
+    fn deezer(base_url: String) -> Deezer {
+        Deezer { base_url }
+    }
+
+    #[tokio::test]
+    async fn finds_album_cover() {
+        let routes = vec![Route::new(
+            "/search/album",
+            200,
+            include_str!("fixtures/deezer_album.json"),
+        )];
+        let (server, result) = lookup(routes, deezer, &query("Daft Punk", "Discovery", None)).await;
+        assert_eq!(result.unwrap().as_deref(), Some(COVER));
+        assert_eq!(
+            server.requests(),
+            ["/search/album?q=artist%3A%22Daft%20Punk%22%20album%3A%22Discovery%22&limit=1"]
+        );
+    }
+
+    #[tokio::test]
+    async fn finds_track_cover() {
+        let routes = vec![Route::new(
+            "/search/track",
+            200,
+            include_str!("fixtures/deezer_track.json"),
+        )];
+        let query = query(
+            "Daft Punk",
+            "Discovery",
+            Some("Harder, Better, Faster, Stronger"),
+        );
+        let (_, result) = lookup(routes, deezer, &query).await;
+        assert_eq!(result.unwrap().as_deref(), Some(COVER));
+    }
+
+    #[tokio::test]
+    async fn empty_results_have_no_cover() {
+        let routes = vec![Route::new(
+            "/search/album",
+            200,
+            include_str!("fixtures/deezer_empty.json"),
+        )];
+        let (_, result) = lookup(routes, deezer, &query("Daft Punk", "Discovery", None)).await;
+        assert_eq!(result, Ok(None));
+    }
+
+    /// Rejected requests are answered with a 200 status and an error object.
+    #[tokio::test]
+    async fn error_body_is_an_error() {
+        let routes = vec![Route::new(
+            "/search/album",
+            200,
+            include_str!("fixtures/deezer_error.json"),
+        )];
+        let (_, result) = lookup(routes, deezer, &query("Daft Punk", "Discovery", None)).await;
+        assert_eq!(result, Err("Quota limit exceeded".to_string()));
+    }
+}
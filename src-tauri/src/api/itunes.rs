@@ -0,0 +1,117 @@
+use serde::Deserialize;
+use urlencoding::encode;
+
+use crate::api::get_json;
+use crate::coverart::types::CoverArtQuery;
+
+const BASE_URL: &str = "https://itunes.apple.com";
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SearchResponse {
+    #[serde(default)]
+    results: Vec<SearchResult>,
+    /// Set instead of the results when the request was rejected.
+    error_message: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SearchResult {
+    artwork_url100: Option<String>,
+}
+
+pub struct ITunes {
+    pub base_url: String,
+}
+
+impl Default for ITunes {
+    fn default() -> Self {
+        Self {
+            base_url: BASE_URL.to_string(),
+        }
+    }
+}
+
+impl ITunes {
+    pub async fn get_cover_art(&self, query: &CoverArtQuery) -> Result<Option<String>, String> {
+        let (entity, term) = match (&query.title, &query.album) {
+            (Some(title), _) => ("song", format!("{} {}", query.artist, title)),
+            (None, Some(album)) => ("album", format!("{} {}", query.artist, album)),
+            (None, None) => return Ok(None),
+        };
+
+        let url = format!(
+            "{}/search?term={}&entity={}&media=music&limit=1",
+            self.base_url,
+            encode(&term),
+            entity
+        );
+        let response = get_json::<SearchResponse>(&url).await?;
+        if let Some(message) = response.error_message {
+            return Err(message);
+        }
+
+        // Artwork is served at any size up to the original by rewriting the file name
+        Ok(response
+            .results
+            .into_iter()
+            .find_map(|r| r.artwork_url100)
+            .map(|url| url.replace("100x100bb", "100000x100000-999")))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::test_server::{lookup, query, Route};
+
+    fn itunes(base_url: String) -> ITunes {
+        ITunes { base_url }
+    }
+
+    #[tokio::test]
+    async fn finds_full_resolution_artwork() {
+        let routes = vec![Route::new(
+            "/search",
+            200,
+            include_str!("fixtures/itunes_album.json"),
+        )];
+        let (server, result) =
+            lookup(routes, itunes, &query("Radiohead", "OK Computer", None)).await;
+        assert_eq!(
+            result.unwrap().as_deref(),
+            Some("https://is1-ssl.mzstatic.com/image/thumb/Music125/v4/e3/37/b7/e337b7a4-b8a9-0b96-3d7b-7a4a9ab4d1a6/mzi.nzfnhtvo.jpg/100000x100000-999.jpg")
+        );
+        assert_eq!(
+            server.requests(),
+            ["/search?term=Radiohead%20OK%20Computer&entity=album&media=music&limit=1"]
+        );
+    }
+
+    #[tokio::test]
+    async fn empty_results_have_no_cover() {
+        let routes = vec![Route::new(
+            "/search",
+            200,
+            include_str!("fixtures/itunes_empty.json"),
+        )];
+        let (_, result) = lookup(routes, itunes, &query("Radiohead", "OK Computer", None)).await;
+        assert_eq!(result, Ok(None));
+    }
+
+    /// Rejected requests are answered with an `errorMessage` instead of results.
+    #[tokio::test]
+    async fn error_body_is_an_error() {
+        let routes = vec![Route::new(
+            "/search",
+            200,
+            include_str!("fixtures/itunes_error.json"),
+        )];
+        let (_, result) = lookup(routes, itunes, &query("Radiohead", "OK Computer", None)).await;
+        assert_eq!(
+            result,
+            Err("Invalid value(s) for key(s): [resultEntity]".to_string())
+        );
+    }
+}
@@ -0,0 +1,168 @@
+use serde::Deserialize;
+use urlencoding::encode;
+
+use crate::api::headers;
+use crate::coverart::types::CoverArtQuery;
+
+const BASE_URL: &str = "https://ws.audioscrobbler.com/2.0";
+
+/// Error code of unknown albums and tracks.
+const ERROR_NOT_FOUND: i64 = 6;
+
+lazy_static::lazy_static! {
+    /// Size segment of image URLs, without which the original is served.
+    static ref IMAGE_SIZE_SEGMENT: regex::Regex = regex::Regex::new(r"/i/u/[^/]+/").unwrap();
+}
+
+/// Errors are answered with a code and message instead.
+#[derive(Deserialize)]
+struct InfoResponse {
+    album: Option<Album>,
+    track: Option<Track>,
+    error: Option<i64>,
+    message: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct Track {
+    album: Option<Album>,
+}
+
+#[derive(Deserialize)]
+struct Album {
+    #[serde(default)]
+    image: Vec<Image>,
+}
+
+#[derive(Deserialize)]
+struct Image {
+    #[serde(rename = "#text")]
+    url: String,
+}
+
+/// Last.fm, which needs an API key of the user.
+pub struct LastFm {
+    pub base_url: String,
+    pub api_key: String,
+}
+
+impl LastFm {
+    pub fn new(api_key: &str) -> Self {
+        Self {
+            base_url: BASE_URL.to_string(),
+            api_key: api_key.to_string(),
+        }
+    }
+
+    pub async fn get_cover_art(&self, query: &CoverArtQuery) -> Result<Option<String>, String> {
+        let (method, name) = match (&query.title, &query.album) {
+            (Some(title), _) => ("track.getInfo", format!("track={}", encode(title))),
+            (None, Some(album)) => ("album.getInfo", format!("album={}", encode(album))),
+            (None, None) => return Ok(None),
+        };
+
+        let url = format!(
+            "{}/?method={}&api_key={}&artist={}&{}&autocorrect=1&format=json",
+            self.base_url,
+            method,
+            encode(&self.api_key),
+            encode(&query.artist),
+            name
+        );
+        let response = Self::get(&url).await?;
+        match response.error {
+            Some(ERROR_NOT_FOUND) => return Ok(None),
+            Some(code) => {
+                return Err(format!(
+                    "Error {}: {}",
+                    code,
+                    response.message.unwrap_or_default()
+                ))
+            }
+            None => {}
+        }
+
+        let album = response.album.or(response.track.and_then(|t| t.album));
+        // Images are listed from small to large
+        Ok(album
+            .and_then(|a| a.image.into_iter().rev().find(|i| !i.url.is_empty()))
+            .map(|i| IMAGE_SIZE_SEGMENT.replace(&i.url, "/i/u/").to_string()))
+    }
+
+    /// Errors come with a non-success status, but their body tells unknown albums apart.
+    async fn get(url: &str) -> Result<InfoResponse, String> {
+        let client = reqwest::Client::builder()
+            .default_headers(headers())
+            .build()
+            .map_err(|e| e.to_string())?;
+
+        let response = client.get(url).send().await.map_err(|e| e.to_string())?;
+        let status = response.status();
+        match response.json::<InfoResponse>().await {
+            Ok(info) if status.is_success() || info.error.is_some() => Ok(info),
+            Ok(_) => Err(format!("Unexpected status {}", status)),
+            Err(_) if !status.is_success() => Err(format!("Unexpected status {}", status)),
+            Err(e) => Err(e.to_string()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::test_server::{lookup, query, Route};
+
+    fn lastfm(base_url: String) -> LastFm {
+        LastFm {
+            base_url,
+            api_key: "key".to_string(),
+        }
+    }
+
+    #[tokio::test]
+    async fn finds_original_image() {
+        let routes = vec![Route::new(
+            "/",
+            200,
+            include_str!("fixtures/lastfm_album.json"),
+        )];
+        let (server, result) = lookup(routes, lastfm, &query("Portishead", "Dummy", None)).await;
+        assert_eq!(
+            result.unwrap().as_deref(),
+            Some("https://lastfm.freetls.fastly.net/i/u/3ab1ef4a6a7e4e3ab0b4c6c1f0d4c6a1.png")
+        );
+        assert_eq!(
+            server.requests(),
+            ["/?method=album.getInfo&api_key=key&artist=Portishead&album=Dummy&autocorrect=1&format=json"]
+        );
+    }
+
+    /// Unknown albums are answered with an error code and a non-success status.
+    #[tokio::test]
+    async fn unknown_album_has_no_cover() {
+        let routes = vec![Route::new(
+            "/",
+            404,
+            include_str!("fixtures/lastfm_not_found.json"),
+        )];
+        let (_, result) = lookup(routes, lastfm, &query("Portishead", "Dummy", None)).await;
+        assert_eq!(result, Ok(None));
+    }
+
+    #[tokio::test]
+    async fn error_body_is_an_error() {
+        let routes = vec![Route::new(
+            "/",
+            403,
+            include_str!("fixtures/lastfm_invalid_key.json"),
+        )];
+        let (_, result) = lookup(routes, lastfm, &query("Portishead", "Dummy", None)).await;
+        assert_eq!(
+            result,
+            Err(
+                "Error 10: Invalid API key - You must be granted a valid key by last.fm"
+                    .to_string()
+            )
+        );
+    }
+}
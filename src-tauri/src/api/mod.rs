@@ -1 +1,43 @@
+use dotenvy_macro::dotenv;
+use tauri::http::HeaderMap;
+
+pub mod deezer;
+pub mod itunes;
+pub mod lastfm;
 pub mod musicbrainz;
+#[cfg(test)]
+pub mod test_server;
+
+fn user_agent() -> String {
+    format!(
+        "{}/{} ( {} )",
+        dotenv!("VITE_APP_NAME"),
+        dotenv!("VITE_APP_VERSION"),
+        dotenv!("VITE_APP_CONTACT_INFO")
+    )
+}
+
+/// Headers identifying the app, which MusicBrainz requires and other APIs appreciate.
+pub fn headers() -> HeaderMap {
+    let mut headers = HeaderMap::new();
+    headers.insert("User-Agent", user_agent().parse().unwrap());
+    headers
+}
+
+/// Get `url` and parse the JSON response. Non-success statuses are errors.
+async fn get_json<T: serde::de::DeserializeOwned>(url: &str) -> Result<T, String> {
+    let client = reqwest::Client::builder()
+        .default_headers(headers())
+        .build()
+        .map_err(|e| e.to_string())?;
+
+    client
+        .get(url)
+        .send()
+        .await
+        .and_then(|r| r.error_for_status())
+        .map_err(|e| e.to_string())?
+        .json::<T>()
+        .await
+        .map_err(|e| e.to_string())
+}
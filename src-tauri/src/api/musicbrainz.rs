@@ -1,7 +1,7 @@
-use dotenvy_macro::dotenv;
 use serde::{Deserialize, Serialize};
-use tauri::http::HeaderMap;
+use urlencoding::encode;
 
+use crate::api::headers;
 use crate::coverart::types::CoverArtQuery;
 
 const BASE_URL: &str = "https://musicbrainz.org/ws/2";
@@ -35,13 +35,10 @@ struct CoverArtResponse {
 
 #[derive(Serialize, Deserialize)]
 struct CoverArtImage {
-    thumbnails: CoverArtThumbnails,
-}
-
-#[derive(Serialize, Deserialize)]
-struct CoverArtThumbnails {
-    #[serde(alias = "500")]
-    i500: String,
+    /// The original upload, at full resolution.
+    image: String,
+    #[serde(default)]
+    front: bool,
 }
 
 // enum BrowseType {
@@ -49,37 +46,39 @@ struct CoverArtThumbnails {
 //     ReleaseGroup
 // }
 
-fn user_agent() -> String {
-    format!(
-        "{}/{} ( {} )",
-        dotenv!("VITE_APP_NAME").to_string(),
-        dotenv!("VITE_APP_VERSION").to_string(),
-        dotenv!("VITE_APP_CONTACT_INFO").to_string()
-    )
+pub struct MusicBrainz {
+    pub base_url: String,
+    pub cover_art_url: String,
 }
 
-fn headers() -> HeaderMap {
-    let mut headers = HeaderMap::new();
-    headers.insert("User-Agent", user_agent().parse().unwrap());
-    headers
+impl Default for MusicBrainz {
+    fn default() -> Self {
+        Self {
+            base_url: BASE_URL.to_string(),
+            cover_art_url: BASE_COVER_ART_URL.to_string(),
+        }
+    }
 }
 
-pub struct MusicBrainz;
-
 impl MusicBrainz {
-    async fn browse(query: CoverArtQuery) -> Result<reqwest::Response, String> {
+    async fn browse(&self, query: CoverArtQuery) -> Result<reqwest::Response, String> {
         let mut btype = String::from("");
         let mut bquery = String::from("");
-        if query.album.is_some() {
+        if let Some(album) = &query.album {
             btype = "release-group".to_string();
-            bquery = format!("{} {}", query.artist, query.album.unwrap());
+            bquery = format!("{} {}", query.artist, album);
         }
-        if query.title.is_some() {
+        if let Some(title) = &query.title {
             btype = "release".to_string();
-            bquery = format!("{} {}", query.artist, query.title.unwrap());
+            bquery = format!("{} {}", query.artist, title);
         }
 
-        let url = format!("{}/{}?query={}&fmt=json&limit=1", BASE_URL, btype, bquery);
+        let url = format!(
+            "{}/{}?query={}&fmt=json&limit=1",
+            self.base_url,
+            btype,
+            encode(&bquery)
+        );
 
         let client = reqwest::Client::builder()
             .default_headers(headers())
@@ -91,31 +90,35 @@ impl MusicBrainz {
             .headers(headers())
             .send()
             .await
+            .and_then(|r| r.error_for_status())
             .map_err(|e| e.to_string())
     }
 
-    async fn browse_release_group(query: CoverArtQuery) -> Result<ReleaseGroupResponse, String> {
-        let response = MusicBrainz::browse(query).await?;
+    async fn browse_release_group(
+        &self,
+        query: CoverArtQuery,
+    ) -> Result<ReleaseGroupResponse, String> {
+        let response = self.browse(query).await?;
         response
             .json::<ReleaseGroupResponse>()
             .await
             .map_err(|e| e.to_string())
     }
 
-    async fn browse_release(query: CoverArtQuery) -> Result<ReleaseResponse, String> {
-        let response = MusicBrainz::browse(query).await?;
+    async fn browse_release(&self, query: CoverArtQuery) -> Result<ReleaseResponse, String> {
+        let response = self.browse(query).await?;
         response
             .json::<ReleaseResponse>()
             .await
             .map_err(|e| e.to_string())
     }
 
-    pub async fn get_cover_art(query: CoverArtQuery) -> Result<Option<String>, String> {
+    pub async fn get_cover_art(&self, query: CoverArtQuery) -> Result<Option<String>, String> {
         let mut id = String::from("");
         let mut ctype = String::from("");
 
         if query.album.is_some() {
-            let rg_browse = MusicBrainz::browse_release_group(query.clone()).await?;
+            let rg_browse = self.browse_release_group(query.clone()).await?;
 
             let release_groups = rg_browse.release_groups;
             if release_groups.is_empty() {
@@ -126,7 +129,7 @@ impl MusicBrainz {
         }
 
         if query.title.is_some() {
-            let r_browse = MusicBrainz::browse_release(query.clone()).await?;
+            let r_browse = self.browse_release(query.clone()).await?;
 
             let releases = r_browse.releases;
             if releases.is_empty() {
@@ -143,23 +146,17 @@ impl MusicBrainz {
             .map_err(|e| e.to_string())?;
 
         let response = client
-            .get(format!("{}/{}/{}", BASE_COVER_ART_URL, ctype, id))
+            .get(format!("{}/{}/{}", self.cover_art_url, ctype, id))
             .headers(headers())
             .send()
             .await
             .map_err(|e| e.to_string())?;
 
-        // If the cover art doesn't exist, the API might return 404.
-        // We should check the status before trying to parse JSON.
+        // Releases without cover art are answered with a 404
         if response.status() == reqwest::StatusCode::NOT_FOUND {
             return Ok(None);
         }
-
-        // Perform error check for other non-success codes if desirable,
-        // but sticking to original logic of just trying to parse or catching error.
-        // Actually, if it's not 200 OK, parsing JSON might fail or return error JSON.
-        // Let's rely on map_err for json parsing unless we want strict status checking.
-        // Original code didn't check status explicitly, just allowed fail on .json().
+        let response = response.error_for_status().map_err(|e| e.to_string())?;
 
         let json = response
             .json::<CoverArtResponse>()
@@ -171,6 +168,106 @@ impl MusicBrainz {
             return Ok(None);
         }
 
-        Ok(Some(images[0].thumbnails.i500.clone()))
+        // Releases may have back covers and booklets too
+        let image = images.iter().find(|i| i.front).unwrap_or(&images[0]);
+        Ok(Some(image.image.clone()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::test_server::{lookup, query, Route};
+
+    const RELEASE_GROUP_ID: &str = "b1392450-e666-3926-a536-22c65f834433";
+
+    fn musicbrainz(server_url: String) -> MusicBrainz {
+        MusicBrainz {
+            base_url: format!("{}/ws/2", server_url),
+            cover_art_url: format!("{}/caa", server_url),
+        }
+    }
+
+    fn release_group() -> Route {
+        Route::new(
+            "/ws/2/release-group",
+            200,
+            include_str!("fixtures/musicbrainz_release_group.json"),
+        )
+    }
+
+    #[tokio::test]
+    async fn finds_front_image() {
+        let routes = vec![
+            release_group(),
+            Route::new(
+                "/caa/release-group",
+                200,
+                include_str!("fixtures/coverartarchive_release_group.json"),
+            ),
+        ];
+        let (server, result) = lookup(
+            routes,
+            musicbrainz,
+            &query("Radiohead", "OK Computer", None),
+        )
+        .await;
+        assert_eq!(
+            result.unwrap().as_deref(),
+            Some("http://coverartarchive.org/release/7b2e2ac4-f2a9-4c5c-8e3b-5b0a2c3b7e7c/17632465722.jpg")
+        );
+        assert_eq!(
+            server.requests(),
+            [
+                "/ws/2/release-group?query=Radiohead%20OK%20Computer&fmt=json&limit=1".to_string(),
+                format!("/caa/release-group/{}", RELEASE_GROUP_ID),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn empty_results_have_no_cover() {
+        let routes = vec![Route::new(
+            "/ws/2/release-group",
+            200,
+            include_str!("fixtures/musicbrainz_empty.json"),
+        )];
+        let (server, result) = lookup(
+            routes,
+            musicbrainz,
+            &query("Radiohead", "OK Computer", None),
+        )
+        .await;
+        assert_eq!(result, Ok(None));
+        assert_eq!(server.requests().len(), 1);
+    }
+
+    /// The Cover Art Archive answers releases without art with a 404.
+    #[tokio::test]
+    async fn release_group_without_art_has_no_cover() {
+        let routes = vec![release_group(), Route::new("/caa/release-group", 404, "")];
+        let (_, result) = lookup(
+            routes,
+            musicbrainz,
+            &query("Radiohead", "OK Computer", None),
+        )
+        .await;
+        assert_eq!(result, Ok(None));
+    }
+
+    #[tokio::test]
+    async fn error_body_is_an_error() {
+        let routes = vec![Route::new(
+            "/ws/2/release-group",
+            200,
+            include_str!("fixtures/musicbrainz_error.json"),
+        )];
+        let (_, result) = lookup(
+            routes,
+            musicbrainz,
+            &query("Radiohead", "OK Computer", None),
+        )
+        .await;
+        assert!(result.is_err());
     }
 }
@@ -0,0 +1,122 @@
+//! A local HTTP stand-in for the online APIs, serving recorded responses.
+
+use std::io::{BufRead, BufReader, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::{Arc, Mutex};
+
+use crate::coverart::provider::CoverArtProvider;
+use crate::coverart::types::CoverArtQuery;
+
+pub type Lookup = Result<Option<String>, String>;
+
+/// Query for the cover of `album`, or of its track `title` when given.
+pub fn query(artist: &str, album: &str, title: Option<&str>) -> CoverArtQuery {
+    CoverArtQuery {
+        artist: artist.to_string(),
+        album: Some(album.to_string()),
+        title: title.map(str::to_string),
+        path: None,
+    }
+}
+
+/// Look up `query` with the provider that `provider` makes for the server's URL, while the
+/// server answers with `routes`.
+pub async fn lookup<P: CoverArtProvider>(
+    routes: Vec<Route>,
+    provider: impl FnOnce(String) -> P,
+    query: &CoverArtQuery,
+) -> (TestServer, Lookup) {
+    let server = TestServer::start(routes);
+    let result = provider(server.url.clone()).cover_art_url(query).await;
+    (server, result)
+}
+
+/// Response to requests whose path starts with `path`. `{{server}}` in the body is replaced
+/// with the server's URL, for responses linking back to it.
+pub struct Route {
+    pub path: String,
+    pub status: u16,
+    pub body: Vec<u8>,
+}
+
+impl Route {
+    pub fn new(path: &str, status: u16, body: impl Into<Vec<u8>>) -> Self {
+        Self {
+            path: path.to_string(),
+            status,
+            body: body.into(),
+        }
+    }
+}
+
+pub struct TestServer {
+    pub url: String,
+    requests: Arc<Mutex<Vec<String>>>,
+}
+
+impl TestServer {
+    /// Serve `routes` on a free local port until the test process exits. Requests matching
+    /// no route get a 404.
+    pub fn start(routes: Vec<Route>) -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let requests = Arc::new(Mutex::new(Vec::new()));
+
+        let routes: Vec<Route> = routes
+            .into_iter()
+            .map(|route| Route {
+                body: match String::from_utf8(route.body) {
+                    Ok(body) => body.replace("{{server}}", &url).into_bytes(),
+                    Err(e) => e.into_bytes(),
+                },
+                ..route
+            })
+            .collect();
+
+        let received = requests.clone();
+        std::thread::spawn(move || {
+            for stream in listener.incoming().flatten() {
+                if let Some(target) = Self::respond(stream, &routes) {
+                    received.lock().unwrap().push(target);
+                }
+            }
+        });
+
+        Self { url, requests }
+    }
+
+    /// Paths and queries requested so far, in order.
+    pub fn requests(&self) -> Vec<String> {
+        self.requests.lock().unwrap().clone()
+    }
+
+    fn respond(mut stream: TcpStream, routes: &[Route]) -> Option<String> {
+        let mut reader = BufReader::new(stream.try_clone().ok()?);
+        let mut request_line = String::new();
+        reader.read_line(&mut request_line).ok()?;
+        // Requests are GETs without a body, so the headers are all that's left
+        loop {
+            let mut line = String::new();
+            if reader.read_line(&mut line).ok()? == 0 || line == "\r\n" {
+                break;
+            }
+        }
+
+        let target = request_line.split_whitespace().nth(1)?.to_string();
+        let path = target.split('?').next().unwrap_or_default();
+        let (status, body) = routes
+            .iter()
+            .find(|route| path.starts_with(&route.path))
+            .map(|route| (route.status, route.body.as_slice()))
+            .unwrap_or((404, b"".as_slice()));
+
+        let head = format!(
+            "HTTP/1.1 {} Stand-in\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+            status,
+            body.len()
+        );
+        stream.write_all(head.as_bytes()).ok()?;
+        stream.write_all(body).ok()?;
+        Some(target)
+    }
+}
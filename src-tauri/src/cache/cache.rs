@@ -0,0 +1,543 @@
+use std::path::PathBuf;
+use std::sync::{Mutex, Once};
+use std::time::{Duration, Instant};
+
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tauri::Manager;
+
+use crate::database::database::GLOBAL_DATABASE;
+use crate::state::{app_handle, app_store};
+
+const STORE_KEY_CACHE_MAX_SIZE: &str = "cache-max-size";
+const DEFAULT_MAX_SIZE: u64 = 512 * 1024 * 1024;
+
+/// Directories used before the cache was shared, removed on first use.
+const LEGACY_DIRS: [&str; 2] = ["coverarts", "lyrics"];
+
+/// Reads are written to `accessed_at` once this many piled up, or after `READ_FLUSH_INTERVAL`.
+const READ_BATCH_SIZE: usize = 64;
+const READ_FLUSH_INTERVAL: Duration = Duration::from_secs(30);
+
+static LEGACY_CLEANUP: Once = Once::new();
+
+/// Reads not yet written to `accessed_at`, so reading an entry doesn't need the database.
+static PENDING_READS: Mutex<PendingReads> = Mutex::new(PendingReads {
+    entries: Vec::new(),
+    since: None,
+});
+
+/// Running size of all entries, summed from the database on first use. Only changed while
+/// holding the database lock, so it stays in step with `cache_entries`.
+static TOTAL_SIZE: Mutex<Option<i64>> = Mutex::new(None);
+
+struct PendingReads {
+    /// Entry names with the time they were read.
+    entries: Vec<(String, i64)>,
+    since: Option<Instant>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum CacheKind {
+    /// Covers of local tracks, at the sizes they were requested in.
+    Cover,
+    /// Covers downloaded from online sources.
+    OnlineCover,
+    Lyrics,
+}
+
+impl CacheKind {
+    pub const ALL: [CacheKind; 3] = [CacheKind::Cover, CacheKind::OnlineCover, CacheKind::Lyrics];
+
+    fn as_str(&self) -> &'static str {
+        match self {
+            CacheKind::Cover => "cover",
+            CacheKind::OnlineCover => "online_cover",
+            CacheKind::Lyrics => "lyrics",
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct CacheKindStats {
+    pub kind: CacheKind,
+    pub entries: i64,
+    /// Size in bytes.
+    pub size: i64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct CacheStats {
+    pub kinds: Vec<CacheKindStats>,
+    /// Size of all entries in bytes.
+    pub size: i64,
+    pub max_size: u64,
+}
+
+/// Files cached on disk by content. Each entry is a file named after the hash of its
+/// content, plus a variant such as the size of a resized cover, so identical content is
+/// stored once. Lookup keys such as `"cover:Artist Album"` point to a hash.
+///
+/// The least recently read entries are evicted once the cache grows past its maximum size.
+pub struct Cache;
+
+impl Cache {
+    pub fn max_size() -> u64 {
+        app_store()
+            .get(STORE_KEY_CACHE_MAX_SIZE)
+            .and_then(|v| serde_json::from_value(v).ok())
+            .unwrap_or(DEFAULT_MAX_SIZE)
+    }
+
+    pub fn set_max_size(max_size: u64) {
+        let store = app_store();
+        store.set(STORE_KEY_CACHE_MAX_SIZE, serde_json::json!(max_size));
+        let _ = store.save();
+        Self::evict();
+    }
+
+    pub fn content_hash(bytes: &[u8]) -> String {
+        format!("{:x}", Sha256::digest(bytes))
+    }
+
+    /// Hash of the content last stored for `key`.
+    pub fn hash_of(key: &str) -> Option<String> {
+        let conn_guard = GLOBAL_DATABASE.lock().unwrap();
+        let conn = conn_guard.as_ref().unwrap();
+
+        conn.query_row(
+            "SELECT hash FROM cache_keys WHERE key = ?1",
+            params![key],
+            |row| row.get(0),
+        )
+        .optional()
+        .unwrap_or_else(|e| {
+            crate::error!("Failed to read cache key {}: {}", key, e);
+            None
+        })
+    }
+
+    /// Point `key` to `hash`, which should have been written before.
+    pub fn link(key: &str, hash: &str) {
+        let conn_guard = GLOBAL_DATABASE.lock().unwrap();
+        let conn = conn_guard.as_ref().unwrap();
+
+        if let Err(e) = conn.execute(
+            "INSERT INTO cache_keys (key, hash) VALUES (?1, ?2)
+            ON CONFLICT (key) DO UPDATE SET hash = excluded.hash",
+            params![key, hash],
+        ) {
+            crate::error!("Failed to write cache key {}: {}", key, e);
+        }
+    }
+
+    /// Forget `key`, so its content is looked up again. The content itself is left to eviction,
+    /// as other keys may point to it.
+    pub fn unlink(key: &str) {
+        let conn_guard = GLOBAL_DATABASE.lock().unwrap();
+        let conn = conn_guard.as_ref().unwrap();
+
+        if let Err(e) = conn.execute("DELETE FROM cache_keys WHERE key = ?1", params![key]) {
+            crate::error!("Failed to remove cache key {}: {}", key, e);
+        }
+    }
+
+    /// Read the entry of `hash` and `variant`, marking it as recently used.
+    pub fn read(hash: &str, variant: Option<u32>) -> Option<Vec<u8>> {
+        let name = Self::entry_name(hash, variant);
+        {
+            let conn_guard = GLOBAL_DATABASE.lock().unwrap();
+            let conn = conn_guard.as_ref().unwrap();
+
+            let indexed = conn
+                .query_row(
+                    "SELECT 1 FROM cache_entries WHERE name = ?1",
+                    params![name],
+                    |_| Ok(()),
+                )
+                .optional()
+                .ok()
+                .flatten()
+                .is_some();
+            if !indexed {
+                return None;
+            }
+        }
+
+        match std::fs::read(Self::cache_dir().join(&name)) {
+            Ok(bytes) => {
+                Self::mark_read(name);
+                Some(bytes)
+            }
+            Err(_) => {
+                // Removed from disk behind our back
+                let conn_guard = GLOBAL_DATABASE.lock().unwrap();
+                let conn = conn_guard.as_ref().unwrap();
+                if let Ok(size) = conn.query_row(
+                    "DELETE FROM cache_entries WHERE name = ?1 RETURNING size",
+                    params![name],
+                    |row| row.get::<_, i64>(0),
+                ) {
+                    Self::adjust_total_size(-size);
+                }
+                None
+            }
+        }
+    }
+
+    pub fn write(kind: CacheKind, hash: &str, variant: Option<u32>, bytes: &[u8]) {
+        let name = Self::entry_name(hash, variant);
+        if let Err(e) = std::fs::write(Self::cache_dir().join(&name), bytes) {
+            crate::warn!("Failed to write cache entry {}: {}", name, e);
+            return;
+        }
+
+        {
+            let conn_guard = GLOBAL_DATABASE.lock().unwrap();
+            let conn = conn_guard.as_ref().unwrap();
+
+            let size = bytes.len() as i64;
+            let result = (|| -> rusqlite::Result<i64> {
+                let previous: Option<i64> = conn
+                    .query_row(
+                        "SELECT size FROM cache_entries WHERE name = ?1",
+                        params![name],
+                        |row| row.get(0),
+                    )
+                    .optional()?;
+                conn.execute(
+                    "INSERT INTO cache_entries (name, hash, kind, size, accessed_at)
+                    VALUES (?1, ?2, ?3, ?4, ?5)
+                    ON CONFLICT (name) DO UPDATE SET
+                        size = excluded.size, accessed_at = excluded.accessed_at",
+                    params![
+                        name,
+                        hash,
+                        kind.as_str(),
+                        size,
+                        chrono::Utc::now().timestamp_millis()
+                    ],
+                )?;
+                Ok(size - previous.unwrap_or(0))
+            })();
+
+            match result {
+                Ok(delta) => Self::adjust_total_size(delta),
+                Err(e) => {
+                    crate::error!("Failed to index cache entry {}: {}", name, e);
+                    let _ = std::fs::remove_file(Self::cache_dir().join(&name));
+                    return;
+                }
+            }
+        }
+        Self::evict();
+    }
+
+    /// The content stored for `key`.
+    pub fn get(key: &str) -> Option<Vec<u8>> {
+        Self::read(&Self::hash_of(key)?, None)
+    }
+
+    /// Store `bytes` as the content of `key`.
+    pub fn put(kind: CacheKind, key: &str, bytes: &[u8]) {
+        let hash = Self::content_hash(bytes);
+        Self::write(kind, &hash, None, bytes);
+        Self::link(key, &hash);
+    }
+
+    /// Remove every entry, or only those of `kind`.
+    pub fn clear(kind: Option<CacheKind>) -> Result<(), String> {
+        let names: Vec<String> = {
+            let conn_guard = GLOBAL_DATABASE.lock().unwrap();
+            let conn = conn_guard.as_ref().unwrap();
+
+            let names = (|| -> rusqlite::Result<Vec<String>> {
+                let kind = kind.map(|k| k.as_str());
+                let names = conn
+                    .prepare("SELECT name FROM cache_entries WHERE ?1 IS NULL OR kind = ?1")?
+                    .query_map(params![kind], |row| row.get(0))?
+                    .collect::<rusqlite::Result<_>>()?;
+                conn.execute(
+                    "DELETE FROM cache_entries WHERE ?1 IS NULL OR kind = ?1",
+                    params![kind],
+                )?;
+                Ok(names)
+            })();
+            // Summed again on next use
+            *TOTAL_SIZE.lock().unwrap() = None;
+            names.map_err(|e| e.to_string())?
+        };
+
+        let dir = Self::cache_dir();
+        for name in names {
+            let _ = std::fs::remove_file(dir.join(name));
+        }
+        Ok(())
+    }
+
+    pub fn stats() -> Result<CacheStats, rusqlite::Error> {
+        let conn_guard = GLOBAL_DATABASE.lock().unwrap();
+        let conn = conn_guard.as_ref().unwrap();
+
+        let mut stmt = conn
+            .prepare("SELECT COUNT(*), IFNULL(SUM(size), 0) FROM cache_entries WHERE kind = ?1")?;
+        let kinds = CacheKind::ALL
+            .into_iter()
+            .map(|kind| {
+                stmt.query_row(params![kind.as_str()], |row| {
+                    Ok(CacheKindStats {
+                        kind,
+                        entries: row.get(0)?,
+                        size: row.get(1)?,
+                    })
+                })
+            })
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+
+        Ok(CacheStats {
+            size: kinds.iter().map(|k| k.size).sum(),
+            kinds,
+            max_size: Self::max_size(),
+        })
+    }
+
+    /// Remove the least recently used entries until the cache fits its maximum size.
+    fn evict() {
+        let max_size = Self::max_size() as i64;
+
+        let evicted = {
+            let conn_guard = GLOBAL_DATABASE.lock().unwrap();
+            let conn = conn_guard.as_ref().unwrap();
+
+            (|| -> rusqlite::Result<Vec<String>> {
+                let total = Self::total_size(conn)?;
+                if total <= max_size {
+                    return Ok(Vec::new());
+                }
+                // The order of eviction depends on the latest reads
+                Self::write_reads(conn, Self::take_pending_reads());
+
+                let (evicted, freed) = Self::evict_entries(conn, total - max_size)?;
+                Self::adjust_total_size(-freed);
+                Ok(evicted)
+            })()
+        };
+
+        match evicted {
+            Ok(evicted) => {
+                let dir = Self::cache_dir();
+                for name in &evicted {
+                    let _ = std::fs::remove_file(dir.join(name));
+                }
+                if !evicted.is_empty() {
+                    crate::info!("Evicted {} cache entries", evicted.len());
+                }
+            }
+            Err(e) => crate::error!("Failed to evict cache entries: {}", e),
+        }
+    }
+
+    /// Delete the least recently used entries until at least `excess` bytes are freed,
+    /// returning their names and the freed size.
+    fn evict_entries(conn: &Connection, excess: i64) -> rusqlite::Result<(Vec<String>, i64)> {
+        let mut freed = 0;
+        let mut evicted = Vec::new();
+        let mut stmt = conn.prepare("SELECT name, size FROM cache_entries ORDER BY accessed_at")?;
+        let mut rows = stmt.query([])?;
+        while let Some(row) = rows.next()? {
+            if freed >= excess {
+                break;
+            }
+            evicted.push(row.get::<_, String>(0)?);
+            freed += row.get::<_, i64>(1)?;
+        }
+        drop(rows);
+
+        let mut delete = conn.prepare("DELETE FROM cache_entries WHERE name = ?1")?;
+        for name in &evicted {
+            delete.execute(params![name])?;
+        }
+        Ok((evicted, freed))
+    }
+
+    /// Queue a read of `name`, writing the queued reads once enough piled up.
+    fn mark_read(name: String) {
+        let batch = {
+            let mut pending = PENDING_READS.lock().unwrap();
+            pending
+                .entries
+                .push((name, chrono::Utc::now().timestamp_millis()));
+            let since = *pending.since.get_or_insert_with(Instant::now);
+            if pending.entries.len() < READ_BATCH_SIZE && since.elapsed() < READ_FLUSH_INTERVAL {
+                return;
+            }
+            pending.since = None;
+            std::mem::take(&mut pending.entries)
+        };
+
+        let conn_guard = GLOBAL_DATABASE.lock().unwrap();
+        let conn = conn_guard.as_ref().unwrap();
+        Self::write_reads(conn, batch);
+    }
+
+    /// Write the queued reads, e.g. before the app exits.
+    pub fn flush_reads() {
+        let reads = Self::take_pending_reads();
+        if let Ok(conn_guard) = GLOBAL_DATABASE.lock() {
+            if let Some(conn) = conn_guard.as_ref() {
+                Self::write_reads(conn, reads);
+            }
+        }
+    }
+
+    fn take_pending_reads() -> Vec<(String, i64)> {
+        let mut pending = PENDING_READS.lock().unwrap();
+        pending.since = None;
+        std::mem::take(&mut pending.entries)
+    }
+
+    fn write_reads(conn: &Connection, reads: Vec<(String, i64)>) {
+        if reads.is_empty() {
+            return;
+        }
+        let result = (|| -> rusqlite::Result<()> {
+            let mut stmt = conn.prepare_cached(
+                "UPDATE cache_entries SET accessed_at = max(accessed_at, ?2) WHERE name = ?1",
+            )?;
+            for (name, accessed_at) in &reads {
+                stmt.execute(params![name, accessed_at])?;
+            }
+            Ok(())
+        })();
+        if let Err(e) = result {
+            crate::error!("Failed to update cache access times: {}", e);
+        }
+    }
+
+    /// Size of all entries, to be called while holding the database lock.
+    fn total_size(conn: &Connection) -> rusqlite::Result<i64> {
+        let mut total = TOTAL_SIZE.lock().unwrap();
+        if let Some(total) = *total {
+            return Ok(total);
+        }
+        let sum = conn.query_row(
+            "SELECT IFNULL(SUM(size), 0) FROM cache_entries",
+            [],
+            |row| row.get(0),
+        )?;
+        *total = Some(sum);
+        Ok(sum)
+    }
+
+    /// Follow a change of `delta` bytes, made while holding the database lock.
+    fn adjust_total_size(delta: i64) {
+        if let Some(total) = TOTAL_SIZE.lock().unwrap().as_mut() {
+            *total += delta;
+        }
+    }
+
+    fn entry_name(hash: &str, variant: Option<u32>) -> String {
+        match variant {
+            Some(variant) => format!("{}-{}", hash, variant),
+            None => hash.to_string(),
+        }
+    }
+
+    fn cache_dir() -> PathBuf {
+        let app_cache_dir = app_handle()
+            .path()
+            .app_cache_dir()
+            .expect("Failed to get app cache dir");
+
+        LEGACY_CLEANUP.call_once(|| {
+            for dir in LEGACY_DIRS {
+                let _ = std::fs::remove_dir_all(app_cache_dir.join(dir));
+            }
+        });
+
+        let dir = app_cache_dir.join("cache");
+        std::fs::create_dir_all(&dir).expect("Failed to create cache dir");
+        dir
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::migrations::DATABASE_MIGRATIONS;
+
+    /// A database with the entries as (name, hash, size, accessed_at).
+    fn entries(entries: &[(&str, &str, i64, i64)]) -> Connection {
+        let mut conn = Connection::open_in_memory().unwrap();
+        DATABASE_MIGRATIONS.to_latest(&mut conn).unwrap();
+        for (name, hash, size, accessed_at) in entries {
+            conn.execute(
+                "INSERT INTO cache_entries (name, hash, kind, size, accessed_at)
+                VALUES (?1, ?2, 'cover', ?3, ?4)",
+                params![name, hash, size, accessed_at],
+            )
+            .unwrap();
+            conn.execute(
+                "INSERT OR IGNORE INTO cache_keys (key, hash) VALUES (?1, ?1)",
+                params![hash],
+            )
+            .unwrap();
+        }
+        conn
+    }
+
+    fn names(conn: &Connection, table: &str, column: &str) -> Vec<String> {
+        let mut stmt = conn
+            .prepare(&format!("SELECT {0} FROM {1} ORDER BY {0}", column, table))
+            .unwrap();
+        stmt.query_map([], |row| row.get(0))
+            .unwrap()
+            .map(|r| r.unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn evicts_least_recently_used_first() {
+        let conn = entries(&[
+            ("a", "a", 10, 3),
+            ("b", "b", 10, 1),
+            ("c", "c", 10, 4),
+            ("d", "d", 10, 2),
+        ]);
+
+        let (evicted, freed) = Cache::evict_entries(&conn, 15).unwrap();
+        assert_eq!(evicted, ["b", "d"]);
+        assert_eq!(freed, 20);
+        assert_eq!(names(&conn, "cache_entries", "name"), ["a", "c"]);
+    }
+
+    #[test]
+    fn recent_reads_keep_entries() {
+        let conn = entries(&[("a", "a", 10, 1), ("b", "b", 10, 2)]);
+        Cache::write_reads(&conn, vec![("a".to_string(), 5)]);
+
+        let (evicted, _) = Cache::evict_entries(&conn, 10).unwrap();
+        assert_eq!(evicted, ["b"]);
+    }
+
+    #[test]
+    fn keys_go_with_the_last_entry_of_their_content() {
+        let conn = entries(&[("h-100", "h", 10, 1), ("h-300", "h", 30, 3)]);
+
+        Cache::evict_entries(&conn, 1).unwrap();
+        assert_eq!(names(&conn, "cache_keys", "key"), ["h"]);
+
+        Cache::evict_entries(&conn, 1).unwrap();
+        assert!(names(&conn, "cache_keys", "key").is_empty());
+    }
+
+    #[test]
+    fn nothing_is_evicted_without_excess() {
+        let conn = entries(&[("a", "a", 10, 1)]);
+        assert_eq!(Cache::evict_entries(&conn, 0).unwrap(), (Vec::new(), 0));
+    }
+}
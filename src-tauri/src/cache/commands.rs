@@ -0,0 +1,31 @@
+use crate::cache::cache::{Cache, CacheKind, CacheStats};
+use crate::coverart::queue as cover_art_queue;
+use crate::lyric::queue as lyric_queue;
+use crate::music::image_cache::ImageCache;
+
+#[tauri::command]
+pub fn cache_stats_get() -> Result<CacheStats, String> {
+    Cache::stats().map_err(|e| e.to_string())
+}
+
+/// Clear cached covers, online cover art and lyrics, or only those of `kind`.
+#[tauri::command]
+pub fn cache_clear(kind: Option<CacheKind>) -> Result<(), String> {
+    Cache::clear(kind)?;
+
+    // Entries known as loaded would otherwise be read from the removed files
+    for kind in kind.map(|k| vec![k]).unwrap_or(CacheKind::ALL.to_vec()) {
+        match kind {
+            CacheKind::Cover => ImageCache::clear_queue(),
+            CacheKind::OnlineCover => cover_art_queue::clear(),
+            CacheKind::Lyrics => lyric_queue::clear(),
+        }
+    }
+    Ok(())
+}
+
+/// Set the cache size limit in bytes, evicting entries right away if needed.
+#[tauri::command]
+pub fn cache_max_size_set(max_size: u64) {
+    Cache::set_max_size(max_size);
+}
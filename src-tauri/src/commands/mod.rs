@@ -10,8 +10,19 @@ pub const COMMAND_HANDLERS: fn(tauri::ipc::Invoke) -> bool = tauri::generate_han
     crate::music::commands::music_previous,
     crate::music::commands::music_clear,
     crate::music::commands::music_repeat_mode_set,
+    crate::music::commands::music_shuffle_set,
     crate::music::commands::music_position_set,
     crate::folder::commands::music_all_get,
+    crate::folder::commands::music_scan_cancel,
+    crate::folder::commands::music_scan_summary_get,
+    crate::folder::commands::music_scan_rules_get,
+    crate::folder::commands::music_scan_rules_set,
+    crate::folder::commands::music_roots_get,
+    crate::folder::commands::music_root_add,
+    crate::folder::commands::music_root_remove,
+    crate::folder::commands::music_root_enabled_set,
+    crate::folder::commands::music_root_label_set,
+    crate::music::commands::music_queue_get,
     crate::music::commands::music_queue_add,
     crate::music::commands::music_queue_remove,
     crate::music::commands::music_volume_set,
@@ -19,17 +30,38 @@ pub const COMMAND_HANDLERS: fn(tauri::ipc::Invoke) -> bool = tauri::generate_han
     crate::music::commands::music_queue_moveto,
     crate::music::commands::music_visualizer_buffer_get,
     crate::music::commands::music_image_get,
+    crate::music::commands::music_tags_write,
     // crate::music::commands::music_default_cover_art_get,
     crate::music::commands::music_current_duration_get,
     crate::music::commands::music_player_request_sync,
     crate::music::commands::music_lyrics_get,
     crate::music::commands::music_bit_perfect_toggle,
+    crate::music::commands::music_replay_gain_get,
+    crate::music::commands::music_replay_gain_set,
+    crate::music::commands::music_crossfade_get,
+    crate::music::commands::music_crossfade_set,
     #[cfg(desktop)]
     crate::music::commands::music_directory_request,
     #[cfg(desktop)]
     crate::music::commands::music_equalizer,
     #[cfg(desktop)]
     crate::music::commands::music_equalizer_reset,
+    #[cfg(desktop)]
+    crate::music::commands::music_equalizer_get,
+    #[cfg(desktop)]
+    crate::music::commands::music_equalizer_set,
+    #[cfg(desktop)]
+    crate::music::commands::music_equalizer_preset_all_get,
+    #[cfg(desktop)]
+    crate::music::commands::music_equalizer_preset_save,
+    #[cfg(desktop)]
+    crate::music::commands::music_equalizer_preset_apply,
+    #[cfg(desktop)]
+    crate::music::commands::music_equalizer_preset_delete,
+    #[cfg(desktop)]
+    crate::music::commands::music_output_devices_get,
+    #[cfg(desktop)]
+    crate::music::commands::music_output_device_set,
     // Folder commands
     crate::folder::commands::folder_items_get,
     crate::folder::commands::folder_first_music_path_get,
@@ -55,6 +87,12 @@ pub const COMMAND_HANDLERS: fn(tauri::ipc::Invoke) -> bool = tauri::generate_han
     mobile::android_directory_request,
     // Cover art commands
     crate::coverart::commands::cover_art_get,
+    crate::coverart::commands::cover_art_lookup_get,
+    crate::coverart::commands::cover_art_lookup_set,
+    // Cache commands
+    crate::cache::commands::cache_stats_get,
+    crate::cache::commands::cache_clear,
+    crate::cache::commands::cache_max_size_set,
     // Lyric commands
     crate::lyric::commands::lyric_get,
     // Platform-specific commands
@@ -66,7 +104,26 @@ pub const COMMAND_HANDLERS: fn(tauri::ipc::Invoke) -> bool = tauri::generate_han
     // Playlist commands
     crate::playlist::commands::playlist_all_get,
     crate::playlist::commands::playlist_create,
+    crate::playlist::commands::playlist_smart_preview,
     crate::playlist::commands::playlist_delete,
+    crate::playlist::commands::playlist_update,
+    crate::playlist::commands::playlist_musics_insert,
+    crate::playlist::commands::playlist_musics_remove,
+    crate::playlist::commands::playlist_musics_move,
     crate::playlist::commands::playlist_image_upload,
     crate::playlist::commands::playlist_image_read,
+    crate::playlist::commands::playlist_import,
+    crate::playlist::commands::playlist_export,
+    // History commands
+    crate::history::commands::music_play_counts_get,
+    crate::history::commands::music_top_tracks_get,
+    crate::history::commands::music_top_artists_get,
+    crate::history::commands::music_top_albums_get,
+    crate::history::commands::music_recently_played_get,
+    // Library commands
+    crate::library::commands::music_artists_get,
+    crate::library::commands::music_albums_get,
+    crate::library::commands::music_genres_get,
+    crate::library::commands::music_query,
+    crate::library::commands::music_search,
 ];
@@ -1,6 +1,7 @@
 pub const MUSIC_PLAYER_SYNC: &str = "music_player_sync";
 #[cfg(desktop)]
 pub const MUSIC_DIRECTORY_REQUEST: &str = "music_directory_request";
+pub const MUSIC_LIBRARY_CHANGED: &str = "music_library_changed";
 #[cfg(target_os = "linux")]
 pub const SIDEBAR_MOUSE_LEAVE: &str = "sidebar_mouse_leave";
 // pub const AUDIO_PERMISSION_READ_CHECK: &str = "audio_permission_read_check";
@@ -10,4 +11,6 @@ pub const SIDEBAR_MOUSE_LEAVE: &str = "sidebar_mouse_leave";
 // pub const MUSIC_QUEUE_REMOVE: &str = "music_queue_remove";
 #[cfg(target_os = "android")]
 pub const ANDROID_DIRECTORY_REQUEST: &str = "android_directory_request";
+pub const MUSIC_SCAN_PROGRESS: &str = "music_scan_progress";
+pub const MUSIC_SCAN_FINISHED: &str = "music_scan_finished";
 pub const LOG: &str = "log";
@@ -1,6 +1,11 @@
-use crate::coverart::{cache, queue, request, types::*};
-use std::fs;
-
+use crate::cache::cache::{Cache, CacheKind};
+use crate::coverart::lookup::CoverArtLookup;
+use crate::coverart::queue;
+use crate::coverart::types::*;
+use crate::music::image_cache::ImageCache;
+
+/// Cover art for an album or track. With a `path` in the query, the track's embedded and
+/// folder images are tried first, as configured by the cover art lookup.
 #[tauri::command]
 pub async fn cover_art_get(query: CoverArtQuery, size: Option<u32>) -> Option<Vec<u8>> {
     if query.album.is_none() && query.title.is_none() {
@@ -8,88 +13,39 @@ pub async fn cover_art_get(query: CoverArtQuery, size: Option<u32>) -> Option<Ve
         return None;
     }
 
-    let mut name = String::from("");
-    let mut folder_name = String::from("");
-    if query.album.is_some() {
-        name = format!("{} {}", query.artist, query.album.clone().unwrap());
-        folder_name = "album".to_string();
-    }
-    if query.title.is_some() {
-        name = format!("{} {}", query.artist, query.title.clone().unwrap());
-        folder_name = "music".to_string();
-    }
-
-    let file_path = format!("{}/{}/{}", cache::get_cache_directory(), folder_name, name);
-
-    // Check if there's an existing queue entry
-    if let Some(queue_item) = queue::get_queue(&name) {
-        match queue_item.status {
-            CoverArtRequestStatus::Loaded => {
-                // Already fetched, return from cache
-                if let Some(image) = get_image_bytes(&file_path) {
-                    return process_image(image, size);
-                }
-                crate::warn!("Cover art file not found: {}", name);
-                return None;
-            }
-            CoverArtRequestStatus::Failed => {
-                // Previous attempt failed
-                crate::warn!("Cover art fetch previously failed: {}", name);
-                return None;
-            }
-            CoverArtRequestStatus::Pending => {
-                // Another request is in progress, wait for it
-                let status = queue::wait_for_result(&name).await;
-                match status {
-                    CoverArtRequestStatus::Loaded => {
-                        if let Some(image) = get_image_bytes(&file_path) {
-                            return process_image(image, size);
-                        }
-                        crate::warn!("Cover art file not found: {}", name);
-                        return None;
-                    }
-                    _ => {
-                        crate::warn!("Cover art fetch failed: {}", name);
-                        return None;
-                    }
-                }
-            }
-        }
+    if let Some(path) = query.path.clone() {
+        let key = ImageCache::get_cache_key(Some(&query.artist), query.album.as_deref(), &path);
+        let image = ImageCache::get_image(&key, &path, Some(&query), size).await;
+        return (!image.is_empty()).then_some(image);
     }
 
-    // No queue entry - we're the first request
-    // Try cache first
-    if let Some(image) = get_image_bytes(&file_path) {
-        queue::set_status(name.clone(), CoverArtRequestStatus::Loaded);
-        return process_image(image, size);
+    match CoverArtLookup::load().find(None, Some(&query)).await {
+        Ok(bytes) => process_image(bytes, size),
+        Err(_) => None,
     }
+}
 
-    // Mark as pending before starting the fetch
-    queue::set_status(name.clone(), CoverArtRequestStatus::Pending);
-
-    // Request from API
-    let cover_art = request::request_cover_art(query).await;
-
-    match cover_art {
-        Ok(Some(bytes)) => {
-            queue::set_status(name.clone(), CoverArtRequestStatus::Loaded);
-            return process_image(bytes, size);
-        }
-        Ok(None) => {
-            queue::set_status(name.clone(), CoverArtRequestStatus::Failed);
-            crate::warn!("Failed to get cover art for: {}", name);
-            return None;
-        }
-        Err(e) => {
-            queue::set_status(name.clone(), CoverArtRequestStatus::Failed);
-            crate::warn!("Failed to get cover art for: {} - {}", name, e);
-            return None;
-        }
-    }
+#[tauri::command]
+pub fn cover_art_lookup_get() -> CoverArtLookup {
+    CoverArtLookup::load()
 }
 
-fn get_image_bytes(file_path: &str) -> Option<Vec<u8>> {
-    fs::read(file_path).ok()
+/// Save the cover art lookup. Cached covers are dropped, as they may have come from a source
+/// that is now skipped or ranked lower. Downloaded covers go too when the providers changed.
+#[tauri::command]
+pub fn cover_art_lookup_set(lookup: CoverArtLookup) -> Result<(), String> {
+    lookup.validate()?;
+    let previous = CoverArtLookup::load();
+    lookup.save();
+
+    if previous.providers != lookup.providers
+        || previous.lastfm_api_key() != lookup.lastfm_api_key()
+    {
+        Cache::clear(Some(CacheKind::OnlineCover))?;
+        queue::clear();
+    }
+    ImageCache::invalidate_cache();
+    Ok(())
 }
 
 fn process_image(bytes: Vec<u8>, size: Option<u32>) -> Option<Vec<u8>> {
@@ -0,0 +1,190 @@
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::api::{deezer::Deezer, itunes::ITunes, lastfm::LastFm, musicbrainz::MusicBrainz};
+use crate::coverart::provider::{CoverArtProviderKind, OnlineProvider};
+use crate::coverart::{request, types::CoverArtQuery};
+use crate::music::metadata::MusicMetadata;
+use crate::state::app_store;
+
+const STORE_KEY_COVER_ART_LOOKUP: &str = "cover-art-lookup";
+
+/// Extensions of folder images, preferred in this order when several share a name.
+const FOLDER_IMAGE_EXTENSIONS: [&str; 4] = ["jpg", "jpeg", "png", "bmp"];
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum CoverArtSource {
+    /// Art embedded in the audio file.
+    Embedded,
+    /// An image next to the audio file, such as `cover.jpg`.
+    Folder,
+    /// The online providers, in their configured order.
+    Online,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct CoverArtLookup {
+    /// Sources tried in order until one has an image.
+    pub sources: Vec<CoverArtSource>,
+    /// Folder image names without extension by priority, matched ignoring case.
+    pub folder_names: Vec<String>,
+    /// Online providers tried in order until one has a cover.
+    pub providers: Vec<CoverArtProviderKind>,
+    pub lastfm_api_key: Option<String>,
+}
+
+impl Default for CoverArtLookup {
+    fn default() -> Self {
+        Self {
+            sources: vec![
+                CoverArtSource::Embedded,
+                CoverArtSource::Folder,
+                CoverArtSource::Online,
+            ],
+            folder_names: ["cover", "folder", "front", "album", "albumart"]
+                .map(String::from)
+                .to_vec(),
+            // Other services only get the user's queries once added
+            providers: vec![CoverArtProviderKind::MusicBrainz],
+            lastfm_api_key: None,
+        }
+    }
+}
+
+impl CoverArtLookup {
+    pub fn load() -> Self {
+        app_store()
+            .get(STORE_KEY_COVER_ART_LOOKUP)
+            .and_then(|v| serde_json::from_value(v).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self) {
+        let store = app_store();
+        store.set(STORE_KEY_COVER_ART_LOOKUP, serde_json::json!(self));
+        let _ = store.save();
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        for (i, source) in self.sources.iter().enumerate() {
+            if self.sources[..i].contains(source) {
+                return Err(format!("Cover art source {:?} is listed twice", source));
+            }
+        }
+        for (i, provider) in self.providers.iter().enumerate() {
+            if self.providers[..i].contains(provider) {
+                return Err(format!("Cover art provider {:?} is listed twice", provider));
+            }
+        }
+        if self.providers.contains(&CoverArtProviderKind::LastFm) && self.lastfm_api_key().is_none()
+        {
+            return Err("Last.fm needs an API key".to_string());
+        }
+        for name in &self.folder_names {
+            if name.trim().is_empty() || name.contains(['/', '\\']) {
+                return Err(format!("Invalid folder image name: {:?}", name));
+            }
+        }
+        Ok(())
+    }
+
+    pub fn lastfm_api_key(&self) -> Option<&str> {
+        self.lastfm_api_key
+            .as_deref()
+            .map(str::trim)
+            .filter(|k| !k.is_empty())
+    }
+
+    /// The online providers to try in order. Last.fm is skipped without an API key.
+    pub fn online_providers(&self) -> Vec<OnlineProvider> {
+        self.providers
+            .iter()
+            .filter_map(|kind| match kind {
+                CoverArtProviderKind::MusicBrainz => {
+                    Some(OnlineProvider::MusicBrainz(MusicBrainz::default()))
+                }
+                CoverArtProviderKind::ITunes => Some(OnlineProvider::ITunes(ITunes::default())),
+                CoverArtProviderKind::Deezer => Some(OnlineProvider::Deezer(Deezer::default())),
+                CoverArtProviderKind::LastFm => self
+                    .lastfm_api_key()
+                    .map(|key| OnlineProvider::LastFm(LastFm::new(key))),
+            })
+            .collect()
+    }
+
+    /// Find cover art for the track at `path` and/or the online `query`. Sources needing
+    /// what wasn't given are skipped.
+    pub async fn find(
+        &self,
+        path: Option<&str>,
+        query: Option<&CoverArtQuery>,
+    ) -> Result<Vec<u8>, String> {
+        for source in &self.sources {
+            let image = match (source, path, query) {
+                (CoverArtSource::Embedded, Some(path), _) => Self::embedded(path).await,
+                (CoverArtSource::Folder, Some(path), _) => self.folder(Path::new(path)),
+                (CoverArtSource::Online, _, Some(query)) => request::get_cover_art(query).await,
+                _ => None,
+            };
+            if let Some(image) = image {
+                return Ok(image);
+            }
+        }
+
+        Err(format!(
+            "No cover art found for {}",
+            path.map(str::to_string)
+                .or_else(|| query.map(|q| q.name()))
+                .unwrap_or_default()
+        ))
+    }
+
+    async fn embedded(path: &str) -> Option<Vec<u8>> {
+        #[cfg(not(target_os = "android"))]
+        let image = MusicMetadata::get_image_from_path(path.to_string()).await;
+        #[cfg(target_os = "android")]
+        let image = MusicMetadata::get_image_from_path_android(path.to_string()).await;
+        image.ok()
+    }
+
+    fn folder(&self, path: &Path) -> Option<Vec<u8>> {
+        let image = self.find_folder_image(path)?;
+        match std::fs::read(&image) {
+            Ok(bytes) => Some(bytes),
+            Err(e) => {
+                crate::warn!("Failed to read folder image {}: {}", image.display(), e);
+                None
+            }
+        }
+    }
+
+    /// The image next to `path` whose name comes first in `folder_names`.
+    pub fn find_folder_image(&self, path: &Path) -> Option<PathBuf> {
+        let mut images: Vec<(usize, String, PathBuf)> = std::fs::read_dir(path.parent()?)
+            .ok()?
+            .flatten()
+            .filter_map(|entry| {
+                let path = entry.path();
+                let extension = path.extension()?.to_str()?.to_lowercase();
+                let rank = FOLDER_IMAGE_EXTENSIONS
+                    .iter()
+                    .position(|e| *e == extension)?;
+                let stem = path.file_stem()?.to_str()?.to_lowercase();
+                Some((rank, stem, path))
+            })
+            .filter(|(_, _, path)| path.is_file())
+            .collect();
+        images.sort_by_key(|(rank, _, _)| *rank);
+
+        self.folder_names.iter().find_map(|name| {
+            let name = name.trim().to_lowercase();
+            images
+                .iter()
+                .find(|(_, stem, _)| *stem == name)
+                .map(|(_, _, path)| path.clone())
+        })
+    }
+}
@@ -1,5 +1,6 @@
-pub mod cache;
 pub mod commands;
+pub mod lookup;
+pub mod provider;
 pub mod queue;
 pub mod request;
 pub mod types;
@@ -0,0 +1,133 @@
+use serde::{Deserialize, Serialize};
+
+use crate::api::{deezer::Deezer, itunes::ITunes, lastfm::LastFm, musicbrainz::MusicBrainz};
+use crate::coverart::types::CoverArtQuery;
+
+/// An online service covers are searched on.
+pub trait CoverArtProvider {
+    fn name(&self) -> &'static str;
+
+    /// URL of the full resolution cover for `query`, or `None` when the service has none.
+    async fn cover_art_url(&self, query: &CoverArtQuery) -> Result<Option<String>, String>;
+}
+
+/// Providers as listed in the cover art lookup.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum CoverArtProviderKind {
+    /// MusicBrainz and the Cover Art Archive.
+    MusicBrainz,
+    ITunes,
+    Deezer,
+    /// Needs an API key of the user.
+    LastFm,
+}
+
+/// A configured provider of the lookup chain.
+pub enum OnlineProvider {
+    MusicBrainz(MusicBrainz),
+    ITunes(ITunes),
+    Deezer(Deezer),
+    LastFm(LastFm),
+}
+
+impl CoverArtProvider for MusicBrainz {
+    fn name(&self) -> &'static str {
+        "MusicBrainz"
+    }
+
+    async fn cover_art_url(&self, query: &CoverArtQuery) -> Result<Option<String>, String> {
+        self.get_cover_art(query.clone()).await
+    }
+}
+
+impl CoverArtProvider for ITunes {
+    fn name(&self) -> &'static str {
+        "iTunes"
+    }
+
+    async fn cover_art_url(&self, query: &CoverArtQuery) -> Result<Option<String>, String> {
+        self.get_cover_art(query).await
+    }
+}
+
+impl CoverArtProvider for Deezer {
+    fn name(&self) -> &'static str {
+        "Deezer"
+    }
+
+    async fn cover_art_url(&self, query: &CoverArtQuery) -> Result<Option<String>, String> {
+        self.get_cover_art(query).await
+    }
+}
+
+impl CoverArtProvider for LastFm {
+    fn name(&self) -> &'static str {
+        "Last.fm"
+    }
+
+    async fn cover_art_url(&self, query: &CoverArtQuery) -> Result<Option<String>, String> {
+        self.get_cover_art(query).await
+    }
+}
+
+impl CoverArtProvider for OnlineProvider {
+    fn name(&self) -> &'static str {
+        match self {
+            OnlineProvider::MusicBrainz(p) => p.name(),
+            OnlineProvider::ITunes(p) => p.name(),
+            OnlineProvider::Deezer(p) => p.name(),
+            OnlineProvider::LastFm(p) => p.name(),
+        }
+    }
+
+    async fn cover_art_url(&self, query: &CoverArtQuery) -> Result<Option<String>, String> {
+        match self {
+            OnlineProvider::MusicBrainz(p) => p.cover_art_url(query).await,
+            OnlineProvider::ITunes(p) => p.cover_art_url(query).await,
+            OnlineProvider::Deezer(p) => p.cover_art_url(query).await,
+            OnlineProvider::LastFm(p) => p.cover_art_url(query).await,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::test_server::{lookup, query, Route};
+
+    /// The provider of `kind` for a server at `url`.
+    fn provider(kind: CoverArtProviderKind, url: String) -> OnlineProvider {
+        match kind {
+            CoverArtProviderKind::MusicBrainz => OnlineProvider::MusicBrainz(MusicBrainz {
+                base_url: format!("{}/ws/2", url),
+                cover_art_url: format!("{}/caa", url),
+            }),
+            CoverArtProviderKind::ITunes => OnlineProvider::ITunes(ITunes { base_url: url }),
+            CoverArtProviderKind::Deezer => OnlineProvider::Deezer(Deezer { base_url: url }),
+            CoverArtProviderKind::LastFm => OnlineProvider::LastFm(LastFm {
+                base_url: url,
+                api_key: "key".to_string(),
+            }),
+        }
+    }
+
+    #[tokio::test]
+    async fn error_status_is_an_error() {
+        for kind in [
+            CoverArtProviderKind::MusicBrainz,
+            CoverArtProviderKind::ITunes,
+            CoverArtProviderKind::Deezer,
+            CoverArtProviderKind::LastFm,
+        ] {
+            let routes = vec![Route::new("/", 503, "<html>Service Unavailable</html>")];
+            let (_, result) = lookup(
+                routes,
+                |url| provider(kind, url),
+                &query("Artist", "Album", None),
+            )
+            .await;
+            assert!(result.is_err(), "{:?} ignored the error status", kind);
+        }
+    }
+}
@@ -49,6 +49,14 @@ pub fn remove(name: &str) {
     COVER_ART_QUEUE.lock().unwrap().remove(name);
 }
 
+/// Forget finished requests, so they are read or fetched again
+pub fn clear() {
+    COVER_ART_QUEUE
+        .lock()
+        .unwrap()
+        .retain(|_, entry| entry.request.status == CoverArtRequestStatus::Pending);
+}
+
 pub async fn wait_for_result(name: &str) -> CoverArtRequestStatus {
     let receiver = {
         let queue = COVER_ART_QUEUE.lock().unwrap();
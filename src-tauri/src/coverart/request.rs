@@ -1,53 +1,259 @@
-use crate::api::musicbrainz::MusicBrainz;
-use crate::coverart::{cache, types::CoverArtQuery};
-use std::io::copy;
-use std::io::Cursor;
+use crate::api::headers;
+use crate::cache::cache::{Cache, CacheKind};
+use crate::coverart::lookup::CoverArtLookup;
+use crate::coverart::provider::{CoverArtProvider, OnlineProvider};
+use crate::coverart::types::CoverArtRequestStatus;
+use crate::coverart::{queue, types::CoverArtQuery};
 
-/// Request cover art from external API and cache it
+/// Cover art for `query` from the online cache, or else from the external API. Concurrent
+/// requests for the same cover wait for the first one.
+pub async fn get_cover_art(query: &CoverArtQuery) -> Option<Vec<u8>> {
+    if query.album.is_none() && query.title.is_none() {
+        crate::warn!("No album or title provided");
+        return None;
+    }
+
+    let name = query.name();
+    let cache_key = cache_key(query);
+
+    // Check if there's an existing queue entry
+    if let Some(queue_item) = queue::get_queue(&name) {
+        match queue_item.status {
+            CoverArtRequestStatus::Loaded => {
+                // Already fetched, return from cache
+                if let Some(image) = Cache::get(&cache_key) {
+                    return Some(image);
+                }
+                crate::warn!("Cover art cache entry not found: {}", name);
+                return None;
+            }
+            CoverArtRequestStatus::Failed => {
+                // Previous attempt failed
+                crate::warn!("Cover art fetch previously failed: {}", name);
+                return None;
+            }
+            CoverArtRequestStatus::Pending => {
+                // Another request is in progress, wait for it
+                let status = queue::wait_for_result(&name).await;
+                match status {
+                    CoverArtRequestStatus::Loaded => {
+                        if let Some(image) = Cache::get(&cache_key) {
+                            return Some(image);
+                        }
+                        crate::warn!("Cover art cache entry not found: {}", name);
+                        return None;
+                    }
+                    _ => {
+                        crate::warn!("Cover art fetch failed: {}", name);
+                        return None;
+                    }
+                }
+            }
+        }
+    }
+
+    // No queue entry - we're the first request
+    // Try cache first
+    if let Some(image) = Cache::get(&cache_key) {
+        queue::set_status(name.clone(), CoverArtRequestStatus::Loaded);
+        return Some(image);
+    }
+
+    // Mark as pending before starting the fetch
+    queue::set_status(name.clone(), CoverArtRequestStatus::Pending);
+
+    // Request from API
+    match request_cover_art(query.clone()).await {
+        Ok(Some(bytes)) => {
+            queue::set_status(name.clone(), CoverArtRequestStatus::Loaded);
+            Some(bytes)
+        }
+        Ok(None) => {
+            queue::set_status(name.clone(), CoverArtRequestStatus::Failed);
+            crate::warn!("Failed to get cover art for: {}", name);
+            None
+        }
+        Err(e) => {
+            queue::set_status(name.clone(), CoverArtRequestStatus::Failed);
+            crate::warn!("Failed to get cover art for: {} - {}", name, e);
+            None
+        }
+    }
+}
+
+/// Albums and tracks may share a name, so they are kept apart.
+fn cache_key(query: &CoverArtQuery) -> String {
+    format!("online:{}/{}", query.kind(), query.name())
+}
+
+/// Request cover art from the online providers in their configured order, and cache the
+/// first cover found.
 pub async fn request_cover_art(query: CoverArtQuery) -> Result<Option<Vec<u8>>, String> {
-    let url = MusicBrainz::get_cover_art(query.clone()).await?;
+    let providers = CoverArtLookup::load().online_providers();
+    let bytes = find_online(&providers, &query).await;
 
-    if url.is_none() {
-        return Ok(None);
+    if let Some(bytes) = &bytes {
+        Cache::put(CacheKind::OnlineCover, &cache_key(&query), bytes);
     }
+    Ok(bytes)
+}
 
-    let res = reqwest::get(url.unwrap())
+/// The first cover of `providers` for `query`. Providers that fail are skipped.
+pub async fn find_online(providers: &[OnlineProvider], query: &CoverArtQuery) -> Option<Vec<u8>> {
+    for provider in providers {
+        match download(provider, query).await {
+            Ok(Some(bytes)) => return Some(bytes),
+            Ok(None) => {}
+            Err(e) => crate::warn!(
+                "{} cover art request failed for {}: {}",
+                provider.name(),
+                query.name(),
+                e
+            ),
+        }
+    }
+    None
+}
+
+/// The full resolution cover of `provider` for `query`.
+async fn download(
+    provider: &impl CoverArtProvider,
+    query: &CoverArtQuery,
+) -> Result<Option<Vec<u8>>, String> {
+    let Some(url) = provider.cover_art_url(query).await? else {
+        return Ok(None);
+    };
+
+    let response = reqwest::Client::builder()
+        .default_headers(headers())
+        .build()
+        .map_err(|e| e.to_string())?
+        .get(url)
+        .send()
         .await
+        .and_then(|r| r.error_for_status())
         .map_err(|e| e.to_string())?;
+    let bytes = response.bytes().await.map_err(|e| e.to_string())?;
+    Ok(Some(bytes.to_vec()))
+}
 
-    let bytes = res.bytes().await.map_err(|e| e.to_string())?;
-    let cache_dir = cache::get_cache_directory();
-
-    let mut folder_name = String::from("");
-    let mut file_path = String::from("");
-    if query.album.is_some() {
-        folder_name = "album".to_string();
-        file_path = format!(
-            "{}/{}/{} {}",
-            cache_dir,
-            folder_name,
-            query.artist,
-            query.album.unwrap()
-        );
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::test_server::{query, Route, TestServer};
+    use crate::api::{deezer::Deezer, itunes::ITunes, musicbrainz::MusicBrainz};
+
+    const IMAGE: &[u8] = b"\x89PNG\r\n\x1a\nstand-in";
+
+    fn providers(server: &TestServer) -> Vec<OnlineProvider> {
+        vec![
+            OnlineProvider::Deezer(Deezer {
+                base_url: format!("{}/deezer", server.url),
+            }),
+            OnlineProvider::ITunes(ITunes {
+                base_url: format!("{}/itunes", server.url),
+            }),
+            OnlineProvider::MusicBrainz(MusicBrainz {
+                base_url: format!("{}/ws/2", server.url),
+                cover_art_url: format!("{}/caa", server.url),
+            }),
+        ]
     }
-    if query.title.is_some() {
-        folder_name = "music".to_string();
-        file_path = format!(
-            "{}/{}/{} {}",
-            cache_dir,
-            folder_name,
-            query.artist,
-            query.title.unwrap()
+
+    #[tokio::test]
+    async fn falls_through_to_the_next_provider() {
+        // Deezer fails, iTunes has nothing, MusicBrainz has the cover
+        let server = TestServer::start(vec![
+            Route::new("/deezer/search/album", 500, ""),
+            Route::new(
+                "/itunes/search",
+                200,
+                include_str!("../api/fixtures/itunes_empty.json"),
+            ),
+            Route::new(
+                "/ws/2/release-group",
+                200,
+                include_str!("../api/fixtures/musicbrainz_release_group.json"),
+            ),
+            Route::new(
+                "/caa/release-group",
+                200,
+                r#"{"images":[{"front":true,"image":"{{server}}/image.png"}]}"#,
+            ),
+            Route::new("/image.png", 200, IMAGE),
+        ]);
+
+        let image = find_online(
+            &providers(&server),
+            &query("Radiohead", "OK Computer", None),
+        )
+        .await;
+        assert_eq!(image.as_deref(), Some(IMAGE));
+
+        let requests = server.requests();
+        let paths: Vec<&str> = requests
+            .iter()
+            .map(|r| r.split('?').next().unwrap_or_default())
+            .collect();
+        assert_eq!(
+            paths,
+            [
+                "/deezer/search/album",
+                "/itunes/search",
+                "/ws/2/release-group",
+                "/caa/release-group/b1392450-e666-3926-a536-22c65f834433",
+                "/image.png",
+            ]
         );
     }
 
-    std::fs::create_dir_all(format!("{}/{}", cache_dir.clone(), folder_name))
-        .map_err(|e| e.to_string())?;
+    #[tokio::test]
+    async fn stops_at_the_first_cover() {
+        let server = TestServer::start(vec![
+            Route::new(
+                "/deezer/search/album",
+                200,
+                r#"{"data":[{"cover_xl":"{{server}}/image.png"}],"total":1}"#,
+            ),
+            Route::new("/image.png", 200, IMAGE),
+        ]);
 
-    let mut file = std::fs::File::create(file_path).map_err(|e| e.to_string())?;
-    let mut content = Cursor::new(bytes.clone());
-    copy(&mut content, &mut file).map_err(|e| e.to_string())?;
-    file.sync_all().map_err(|e| e.to_string())?;
+        let image = find_online(
+            &providers(&server),
+            &query("Radiohead", "OK Computer", None),
+        )
+        .await;
+        assert_eq!(image.as_deref(), Some(IMAGE));
+        assert_eq!(server.requests().len(), 2);
+    }
 
-    Ok(Some(bytes.to_vec()))
+    #[tokio::test]
+    async fn failed_download_falls_through() {
+        // Deezer points to an image that's gone, the others have nothing
+        let server = TestServer::start(vec![
+            Route::new(
+                "/deezer/search/album",
+                200,
+                r#"{"data":[{"cover_xl":"{{server}}/gone.png"}],"total":1}"#,
+            ),
+            Route::new(
+                "/itunes/search",
+                200,
+                include_str!("../api/fixtures/itunes_empty.json"),
+            ),
+            Route::new(
+                "/ws/2/release-group",
+                200,
+                include_str!("../api/fixtures/musicbrainz_empty.json"),
+            ),
+        ]);
+
+        let image = find_online(
+            &providers(&server),
+            &query("Radiohead", "OK Computer", None),
+        )
+        .await;
+        assert_eq!(image, None);
+        assert_eq!(server.requests().len(), 4);
+    }
 }
@@ -28,4 +28,50 @@ pub struct CoverArtQuery {
     pub artist: String,
     pub album: Option<String>,
     pub title: Option<String>,
+    /// A track to look for embedded and folder images before going online.
+    #[serde(default)]
+    pub path: Option<String>,
+}
+
+impl CoverArtQuery {
+    /// Online query for a track's cover: by album, or by title for tracks without one.
+    pub fn for_track(
+        artist: Option<&str>,
+        album: Option<&str>,
+        title: Option<&str>,
+    ) -> Option<Self> {
+        match (artist, album, title) {
+            (Some(artist), Some(album), _) => Some(Self {
+                artist: artist.to_string(),
+                album: Some(album.to_string()),
+                title: None,
+                path: None,
+            }),
+            (Some(artist), None, Some(title)) => Some(Self {
+                artist: artist.to_string(),
+                album: None,
+                title: Some(title.to_string()),
+                path: None,
+            }),
+            _ => None,
+        }
+    }
+
+    /// Name of the online request, used for its queue entry and cache file.
+    pub fn name(&self) -> String {
+        match (&self.title, &self.album) {
+            (Some(title), _) => format!("{} {}", self.artist, title),
+            (None, Some(album)) => format!("{} {}", self.artist, album),
+            (None, None) => self.artist.clone(),
+        }
+    }
+
+    /// Kind of the online request, as albums and tracks may share a name.
+    pub fn kind(&self) -> &'static str {
+        if self.title.is_some() {
+            "music"
+        } else {
+            "album"
+        }
+    }
 }
@@ -76,5 +76,285 @@ pub const MIGRATIONS_SLICE: &[M<'_>] = &[
     );
     ",
     ),
+    M::up(
+        "
+    ALTER TABLE musics ADD COLUMN replay_gain_track_gain REAL;
+    ALTER TABLE musics ADD COLUMN replay_gain_track_peak REAL;
+    ALTER TABLE musics ADD COLUMN replay_gain_album_gain REAL;
+    ALTER TABLE musics ADD COLUMN replay_gain_album_peak REAL;
+    ALTER TABLE musics ADD COLUMN loudness REAL;
+    ALTER TABLE musics ADD COLUMN loudness_analyzed INTEGER NOT NULL DEFAULT 0;
+
+    -- Force a metadata re-read so existing rows pick up their ReplayGain tags
+    UPDATE musics SET modified_at = '';
+    ",
+    ),
+    M::up(
+        "
+    CREATE TABLE player_queue (
+        position INTEGER PRIMARY KEY,
+        path TEXT NOT NULL
+    );
+
+    CREATE TABLE player_session (
+        id INTEGER PRIMARY KEY CHECK (id = 1),
+        current_index INTEGER,
+        position REAL NOT NULL DEFAULT 0,
+        repeat_mode TEXT,
+        shuffle_order TEXT
+    );
+    ",
+    ),
+    M::up(
+        "
+    CREATE TABLE plays (
+        id INTEGER PRIMARY KEY,
+        path TEXT NOT NULL,
+        played_at INTEGER NOT NULL
+    );
+
+    CREATE INDEX plays_path ON plays(path);
+    CREATE INDEX plays_played_at ON plays(played_at);
+    ",
+    ),
+    M::up(
+        "
+    ALTER TABLE playlists ADD COLUMN rules TEXT;
+    ",
+    ),
+    M::up(
+        "
+    CREATE TABLE artists (
+        id INTEGER PRIMARY KEY,
+        name TEXT NOT NULL UNIQUE COLLATE NOCASE
+    );
+
+    CREATE TABLE albums (
+        id INTEGER PRIMARY KEY,
+        name TEXT NOT NULL COLLATE NOCASE,
+        artist TEXT NOT NULL DEFAULT '' COLLATE NOCASE,
+        UNIQUE (name, artist)
+    );
+
+    CREATE TABLE genres (
+        id INTEGER PRIMARY KEY,
+        name TEXT NOT NULL UNIQUE COLLATE NOCASE
+    );
+
+    CREATE TABLE music_artists (
+        music_id INTEGER NOT NULL,
+        artist_id INTEGER NOT NULL,
+        position INTEGER NOT NULL,
+        PRIMARY KEY (music_id, artist_id)
+    );
+    CREATE INDEX music_artists_artist_id ON music_artists(artist_id);
+
+    CREATE TABLE music_genres (
+        music_id INTEGER NOT NULL,
+        genre_id INTEGER NOT NULL,
+        position INTEGER NOT NULL,
+        PRIMARY KEY (music_id, genre_id)
+    );
+    CREATE INDEX music_genres_genre_id ON music_genres(genre_id);
+
+    ALTER TABLE musics ADD COLUMN album_id INTEGER REFERENCES albums(id);
+    CREATE INDEX musics_album_id ON musics(album_id);
+
+    -- Backfill from the existing tag columns, splitting artists and genres on ';'
+    CREATE TEMP TABLE tag_values AS
+    WITH RECURSIVE split(music_id, kind, value, rest, position) AS (
+        SELECT id, 'artist', '', artist || ';', -1 FROM musics WHERE artist IS NOT NULL
+        UNION ALL
+        SELECT id, 'genre', '', genre || ';', -1 FROM musics WHERE genre IS NOT NULL
+        UNION ALL
+        SELECT music_id, kind, trim(substr(rest, 1, instr(rest, ';') - 1)),
+            substr(rest, instr(rest, ';') + 1), position + 1
+        FROM split WHERE rest <> ''
+    )
+    SELECT music_id, kind, value, position FROM split WHERE value <> '';
+
+    INSERT OR IGNORE INTO artists (name)
+        SELECT value FROM tag_values WHERE kind = 'artist' ORDER BY music_id, position;
+    INSERT OR IGNORE INTO music_artists (music_id, artist_id, position)
+        SELECT music_id, artists.id, position FROM tag_values
+        JOIN artists ON artists.name = tag_values.value
+        WHERE kind = 'artist' ORDER BY music_id, position;
+
+    INSERT OR IGNORE INTO genres (name)
+        SELECT value FROM tag_values WHERE kind = 'genre' ORDER BY music_id, position;
+    INSERT OR IGNORE INTO music_genres (music_id, genre_id, position)
+        SELECT music_id, genres.id, position FROM tag_values
+        JOIN genres ON genres.name = tag_values.value
+        WHERE kind = 'genre' ORDER BY music_id, position;
+
+    -- Without an album artist, the album goes under the track's primary artist
+    CREATE TEMP TABLE album_keys AS
+    SELECT id AS music_id, trim(album) AS name, COALESCE(
+        NULLIF(trim(IFNULL(album_artist, '')), ''),
+        (SELECT value FROM tag_values
+            WHERE tag_values.music_id = musics.id AND kind = 'artist'
+            ORDER BY position LIMIT 1),
+        ''
+    ) AS artist
+    FROM musics WHERE trim(IFNULL(album, '')) <> '';
+
+    DROP TABLE tag_values;
+
+    INSERT OR IGNORE INTO albums (name, artist)
+        SELECT name, artist FROM album_keys ORDER BY music_id;
+    UPDATE musics SET album_id = (
+        SELECT albums.id FROM album_keys
+        JOIN albums ON albums.name = album_keys.name AND albums.artist = album_keys.artist
+        WHERE album_keys.music_id = musics.id
+    );
+
+    DROP TABLE album_keys;
+
+    -- Foreign keys aren't enforced, so links and entries left without tracks are removed here
+    CREATE TRIGGER musics_delete_links AFTER DELETE ON musics
+    BEGIN
+        DELETE FROM music_artists WHERE music_id = OLD.id;
+        DELETE FROM music_genres WHERE music_id = OLD.id;
+        DELETE FROM albums WHERE id = OLD.album_id
+            AND NOT EXISTS (SELECT 1 FROM musics WHERE album_id = OLD.album_id);
+    END;
+
+    CREATE TRIGGER musics_album_id_update AFTER UPDATE OF album_id ON musics
+    WHEN OLD.album_id IS NOT NULL
+    BEGIN
+        DELETE FROM albums WHERE id = OLD.album_id
+            AND NOT EXISTS (SELECT 1 FROM musics WHERE album_id = OLD.album_id);
+    END;
+
+    CREATE TRIGGER music_artists_delete AFTER DELETE ON music_artists
+    BEGIN
+        DELETE FROM artists WHERE id = OLD.artist_id
+            AND NOT EXISTS (SELECT 1 FROM music_artists WHERE artist_id = OLD.artist_id);
+    END;
+
+    CREATE TRIGGER music_genres_delete AFTER DELETE ON music_genres
+    BEGIN
+        DELETE FROM genres WHERE id = OLD.genre_id
+            AND NOT EXISTS (SELECT 1 FROM music_genres WHERE genre_id = OLD.genre_id);
+    END;
+    ",
+    ),
+    M::up(
+        "
+    CREATE INDEX musics_path ON musics(path);
+    CREATE INDEX musics_title ON musics(title COLLATE NOCASE);
+    CREATE INDEX musics_artist ON musics(artist COLLATE NOCASE);
+    CREATE INDEX musics_album ON musics(album COLLATE NOCASE);
+    CREATE INDEX musics_album_artist ON musics(album_artist COLLATE NOCASE);
+    CREATE INDEX musics_year ON musics(CAST(substr(date, 1, 4) AS INTEGER));
+    ",
+    ),
+    M::up(
+        "
+    -- The file name is what follows the last '/' or '\\' of the path
+    CREATE VIRTUAL TABLE musics_fts USING fts5(
+        title, artist, album, album_artist, genre, filename,
+        tokenize = 'unicode61 remove_diacritics 2',
+        prefix = '1 2 3'
+    );
+
+    INSERT INTO musics_fts (rowid, title, artist, album, album_artist, genre, filename)
+        SELECT id, title, artist, album, album_artist, genre,
+            substr(path, length(rtrim(path, replace(replace(path, '/', ''), '\\', ''))) + 1)
+        FROM musics;
+
+    CREATE TRIGGER musics_fts_insert AFTER INSERT ON musics
+    BEGIN
+        INSERT INTO musics_fts (rowid, title, artist, album, album_artist, genre, filename)
+        VALUES (
+            NEW.id, NEW.title, NEW.artist, NEW.album, NEW.album_artist, NEW.genre,
+            substr(NEW.path, length(rtrim(NEW.path, replace(replace(NEW.path, '/', ''), '\\', ''))) + 1)
+        );
+    END;
+
+    CREATE TRIGGER musics_fts_delete AFTER DELETE ON musics
+    BEGIN
+        DELETE FROM musics_fts WHERE rowid = OLD.id;
+    END;
+
+    CREATE TRIGGER musics_fts_update
+    AFTER UPDATE OF path, title, artist, album, album_artist, genre ON musics
+    BEGIN
+        DELETE FROM musics_fts WHERE rowid = OLD.id;
+        INSERT INTO musics_fts (rowid, title, artist, album, album_artist, genre, filename)
+        VALUES (
+            NEW.id, NEW.title, NEW.artist, NEW.album, NEW.album_artist, NEW.genre,
+            substr(NEW.path, length(rtrim(NEW.path, replace(replace(NEW.path, '/', ''), '\\', ''))) + 1)
+        );
+    END;
+    ",
+    ),
+    M::up(
+        "
+    ALTER TABLE musics ADD COLUMN track_total INTEGER;
+    ALTER TABLE musics ADD COLUMN disc_number INTEGER;
+    ALTER TABLE musics ADD COLUMN disc_total INTEGER;
+    ALTER TABLE musics ADD COLUMN composer TEXT;
+    ALTER TABLE musics ADD COLUMN conductor TEXT;
+    ALTER TABLE musics ADD COLUMN label TEXT;
+    ALTER TABLE musics ADD COLUMN isrc TEXT;
+    ALTER TABLE musics ADD COLUMN musicbrainz_recording_id TEXT;
+    ALTER TABLE musics ADD COLUMN musicbrainz_album_id TEXT;
+    ALTER TABLE musics ADD COLUMN musicbrainz_artist_id TEXT;
+    ALTER TABLE musics ADD COLUMN musicbrainz_release_group_id TEXT;
+    ALTER TABLE musics ADD COLUMN codec TEXT;
+    ALTER TABLE musics ADD COLUMN bitrate INTEGER;
+    ALTER TABLE musics ADD COLUMN channels INTEGER;
+    ALTER TABLE musics ADD COLUMN file_size INTEGER;
+
+    -- Force a metadata re-read so existing rows pick up the new fields
+    UPDATE musics SET modified_at = '';
+    ",
+    ),
+    M::up(
+        "
+    CREATE TABLE music_roots (
+        id INTEGER PRIMARY KEY,
+        path TEXT NOT NULL UNIQUE,
+        label TEXT,
+        enabled INTEGER NOT NULL DEFAULT 1,
+        last_scanned_at INTEGER,
+        created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP
+    );
+    ",
+    ),
+    M::up(
+        "
+    CREATE TABLE cache_entries (
+        name TEXT PRIMARY KEY,
+        hash TEXT NOT NULL,
+        kind TEXT NOT NULL,
+        size INTEGER NOT NULL,
+        accessed_at INTEGER NOT NULL
+    );
+    CREATE INDEX cache_entries_hash ON cache_entries (hash);
+    CREATE INDEX cache_entries_accessed_at ON cache_entries (accessed_at);
+
+    CREATE TABLE cache_keys (
+        key TEXT PRIMARY KEY,
+        hash TEXT NOT NULL
+    );
+    CREATE INDEX cache_keys_hash ON cache_keys (hash);
+
+    -- Keys are dropped with the last file of their content
+    CREATE TRIGGER cache_entries_delete
+    AFTER DELETE ON cache_entries
+    WHEN NOT EXISTS (SELECT 1 FROM cache_entries WHERE hash = OLD.hash)
+    BEGIN
+        DELETE FROM cache_keys WHERE hash = OLD.hash;
+    END;
+    ",
+    ),
+    M::up(
+        "
+    -- Cover cache key, set once the album's thumbnails were generated
+    ALTER TABLE musics ADD COLUMN image_key TEXT;
+    ",
+    ),
 ];
 pub const DATABASE_MIGRATIONS: Migrations<'_> = Migrations::from_slice(MIGRATIONS_SLICE);
@@ -57,6 +57,14 @@ pub fn handle_app_events(app_handle: &AppHandle, event: RunEvent) {
             #[cfg(not(target_os = "linux"))]
             crate::wgpu_renderer::handle_wgpu_resize(app_handle, size.width, size.height);
         }
+        RunEvent::Exit => {
+            // Startup may have failed before the state was managed
+            if let Some(state) = app_handle.try_state::<crate::state::AppState>() {
+                state.music_player.save_session();
+                crate::music::session::flush();
+            }
+            crate::cache::cache::Cache::flush_reads();
+        }
         RunEvent::Resumed => {
             #[cfg(not(target_os = "linux"))]
             crate::wgpu_renderer::resume_wgpu(app_handle);
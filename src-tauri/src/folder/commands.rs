@@ -1,10 +1,13 @@
 use crate::database::database::GLOBAL_DATABASE;
-use crate::folder::types::MUSIC_PATH_SEPARATOR;
+use crate::folder::roots::{MusicRoot, RootRemoval};
+use crate::folder::rules::ScanRules;
+use crate::folder::types::ScanSummary;
+#[cfg(desktop)]
+use crate::folder::watcher;
 use crate::folder::{database, scanner, types::FolderItem};
-use crate::music::commands::directory::MUSIC_STORE_PATH_NAME;
 use crate::music::metadata::MusicMetadata;
 use crate::platform::is_ios;
-use crate::state::app_store;
+use crate::playlist::playlist::Playlist;
 
 #[cfg(target_os = "android")]
 use crate::commands::mobile::audio_permission_read_check;
@@ -21,52 +24,133 @@ pub fn folder_first_music_path_get(path: String) -> Option<String> {
     database::get_folder_first_music_path(conn, path.as_str())
 }
 
+/// All tracks in the library. Directories are only walked on the first call, when the
+/// configured directories change or when `force` is set, since the watcher keeps the
+/// database current in between.
 #[tauri::command]
-pub async fn music_all_get() -> Option<Vec<MusicMetadata>> {
+pub async fn music_all_get(force: Option<bool>) -> Option<Vec<MusicMetadata>> {
     #[cfg(target_os = "android")]
     if !audio_permission_read_check() {
         return None;
     }
 
-    let mut search_dirs: Vec<String> = vec![];
-
-    // Get store music paths
-    let dir = app_store().get(MUSIC_STORE_PATH_NAME)?.to_string();
-    let dir_paths = dir.split(MUSIC_PATH_SEPARATOR);
-
-    for d in dir_paths {
-        let trimmed = d.trim().trim_matches('"');
-        if !trimmed.is_empty() {
-            search_dirs.push(trimmed.to_string());
-        }
-    }
+    let mut search_dirs = MusicRoot::enabled_paths()?;
 
     if is_ios() {
         search_dirs.push(scanner::get_home_dir())
     }
 
+    #[cfg(desktop)]
+    if !force.unwrap_or(false) && watcher::is_watching(&search_dirs) {
+        return Some(database::get_all_music_from_db());
+    }
+    #[cfg(not(desktop))]
+    let _ = force;
+
+    scanner::reset_scan_cancel();
+
     let now = std::time::Instant::now();
     // Scan directories for music files
-    let paths = scanner::scan_directories(search_dirs);
+    let paths = scanner::scan_directories(search_dirs.clone());
     crate::info!("Scan directories took {}s", now.elapsed().as_secs_f64());
 
     // database::windows_fix_music_paths_older_version(conn);
 
     let now = std::time::Instant::now();
     // Process files and update database
-    scanner::process_supported_files(&paths).await;
+    let summary = scanner::process_supported_files(&paths, true).await;
     crate::info!("Process files took {}s", now.elapsed().as_secs_f64());
 
     let now = std::time::Instant::now();
-    database::delete_non_existing_paths(paths);
+    let deleted = database::delete_non_existing_paths(paths);
     crate::info!(
         "Delete non existing paths took {}s",
         now.elapsed().as_secs_f64()
     );
 
+    if summary.updated || deleted {
+        if let Err(e) = Playlist::refresh_smart() {
+            crate::error!("Failed to refresh smart playlists: {}", e);
+        }
+    }
+
+    if !summary.cancelled {
+        MusicRoot::mark_scanned(&search_dirs);
+    }
+
+    #[cfg(desktop)]
+    {
+        scanner::spawn_loudness_analysis();
+        // Files skipped by a cancelled scan are only picked up by walking again
+        if !summary.cancelled {
+            watcher::watch(search_dirs);
+        }
+    }
+
+    scanner::finish_scan(summary);
+
     let now = std::time::Instant::now();
     let musics = database::get_all_music_from_db();
     crate::info!("Get all music took {}s", now.elapsed().as_secs_f64());
 
     Some(musics)
 }
+
+#[tauri::command]
+pub fn music_scan_cancel() {
+    scanner::cancel_scan();
+}
+
+/// Summary of the last library scan, including files whose metadata couldn't be read.
+#[tauri::command]
+pub fn music_scan_summary_get() -> Option<ScanSummary> {
+    scanner::last_scan_summary()
+}
+
+#[tauri::command]
+pub fn music_scan_rules_get() -> ScanRules {
+    ScanRules::load()
+}
+
+/// Save the scan rules. They apply from the next full scan, which the next `music_all_get`
+/// call starts.
+#[tauri::command]
+pub fn music_scan_rules_set(rules: ScanRules) -> Result<(), String> {
+    rules.validate()?;
+    // Unchanged files aren't read again, so tracks that fall below a raised minimum go now
+    if rules.min_duration > ScanRules::load().min_duration {
+        database::delete_short_tracks(rules.min_duration);
+    }
+    rules.save();
+
+    #[cfg(desktop)]
+    watcher::stop();
+    Ok(())
+}
+
+#[tauri::command]
+pub fn music_roots_get() -> Result<Vec<MusicRoot>, String> {
+    MusicRoot::get_all().map_err(|e| e.to_string())
+}
+
+/// Add a library directory. Its tracks are scanned by the next `music_all_get` call.
+#[tauri::command]
+pub fn music_root_add(path: String, label: Option<String>) -> Result<MusicRoot, String> {
+    MusicRoot::add(&path, label)
+}
+
+/// Remove a library directory and its tracks, reporting the playlists that referenced them.
+#[tauri::command]
+pub fn music_root_remove(id: i64) -> Result<RootRemoval, String> {
+    MusicRoot::remove(id)
+}
+
+#[tauri::command]
+pub fn music_root_enabled_set(id: i64, enabled: bool) -> Result<(), String> {
+    MusicRoot::set_enabled(id, enabled)
+}
+
+#[tauri::command]
+pub fn music_root_label_set(id: i64, label: Option<String>) -> Result<(), String> {
+    MusicRoot::set_label(id, label)
+}
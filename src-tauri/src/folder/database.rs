@@ -1,3 +1,4 @@
+use crate::folder::utils::path_prefix_range;
 use crate::{database::database::GLOBAL_DATABASE, music::metadata::MusicMetadata};
 use rusqlite::{params, Connection};
 use std::path::{Path, PathBuf};
@@ -14,62 +15,114 @@ pub fn get_folder_first_music_path(conn: &mut Connection, path: &str) -> Option<
     None
 }
 
+pub const MUSIC_COLUMNS: &str = "
+    path, duration, title, artist, album, album_artist, track_number,
+    genre, bits_per_sample, sample_rate, date, id, replay_gain_track_gain,
+    replay_gain_track_peak, replay_gain_album_gain, replay_gain_album_peak, loudness,
+    track_total, disc_number, disc_total, composer, conductor, label, isrc,
+    musicbrainz_recording_id, musicbrainz_album_id, musicbrainz_artist_id,
+    musicbrainz_release_group_id, codec, bitrate, channels, file_size
+";
+/// Number of `MUSIC_COLUMNS`, the index of the first column selected after them.
+pub const MUSIC_COLUMN_COUNT: usize = 32;
+
 pub fn get_all_music_from_db() -> Vec<MusicMetadata> {
-    let query = "
-        SELECT path, duration, title, artist, album, album_artist, track_number,
-        genre, bits_per_sample, sample_rate, date, id FROM musics
-    "
-    .to_string();
+    let query = format!("SELECT {} FROM musics", MUSIC_COLUMNS);
 
     let conn_guard = GLOBAL_DATABASE.lock().ok().unwrap();
     let conn = conn_guard.as_ref().unwrap();
     let mut stmt = conn.prepare(&query).ok().unwrap();
 
-    stmt.query_map(params![], |row| {
-        let path: String = row.get(0)?;
-        let filename = Path::new(&path)
-            .file_name()
-            .map(|s| s.to_string_lossy().to_string());
-
-        Ok(MusicMetadata {
-            id: row.get(11)?,
-            path: path.clone(),
-            duration: row.get::<_, Option<i64>>(1)?.map(|v| v as u128),
-            title: row.get(2)?,
-            artist: row.get::<_, Option<String>>(3)?.map(|v| {
-                let separator = MusicMetadata::separator();
-                let artist_separator = MusicMetadata::artist_separator();
-                let mut artists = Vec::new();
-
-                for s in v.split(artist_separator) {
-                    let s = s.trim();
-                    if !s.is_empty() && !artists.contains(&s) {
-                        artists.push(s);
-                    }
+    stmt.query_map(params![], music_from_row)
+        .ok()
+        .unwrap()
+        .filter_map(|r| r.ok())
+        .collect()
+}
+
+pub fn get_music_from_db(path: &str) -> Option<MusicMetadata> {
+    let query = format!("SELECT {} FROM musics WHERE path = ?1", MUSIC_COLUMNS);
+
+    let conn_guard = GLOBAL_DATABASE.lock().ok()?;
+    let conn = conn_guard.as_ref()?;
+    conn.query_row(&query, params![path], music_from_row).ok()
+}
+
+/// Cover cache key of the track, set once its thumbnails were generated.
+pub fn get_music_image_key(path: &str) -> Option<String> {
+    let conn_guard = GLOBAL_DATABASE.lock().ok()?;
+    let conn = conn_guard.as_ref()?;
+    conn.query_row(
+        "SELECT image_key FROM musics WHERE path = ?1",
+        params![path],
+        |row| row.get(0),
+    )
+    .ok()
+    .flatten()
+}
+
+/// Map a row selected with `MUSIC_COLUMNS`.
+pub fn music_from_row(row: &rusqlite::Row) -> rusqlite::Result<MusicMetadata> {
+    let path: String = row.get(0)?;
+    let filename = Path::new(&path)
+        .file_name()
+        .map(|s| s.to_string_lossy().to_string());
+
+    Ok(MusicMetadata {
+        id: row.get(11)?,
+        path: path.clone(),
+        duration: row.get::<_, Option<i64>>(1)?.map(|v| v as u128),
+        title: row.get(2)?,
+        artist: row.get::<_, Option<String>>(3)?.map(|v| {
+            let separator = MusicMetadata::separator();
+            let artist_separator = MusicMetadata::artist_separator();
+            let mut artists = Vec::new();
+
+            for s in v.split(artist_separator) {
+                let s = s.trim();
+                if !s.is_empty() && !artists.contains(&s) {
+                    artists.push(s);
                 }
+            }
 
-                artists.join(separator)
-            }),
-            album: row.get(4)?,
-            album_artist: row.get(5)?,
-            track_number: row.get(6)?,
-            genre: row.get(7)?,
-            bits_per_sample: row.get::<_, Option<i64>>(8)?.map(|v| v as u32),
-            sample_rate: row.get::<_, Option<i64>>(9)?.map(|v| v as u32),
-            date: row.get(10)?,
-
-            filename,
-            image: None,
-            extra_tags: None,
-        })
+            artists.join(separator)
+        }),
+        album: row.get(4)?,
+        album_artist: row.get(5)?,
+        track_number: row.get(6)?,
+        genre: row.get(7)?,
+        bits_per_sample: row.get::<_, Option<i64>>(8)?.map(|v| v as u32),
+        sample_rate: row.get::<_, Option<i64>>(9)?.map(|v| v as u32),
+        date: row.get(10)?,
+        replay_gain_track_gain: row.get(12)?,
+        replay_gain_track_peak: row.get(13)?,
+        replay_gain_album_gain: row.get(14)?,
+        replay_gain_album_peak: row.get(15)?,
+        loudness: row.get(16)?,
+        track_total: row.get::<_, Option<i64>>(17)?.map(|v| v as u32),
+        disc_number: row.get::<_, Option<i64>>(18)?.map(|v| v as u32),
+        disc_total: row.get::<_, Option<i64>>(19)?.map(|v| v as u32),
+        composer: row.get(20)?,
+        conductor: row.get(21)?,
+        label: row.get(22)?,
+        isrc: row.get(23)?,
+        musicbrainz_recording_id: row.get(24)?,
+        musicbrainz_album_id: row.get(25)?,
+        musicbrainz_artist_id: row.get(26)?,
+        musicbrainz_release_group_id: row.get(27)?,
+        codec: row.get(28)?,
+        bitrate: row.get::<_, Option<i64>>(29)?.map(|v| v as u32),
+        channels: row.get::<_, Option<i64>>(30)?.map(|v| v as u32),
+        file_size: row.get::<_, Option<i64>>(31)?.map(|v| v as u64),
+
+        filename,
+        image: None,
+        extra_tags: None,
     })
-    .ok()
-    .unwrap()
-    .filter_map(|r| r.ok())
-    .collect()
 }
 
-pub fn delete_non_existing_paths(musics: Vec<PathBuf>) {
+/// Returns whether any track was removed.
+pub fn delete_non_existing_paths(musics: Vec<PathBuf>) -> bool {
     let mut conn_guard = GLOBAL_DATABASE.lock().ok().unwrap();
     let conn = conn_guard.as_mut().unwrap();
     let tx = conn.transaction().unwrap();
@@ -94,11 +147,66 @@ pub fn delete_non_existing_paths(musics: Vec<PathBuf>) {
     }
 
     // Delete all paths in musics table that are not in the temp table
-    tx.execute(
-        "DELETE FROM musics WHERE path NOT IN (SELECT path FROM temp_existing_paths)",
-        [],
-    )
-    .unwrap();
+    let deleted = tx
+        .execute(
+            "DELETE FROM musics WHERE path NOT IN (SELECT path FROM temp_existing_paths)",
+            [],
+        )
+        .unwrap();
 
     tx.commit().unwrap();
+    deleted > 0
+}
+
+/// Remove tracks at `paths` or inside them when they were directories.
+/// Returns whether any track was removed.
+pub fn delete_paths(paths: &[PathBuf]) -> bool {
+    let mut conn_guard = GLOBAL_DATABASE.lock().unwrap();
+    let conn = conn_guard.as_mut().unwrap();
+
+    let result = (|| -> rusqlite::Result<usize> {
+        let tx = conn.transaction()?;
+        let mut deleted = 0;
+        {
+            let mut stmt =
+                tx.prepare("DELETE FROM musics WHERE path = ?1 OR (path >= ?2 AND path < ?3)")?;
+            for path in paths {
+                let path = path.display().to_string();
+                // A removed directory takes the tracks inside it along
+                let (start, end) = path_prefix_range(&path);
+                deleted += stmt.execute(params![path, start, end])?;
+            }
+        }
+        tx.commit()?;
+        Ok(deleted)
+    })();
+
+    match result {
+        Ok(deleted) => deleted > 0,
+        Err(e) => {
+            crate::error!("Failed to delete removed paths: {}", e);
+            false
+        }
+    }
+}
+
+/// Remove tracks shorter than `min_duration` seconds, returning whether any track was removed.
+pub fn delete_short_tracks(min_duration: u32) -> bool {
+    if min_duration == 0 {
+        return false;
+    }
+
+    let conn_guard = GLOBAL_DATABASE.lock().unwrap();
+    let conn = conn_guard.as_ref().unwrap();
+
+    match conn.execute(
+        "DELETE FROM musics WHERE duration IS NOT NULL AND duration < ?1",
+        params![min_duration as i64 * 1000],
+    ) {
+        Ok(deleted) => deleted > 0,
+        Err(e) => {
+            crate::error!("Failed to delete short tracks: {}", e);
+            false
+        }
+    }
 }
@@ -1,5 +1,9 @@
 pub mod commands;
 pub mod database;
+pub mod roots;
+pub mod rules;
 pub mod scanner;
 pub mod types;
 pub mod utils;
+#[cfg(desktop)]
+pub mod watcher;
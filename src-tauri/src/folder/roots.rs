@@ -0,0 +1,348 @@
+use std::path::Path;
+use std::sync::Once;
+
+use rusqlite::{params, OptionalExtension};
+use serde::{Deserialize, Serialize};
+
+use crate::database::database::GLOBAL_DATABASE;
+#[cfg(desktop)]
+use crate::folder::watcher;
+use crate::folder::{types::MUSIC_PATH_SEPARATOR, utils::path_prefix_range};
+use crate::music::commands::directory::MUSIC_STORE_PATH_NAME;
+use crate::playlist::playlist::Playlist;
+use crate::state::app_store;
+
+/// A directory scanned into the library.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct MusicRoot {
+    pub id: i64,
+    pub path: String,
+    pub label: Option<String>,
+    /// Disabled roots are skipped by the scan, which drops their tracks from the library.
+    pub enabled: bool,
+    /// Unix time in seconds of the last full scan that completed.
+    pub last_scanned_at: Option<i64>,
+    pub track_count: i64,
+}
+
+/// What removing a root took out of the library.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct RootRemoval {
+    pub tracks: usize,
+    /// Entries of regular playlists pointing inside the root. They are kept, so the tracks
+    /// come back if the root is added again.
+    pub playlist_entries: i64,
+    pub playlists: Vec<String>,
+}
+
+static STORE_IMPORT: Once = Once::new();
+
+/// Condition on `column` for paths that no other enabled root than `?3` contains, given the
+/// path separator as `?4` and the character after it as `?5`, like `path_prefix_range`.
+fn outside_other_roots(column: &str) -> String {
+    format!(
+        "NOT EXISTS (
+            SELECT 1 FROM music_roots other
+            WHERE other.id <> ?3 AND other.enabled = 1
+                AND {0} >= rtrim(other.path, ?4) || ?4
+                AND {0} < rtrim(other.path, ?4) || ?5
+        )",
+        column
+    )
+}
+
+/// Separator bounds for `outside_other_roots`.
+fn separator_bounds() -> (String, String) {
+    path_prefix_range("")
+}
+
+impl MusicRoot {
+    pub fn get_all() -> Result<Vec<MusicRoot>, rusqlite::Error> {
+        Self::import_store();
+
+        let conn_guard = GLOBAL_DATABASE.lock().unwrap();
+        let conn = conn_guard.as_ref().unwrap();
+
+        let mut stmt = conn.prepare(
+            "SELECT id, path, label, enabled, last_scanned_at FROM music_roots ORDER BY id",
+        )?;
+        // Tracks another enabled root also contains aren't counted, as removing this root
+        // keeps them
+        let mut count_stmt = conn.prepare(&format!(
+            "SELECT COUNT(*) FROM musics WHERE path >= ?1 AND path < ?2 AND {}",
+            outside_other_roots("musics.path")
+        ))?;
+        let (separator, next) = separator_bounds();
+
+        let rows = stmt
+            .query_map([], |row| {
+                Ok((
+                    row.get::<_, i64>(0)?,
+                    row.get::<_, String>(1)?,
+                    row.get::<_, Option<String>>(2)?,
+                    row.get::<_, bool>(3)?,
+                    row.get::<_, Option<i64>>(4)?,
+                ))
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+
+        rows.into_iter()
+            .map(|(id, path, label, enabled, last_scanned_at)| {
+                let (start, end) = path_prefix_range(&path);
+                let track_count = count_stmt
+                    .query_row(params![start, end, id, separator, next], |row| row.get(0))?;
+                Ok(MusicRoot {
+                    id,
+                    path,
+                    label,
+                    enabled,
+                    last_scanned_at,
+                    track_count,
+                })
+            })
+            .collect()
+    }
+
+    /// Paths of the roots to scan, or `None` when no root was added yet.
+    pub fn enabled_paths() -> Option<Vec<String>> {
+        Self::import_store();
+
+        let conn_guard = GLOBAL_DATABASE.lock().unwrap();
+        let conn = conn_guard.as_ref().unwrap();
+
+        let result = (|| -> rusqlite::Result<Option<Vec<String>>> {
+            let total: i64 =
+                conn.query_row("SELECT COUNT(*) FROM music_roots", [], |row| row.get(0))?;
+            if total == 0 {
+                return Ok(None);
+            }
+
+            let mut stmt =
+                conn.prepare("SELECT path FROM music_roots WHERE enabled = 1 ORDER BY id")?;
+            let paths = stmt
+                .query_map([], |row| row.get(0))?
+                .collect::<rusqlite::Result<_>>()?;
+            Ok(Some(paths))
+        })();
+
+        result.unwrap_or_else(|e| {
+            crate::error!("Failed to get music roots: {}", e);
+            None
+        })
+    }
+
+    pub fn add(path: &str, label: Option<String>) -> Result<MusicRoot, String> {
+        Self::import_store();
+
+        let path = path.trim();
+        if !Path::new(path).is_dir() {
+            return Err(format!("Not a directory: {}", path));
+        }
+        let label = label
+            .map(|l| l.trim().to_string())
+            .filter(|l| !l.is_empty());
+
+        let id = {
+            let conn_guard = GLOBAL_DATABASE.lock().unwrap();
+            let conn = conn_guard.as_ref().unwrap();
+
+            let existing: Option<i64> = conn
+                .query_row(
+                    "SELECT id FROM music_roots WHERE path = ?1",
+                    params![path],
+                    |row| row.get(0),
+                )
+                .optional()
+                .map_err(|e| e.to_string())?;
+            if existing.is_some() {
+                return Err(format!("Already in the library: {}", path));
+            }
+
+            conn.execute(
+                "INSERT INTO music_roots (path, label) VALUES (?1, ?2)",
+                params![path, label],
+            )
+            .map_err(|e| e.to_string())?;
+            conn.last_insert_rowid()
+        };
+
+        Self::roots_changed();
+        Self::get_all()
+            .map_err(|e| e.to_string())?
+            .into_iter()
+            .find(|root| root.id == id)
+            .ok_or_else(|| "Failed to read the added music root".to_string())
+    }
+
+    /// Remove the root and its tracks from the library. Files on disk are left alone.
+    pub fn remove(id: i64) -> Result<RootRemoval, String> {
+        let removal = {
+            let mut conn_guard = GLOBAL_DATABASE.lock().unwrap();
+            let conn = conn_guard.as_mut().unwrap();
+
+            (|| -> rusqlite::Result<Option<RootRemoval>> {
+                let tx = conn.transaction()?;
+
+                let Some(path) = tx
+                    .query_row(
+                        "SELECT path FROM music_roots WHERE id = ?1",
+                        params![id],
+                        |row| row.get::<_, String>(0),
+                    )
+                    .optional()?
+                else {
+                    return Ok(None);
+                };
+                let (start, end) = path_prefix_range(&path);
+                let (separator, next) = separator_bounds();
+
+                // Smart playlists are left out, as their entries are re-evaluated anyway
+                let mut stmt = tx.prepare(&format!(
+                    "SELECT playlists.name, COUNT(*) FROM playlist_musics
+                    JOIN playlists ON playlists.id = playlist_musics.playlist_id
+                    WHERE playlists.rules IS NULL
+                        AND playlist_musics.path >= ?1 AND playlist_musics.path < ?2 AND {}
+                    GROUP BY playlists.id
+                    ORDER BY playlists.name",
+                    outside_other_roots("playlist_musics.path")
+                ))?;
+                let references = stmt
+                    .query_map(params![start, end, id, separator, next], |row| {
+                        Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)?))
+                    })?
+                    .collect::<rusqlite::Result<Vec<_>>>()?;
+                drop(stmt);
+
+                // Tracks that another enabled root also contains stay in the library
+                let tracks = tx.execute(
+                    &format!(
+                        "DELETE FROM musics WHERE path >= ?1 AND path < ?2 AND {}",
+                        outside_other_roots("musics.path")
+                    ),
+                    params![start, end, id, separator, next],
+                )?;
+                tx.execute("DELETE FROM music_roots WHERE id = ?1", params![id])?;
+                tx.commit()?;
+
+                Ok(Some(RootRemoval {
+                    tracks,
+                    playlist_entries: references.iter().map(|(_, count)| count).sum(),
+                    playlists: references.into_iter().map(|(name, _)| name).collect(),
+                }))
+            })()
+            .map_err(|e| e.to_string())?
+            .ok_or_else(|| "Music root not found".to_string())?
+        };
+
+        if removal.tracks > 0 {
+            if let Err(e) = Playlist::refresh_smart() {
+                crate::error!("Failed to refresh smart playlists: {}", e);
+            }
+        }
+        Self::roots_changed();
+        Ok(removal)
+    }
+
+    pub fn set_enabled(id: i64, enabled: bool) -> Result<(), String> {
+        Self::update(
+            "UPDATE music_roots SET enabled = ?2 WHERE id = ?1",
+            params![id, enabled],
+        )?;
+        Self::roots_changed();
+        Ok(())
+    }
+
+    pub fn set_label(id: i64, label: Option<String>) -> Result<(), String> {
+        let label = label
+            .map(|l| l.trim().to_string())
+            .filter(|l| !l.is_empty());
+        Self::update(
+            "UPDATE music_roots SET label = ?2 WHERE id = ?1",
+            params![id, label],
+        )
+    }
+
+    /// Record that a full scan of `paths` just completed.
+    pub fn mark_scanned(paths: &[String]) {
+        let conn_guard = GLOBAL_DATABASE.lock().unwrap();
+        let conn = conn_guard.as_ref().unwrap();
+
+        let result = (|| -> rusqlite::Result<()> {
+            let mut stmt = conn.prepare(
+                "UPDATE music_roots SET last_scanned_at = CAST(strftime('%s', 'now') AS INTEGER)
+                WHERE path = ?1",
+            )?;
+            for path in paths {
+                stmt.execute(params![path])?;
+            }
+            Ok(())
+        })();
+        if let Err(e) = result {
+            crate::error!("Failed to update music root scan time: {}", e);
+        }
+    }
+
+    fn update(query: &str, params: impl rusqlite::Params) -> Result<(), String> {
+        let conn_guard = GLOBAL_DATABASE.lock().unwrap();
+        let conn = conn_guard.as_ref().unwrap();
+
+        match conn.execute(query, params) {
+            Ok(0) => Err("Music root not found".to_string()),
+            Ok(_) => Ok(()),
+            Err(e) => Err(e.to_string()),
+        }
+    }
+
+    /// Mirror the enabled roots to the store for the frontend, and make the next library
+    /// request walk the directories again.
+    fn roots_changed() {
+        let paths = Self::enabled_paths().unwrap_or_default();
+        let store = app_store();
+        if paths.is_empty() {
+            store.delete(MUSIC_STORE_PATH_NAME);
+        } else {
+            store.set(MUSIC_STORE_PATH_NAME, paths.join(MUSIC_PATH_SEPARATOR));
+        }
+        let _ = store.save();
+
+        #[cfg(desktop)]
+        watcher::stop();
+    }
+
+    /// Roots used to be kept in the store only. Import them once when the table is empty.
+    fn import_store() {
+        STORE_IMPORT.call_once(|| {
+            let Some(value) = app_store().get(MUSIC_STORE_PATH_NAME) else {
+                return;
+            };
+            let value = value.as_str().unwrap_or_default().to_string();
+
+            let mut conn_guard = GLOBAL_DATABASE.lock().unwrap();
+            let conn = conn_guard.as_mut().unwrap();
+
+            let result = (|| -> rusqlite::Result<()> {
+                let tx = conn.transaction()?;
+                let total: i64 =
+                    tx.query_row("SELECT COUNT(*) FROM music_roots", [], |row| row.get(0))?;
+                if total > 0 {
+                    return Ok(());
+                }
+                for path in value.split(MUSIC_PATH_SEPARATOR) {
+                    let path = path.trim();
+                    if !path.is_empty() {
+                        tx.execute(
+                            "INSERT OR IGNORE INTO music_roots (path) VALUES (?1)",
+                            params![path],
+                        )?;
+                    }
+                }
+                tx.commit()
+            })();
+            if let Err(e) = result {
+                crate::error!("Failed to import music roots: {}", e);
+            }
+        });
+    }
+}
@@ -0,0 +1,240 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use glob::{MatchOptions, Pattern};
+use serde::{Deserialize, Serialize};
+
+use crate::state::app_store;
+
+const STORE_KEY_SCAN_RULES: &str = "scan-rules";
+
+/// Files that are never part of the library, whatever the user patterns.
+const BUILT_IN_EXCLUDE: &[&str] = &["**/au_uu_SzH34yR2.mp3"];
+
+const GLOB_OPTIONS: MatchOptions = MatchOptions {
+    case_sensitive: false,
+    require_literal_separator: true,
+    require_literal_leading_dot: false,
+};
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ScanRules {
+    /// Glob patterns matched against full paths, e.g. `**/Samples/**`.
+    pub exclude: Vec<String>,
+    /// Patterns applied on top of `exclude`, listed for the settings but not editable.
+    #[serde(skip_deserializing)]
+    pub built_in_exclude: Vec<String>,
+    /// Files smaller than this many bytes are skipped, 0 disables the check.
+    pub min_file_size: u64,
+    /// Tracks shorter than this many seconds are left out of the library, 0 disables the check.
+    pub min_duration: u32,
+    /// Options of single music directories, keyed by their path.
+    pub roots: HashMap<String, RootScanOptions>,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct RootScanOptions {
+    pub follow_symlinks: bool,
+    /// How deep to look below the directory, where 1 only reads the files directly in it.
+    pub max_depth: Option<usize>,
+}
+
+impl Default for ScanRules {
+    fn default() -> Self {
+        Self {
+            exclude: Vec::new(),
+            built_in_exclude: BUILT_IN_EXCLUDE.iter().map(|p| p.to_string()).collect(),
+            min_file_size: 0,
+            min_duration: 0,
+            roots: HashMap::new(),
+        }
+    }
+}
+
+impl ScanRules {
+    pub fn load() -> Self {
+        app_store()
+            .get(STORE_KEY_SCAN_RULES)
+            .and_then(|v| serde_json::from_value(v).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self) {
+        let store = app_store();
+        store.set(STORE_KEY_SCAN_RULES, serde_json::json!(self));
+        let _ = store.save();
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        for pattern in &self.exclude {
+            Pattern::new(pattern).map_err(|e| format!("Invalid pattern {}: {}", pattern, e))?;
+        }
+        Ok(())
+    }
+
+    /// Whether a track of `duration` milliseconds is left out of the library.
+    pub fn is_too_short(&self, duration: Option<u128>) -> bool {
+        duration.is_some_and(|d| d < self.min_duration as u128 * 1000)
+    }
+
+    pub fn filter(&self) -> ScanFilter {
+        let exclude = self
+            .built_in_exclude
+            .iter()
+            .chain(&self.exclude)
+            .filter_map(|pattern| match Pattern::new(pattern) {
+                Ok(pattern) => Some(pattern),
+                Err(e) => {
+                    crate::warn!("Ignoring invalid exclude pattern {}: {}", pattern, e);
+                    None
+                }
+            })
+            .collect();
+
+        ScanFilter {
+            exclude,
+            min_file_size: self.min_file_size,
+            roots: self
+                .roots
+                .iter()
+                .map(|(path, options)| (PathBuf::from(path), *options))
+                .collect(),
+        }
+    }
+}
+
+/// Compiled `ScanRules`, used while walking directories and for watcher events.
+pub struct ScanFilter {
+    exclude: Vec<Pattern>,
+    min_file_size: u64,
+    roots: Vec<(PathBuf, RootScanOptions)>,
+}
+
+impl ScanFilter {
+    /// Options for walking `dir`, with the depth limit reduced when `dir` is below its root.
+    pub fn walk_options(&self, dir: &Path) -> RootScanOptions {
+        match self.root_of(dir) {
+            Some((depth, options)) => RootScanOptions {
+                follow_symlinks: options.follow_symlinks,
+                max_depth: options.max_depth.map(|max| max.saturating_sub(depth)),
+            },
+            None => RootScanOptions::default(),
+        }
+    }
+
+    pub fn is_excluded(&self, path: &Path) -> bool {
+        self.exclude
+            .iter()
+            .any(|pattern| pattern.matches_path_with(path, GLOB_OPTIONS))
+    }
+
+    pub fn allows_file(&self, path: &Path) -> bool {
+        if self.is_excluded(path) {
+            return false;
+        }
+
+        if let Some((depth, options)) = self.root_of(path) {
+            if options.max_depth.is_some_and(|max| depth > max) {
+                return false;
+            }
+        }
+
+        self.min_file_size == 0
+            || std::fs::metadata(path)
+                .map(|m| m.len() >= self.min_file_size)
+                .unwrap_or(false)
+    }
+
+    /// The configured root containing `path`, with how many levels below it `path` is.
+    fn root_of(&self, path: &Path) -> Option<(usize, RootScanOptions)> {
+        self.roots
+            .iter()
+            .filter_map(|(root, options)| {
+                let depth = path.strip_prefix(root).ok()?.components().count();
+                Some((depth, *options))
+            })
+            // The innermost root wins when roots are nested
+            .min_by_key(|(depth, _)| *depth)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rules(exclude: &[&str], roots: &[(&str, Option<usize>)]) -> ScanRules {
+        ScanRules {
+            exclude: exclude.iter().map(|p| p.to_string()).collect(),
+            roots: roots
+                .iter()
+                .map(|(path, max_depth)| {
+                    let options = RootScanOptions {
+                        follow_symlinks: false,
+                        max_depth: *max_depth,
+                    };
+                    (path.to_string(), options)
+                })
+                .collect(),
+            ..ScanRules::default()
+        }
+    }
+
+    #[test]
+    fn exclude_globs_match_full_paths() {
+        let filter = rules(&["**/samples/**", "/music/*.wav"], &[]).filter();
+
+        assert!(!filter.allows_file(Path::new("/music/Samples/kick.flac")));
+        assert!(!filter.allows_file(Path::new("/music/take.WAV")));
+        // `*` doesn't cross directories
+        assert!(filter.allows_file(Path::new("/music/Album/take.wav")));
+        assert!(filter.allows_file(Path::new("/music/samples.flac")));
+    }
+
+    #[test]
+    fn built_in_exclusions_apply_without_user_patterns() {
+        let rules = ScanRules::default();
+        assert!(rules.exclude.is_empty());
+        assert!(!rules
+            .filter()
+            .allows_file(Path::new("/music/Album/au_uu_SzH34yR2.mp3")));
+    }
+
+    #[test]
+    fn built_in_exclusions_are_not_loaded_from_the_store() {
+        let stored = serde_json::json!({ "exclude": ["*.tmp"], "builtInExclude": [] });
+        let rules: ScanRules = serde_json::from_value(stored).unwrap();
+        assert_eq!(rules.exclude, ["*.tmp"]);
+        assert_eq!(rules.built_in_exclude, BUILT_IN_EXCLUDE);
+    }
+
+    #[test]
+    fn max_depth_counts_from_the_innermost_root() {
+        let filter = rules(&[], &[("/music", Some(2)), ("/music/Live", Some(1))]).filter();
+
+        assert!(filter.allows_file(Path::new("/music/a.mp3")));
+        assert!(filter.allows_file(Path::new("/music/Album/a.mp3")));
+        assert!(!filter.allows_file(Path::new("/music/Album/CD1/a.mp3")));
+        assert!(filter.allows_file(Path::new("/music/Live/a.mp3")));
+        assert!(!filter.allows_file(Path::new("/music/Live/2001/a.mp3")));
+        // Directories outside any configured root have no limit
+        assert!(filter.allows_file(Path::new("/other/a/b/c/a.mp3")));
+    }
+
+    #[test]
+    fn walk_options_reduce_the_depth_below_the_root() {
+        let filter = rules(&[], &[("/music", Some(3))]).filter();
+
+        assert_eq!(filter.walk_options(Path::new("/music")).max_depth, Some(3));
+        assert_eq!(
+            filter.walk_options(Path::new("/music/Album")).max_depth,
+            Some(2)
+        );
+        assert_eq!(
+            filter.walk_options(Path::new("/music/a/b/c/d")).max_depth,
+            Some(0)
+        );
+        assert_eq!(filter.walk_options(Path::new("/other")).max_depth, None);
+    }
+}
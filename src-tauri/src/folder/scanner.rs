@@ -1,13 +1,27 @@
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
 
 use crate::database::database::GLOBAL_DATABASE;
-use crate::folder::types::FolderItem;
+use crate::folder::rules::ScanRules;
+use crate::folder::types::{FolderItem, ScanFailure, ScanProgress, ScanSummary};
 use crate::folder::utils::is_not_hidden;
+use crate::library::library::Library;
+use crate::music::image_cache::ImageCache;
 use crate::music::metadata::MusicMetadata;
+use crate::state::app_handle;
 use chrono::{DateTime, Utc};
 use futures::StreamExt;
+use tauri::Emitter;
 use walkdir::{DirEntry, WalkDir};
 
+/// Minimum time between two scan progress events.
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(100);
+
+static SCAN_CANCEL_REQUESTED: AtomicBool = AtomicBool::new(false);
+static LAST_SCAN_SUMMARY: Mutex<Option<ScanSummary>> = Mutex::new(None);
+
 pub fn get_folder_items(path: &str) -> Vec<FolderItem> {
     WalkDir::new(path)
         .max_depth(1)
@@ -30,13 +44,20 @@ const SUPPORTED_EXTENSIONS: &[&str] = &[
 ];
 
 pub fn scan_directories(search_dirs: Vec<String>) -> Vec<PathBuf> {
+    let filter = ScanRules::load().filter();
     let mut dirs: Vec<Result<DirEntry, walkdir::Error>> = vec![];
 
     for dir in search_dirs {
+        let options = filter.walk_options(Path::new(&dir));
+        let mut walker = WalkDir::new(dir).follow_links(options.follow_symlinks);
+        if let Some(max_depth) = options.max_depth {
+            walker = walker.max_depth(max_depth);
+        }
+
         dirs.extend(
-            WalkDir::new(dir)
+            walker
                 .into_iter()
-                .filter_entry(|e| is_not_hidden(e))
+                .filter_entry(|e| is_not_hidden(e) && !filter.is_excluded(e.path()))
                 .collect::<Vec<_>>(),
         );
     }
@@ -49,25 +70,23 @@ pub fn scan_directories(search_dirs: Vec<String>) -> Vec<PathBuf> {
             }
             e.ok()
         })
-        .filter(|e| {
-            if !e.path().is_file()
-                || e.path().file_name().unwrap_or_default() == "au_uu_SzH34yR2.mp3"
-            {
-                return false;
-            }
-
-            e.path()
-                .extension()
-                .and_then(|ext| ext.to_str())
-                .map(|ext| SUPPORTED_EXTENSIONS.contains(&ext.to_lowercase().as_str()))
-                .unwrap_or(false)
-        })
+        .filter(|e| is_supported_file(e.path()) && filter.allows_file(e.path()))
         .map(|entry| entry.path().to_path_buf())
         .collect()
 }
 
+pub fn is_supported_file(path: &Path) -> bool {
+    if !path.is_file() {
+        return false;
+    }
+
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .map(|ext| SUPPORTED_EXTENSIONS.contains(&ext.to_lowercase().as_str()))
+        .unwrap_or(false)
+}
+
 pub fn get_home_dir() -> String {
-    use crate::state::app_handle;
     use tauri::Manager;
 
     app_handle()
@@ -78,7 +97,18 @@ pub fn get_home_dir() -> String {
         .to_string()
 }
 
-pub async fn process_supported_files(paths: &[PathBuf]) {
+/// Read metadata of new and modified files and write it to the database. Interactive scans
+/// emit progress events and stop early once `cancel_scan` is called.
+pub async fn process_supported_files(paths: &[PathBuf], interactive: bool) -> ScanSummary {
+    let started = Instant::now();
+    let mut summary = ScanSummary {
+        found: paths.len(),
+        ..Default::default()
+    };
+    if interactive {
+        emit_scan_progress(&summary, None, started);
+    }
+
     // Optimization: Pre-fetch existing records to skip unmodified files
     let existing_records = tokio::task::spawn_blocking(|| {
         let conn_guard = GLOBAL_DATABASE.lock().ok()?;
@@ -104,9 +134,10 @@ pub async fn process_supported_files(paths: &[PathBuf]) {
     .flatten()
     .unwrap_or_default();
 
+    let rules = ScanRules::load();
     let existing_records = std::sync::Arc::new(existing_records);
 
-    let metadata_results: Vec<_> = futures::stream::iter(paths.to_vec())
+    let mut metadata_stream = futures::stream::iter(paths.to_vec())
         .map(|path| {
             let existing_records = existing_records.clone();
             async move {
@@ -117,7 +148,7 @@ pub async fn process_supported_files(paths: &[PathBuf]) {
                 if let Some(db_modified) = existing_records.get(&path_str) {
                     if let Some(curr_modified) = &modified {
                         if db_modified == curr_modified {
-                            return None;
+                            return (path_str, None);
                         }
                     }
                 }
@@ -128,37 +159,75 @@ pub async fn process_supported_files(paths: &[PathBuf]) {
                 #[cfg(not(target_os = "android"))]
                 let metadata = MusicMetadata::get(path_str.clone()).await;
 
-                Some((path_str, modified, metadata))
+                (path_str, Some((modified, metadata)))
             }
         })
-        .buffer_unordered(10)
-        .filter_map(|res| async { res })
-        .collect()
-        .await;
+        .buffer_unordered(10);
+
+    let mut metadata_results = Vec::new();
+    // Known tracks that became too short to stay in the library, new ones are never added
+    let mut too_short = Vec::new();
+    let mut last_progress = Instant::now();
+    while let Some((path, result)) = metadata_stream.next().await {
+        if interactive && is_scan_cancelled() {
+            crate::info!("Library scan cancelled");
+            summary.cancelled = true;
+            break;
+        }
+
+        summary.processed += 1;
+        match result {
+            Some((modified, Ok(metadata))) => {
+                if !rules.is_too_short(metadata.duration) {
+                    metadata_results.push((modified, metadata));
+                } else if existing_records.contains_key(&path) {
+                    too_short.push(path.clone());
+                }
+            }
+            Some((_, Err(error))) => {
+                crate::warn!("Failed to read metadata for file {}: {:?}", path, error);
+                summary.failed.push(ScanFailure {
+                    path: path.clone(),
+                    error,
+                });
+            }
+            None => {}
+        }
+
+        if interactive && last_progress.elapsed() >= PROGRESS_INTERVAL {
+            last_progress = Instant::now();
+            emit_scan_progress(&summary, Some(path), started);
+        }
+    }
+    if interactive && !summary.cancelled {
+        emit_scan_progress(&summary, None, started);
+    }
 
-    if metadata_results.is_empty() {
+    if metadata_results.is_empty() && too_short.is_empty() {
         crate::info!("No new or modified files to process.");
-        return;
+        spawn_thumbnail_generation();
+        summary.elapsed = started.elapsed().as_secs_f64();
+        return summary;
     }
     crate::info!("Processed metadata for {} files.", metadata_results.len());
 
     // Then do one blocking DB transaction
-    tokio::task::spawn_blocking(move || {
+    let updated = tokio::task::spawn_blocking(move || {
         let mut conn_guard = GLOBAL_DATABASE.lock().ok()?;
         let conn = conn_guard.as_mut()?;
         let tx = conn.transaction().ok()?;
 
-        for (path, modified_at, metadata) in metadata_results {
-            if metadata.is_err() {
-                crate::warn!(
-                    "Failed to read metadata for file {}: {:?}",
-                    path,
-                    metadata.unwrap_err()
-                );
-                continue;
+        for path in too_short {
+            if let Err(e) = tx.execute(
+                "DELETE FROM musics WHERE path = ?1",
+                rusqlite::params![path],
+            ) {
+                crate::error!("Failed to remove short track {}: {}", path, e);
             }
-            let metadata = metadata.unwrap();
-            let path_string = path.to_string();
+        }
+
+        for (modified_at, metadata) in metadata_results {
+            let path_string = metadata.path.clone();
             let modified_at = modified_at.unwrap_or_default();
 
             // Check if exists
@@ -174,8 +243,16 @@ pub async fn process_supported_files(paths: &[PathBuf]) {
                     let res = tx.execute(
                         "INSERT INTO musics (
                             path, duration, title, artist, album, album_artist,
-                            track_number, genre, date, bits_per_sample, sample_rate, modified_at
-                        ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12)",
+                            track_number, genre, date, bits_per_sample, sample_rate, modified_at,
+                            replay_gain_track_gain, replay_gain_track_peak,
+                            replay_gain_album_gain, replay_gain_album_peak,
+                            track_total, disc_number, disc_total, composer, conductor, label, isrc,
+                            musicbrainz_recording_id, musicbrainz_album_id, musicbrainz_artist_id,
+                            musicbrainz_release_group_id, codec, bitrate, channels, file_size
+                        ) VALUES (
+                            ?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16,
+                            ?17, ?18, ?19, ?20, ?21, ?22, ?23, ?24, ?25, ?26, ?27, ?28, ?29, ?30, ?31
+                        )",
                         rusqlite::params![
                             metadata.path,
                             metadata.duration.map(|d| d as i64),
@@ -188,12 +265,32 @@ pub async fn process_supported_files(paths: &[PathBuf]) {
                             metadata.date,
                             metadata.bits_per_sample.map(|b| b as i64),
                             metadata.sample_rate.map(|s| s as i64),
-                            modified_at
+                            modified_at,
+                            metadata.replay_gain_track_gain,
+                            metadata.replay_gain_track_peak,
+                            metadata.replay_gain_album_gain,
+                            metadata.replay_gain_album_peak,
+                            metadata.track_total,
+                            metadata.disc_number,
+                            metadata.disc_total,
+                            metadata.composer,
+                            metadata.conductor,
+                            metadata.label,
+                            metadata.isrc,
+                            metadata.musicbrainz_recording_id,
+                            metadata.musicbrainz_album_id,
+                            metadata.musicbrainz_artist_id,
+                            metadata.musicbrainz_release_group_id,
+                            metadata.codec,
+                            metadata.bitrate,
+                            metadata.channels,
+                            metadata.file_size.map(|s| s as i64)
                         ],
                     );
 
-                    if res.is_err() {
-                        crate::error!("Insert music to table error: {}", res.unwrap_err());
+                    match res {
+                        Ok(_) => link_music(&tx, &metadata),
+                        Err(e) => crate::error!("Insert music to table error: {}", e),
                     }
                 }
                 Ok(existing_modified_at) => {
@@ -203,8 +300,16 @@ pub async fn process_supported_files(paths: &[PathBuf]) {
                                     duration = ?1, title = ?2, artist = ?3,
                                     album = ?4, album_artist = ?5, track_number = ?6,
                                     genre = ?7, bits_per_sample = ?8, sample_rate = ?9,
-                                    modified_at = ?10, date = ?11
-                                WHERE path = ?12",
+                                    modified_at = ?10, date = ?11,
+                                    replay_gain_track_gain = ?12, replay_gain_track_peak = ?13,
+                                    replay_gain_album_gain = ?14, replay_gain_album_peak = ?15,
+                                    loudness = NULL, loudness_analyzed = 0, image_key = NULL,
+                                    track_total = ?17, disc_number = ?18, disc_total = ?19,
+                                    composer = ?20, conductor = ?21, label = ?22, isrc = ?23,
+                                    musicbrainz_recording_id = ?24, musicbrainz_album_id = ?25,
+                                    musicbrainz_artist_id = ?26, musicbrainz_release_group_id = ?27,
+                                    codec = ?28, bitrate = ?29, channels = ?30, file_size = ?31
+                                WHERE path = ?16",
                             rusqlite::params![
                                 metadata.duration.map(|d| d as i64),
                                 metadata.title,
@@ -217,12 +322,32 @@ pub async fn process_supported_files(paths: &[PathBuf]) {
                                 metadata.sample_rate.map(|s| s as i64),
                                 modified_at,
                                 metadata.date,
-                                metadata.path
+                                metadata.replay_gain_track_gain,
+                                metadata.replay_gain_track_peak,
+                                metadata.replay_gain_album_gain,
+                                metadata.replay_gain_album_peak,
+                                metadata.path,
+                                metadata.track_total,
+                                metadata.disc_number,
+                                metadata.disc_total,
+                                metadata.composer,
+                                metadata.conductor,
+                                metadata.label,
+                                metadata.isrc,
+                                metadata.musicbrainz_recording_id,
+                                metadata.musicbrainz_album_id,
+                                metadata.musicbrainz_artist_id,
+                                metadata.musicbrainz_release_group_id,
+                                metadata.codec,
+                                metadata.bitrate,
+                                metadata.channels,
+                                metadata.file_size.map(|s| s as i64)
                             ],
                         );
 
-                        if res.is_err() {
-                            crate::error!("Update music to table error: {}", res.unwrap_err());
+                        match res {
+                            Ok(_) => link_music(&tx, &metadata),
+                            Err(e) => crate::error!("Update music to table error: {}", e),
                         }
                     }
                 }
@@ -235,6 +360,262 @@ pub async fn process_supported_files(paths: &[PathBuf]) {
         tx.commit().ok()
     })
     .await
+    .ok()
+    .flatten()
+    .is_some();
+
+    spawn_thumbnail_generation();
+
+    summary.updated = updated;
+    summary.elapsed = started.elapsed().as_secs_f64();
+    summary
+}
+
+fn link_music(tx: &rusqlite::Transaction, metadata: &MusicMetadata) {
+    if let Err(e) = Library::link_music(tx, metadata) {
+        crate::error!(
+            "Failed to link artists, album and genres of {}: {}",
+            metadata.path,
+            e
+        );
+    }
+}
+
+pub fn reset_scan_cancel() {
+    SCAN_CANCEL_REQUESTED.store(false, Ordering::SeqCst);
+}
+
+/// Ask the running interactive scan to stop after the files currently being read.
+pub fn cancel_scan() {
+    SCAN_CANCEL_REQUESTED.store(true, Ordering::SeqCst);
+}
+
+pub fn is_scan_cancelled() -> bool {
+    SCAN_CANCEL_REQUESTED.load(Ordering::SeqCst)
+}
+
+/// Keep the summary of a finished interactive scan and hand it to the frontend.
+pub fn finish_scan(summary: ScanSummary) {
+    crate::info!(
+        "Library scan finished: {} found, {} processed, {} failed{}",
+        summary.found,
+        summary.processed,
+        summary.failed.len(),
+        if summary.cancelled { ", cancelled" } else { "" }
+    );
+
+    app_handle()
+        .emit(crate::commands::route::MUSIC_SCAN_FINISHED, &summary)
+        .unwrap_or_else(|_| {
+            crate::error!(
+                "Failed to emit {}",
+                crate::commands::route::MUSIC_SCAN_FINISHED
+            )
+        });
+    if let Ok(mut last) = LAST_SCAN_SUMMARY.lock() {
+        *last = Some(summary);
+    }
+}
+
+pub fn last_scan_summary() -> Option<ScanSummary> {
+    LAST_SCAN_SUMMARY.lock().ok()?.clone()
+}
+
+fn emit_scan_progress(summary: &ScanSummary, current_path: Option<String>, started: Instant) {
+    let remaining = summary.found.saturating_sub(summary.processed);
+    let eta = (summary.processed > 0)
+        .then(|| started.elapsed().as_secs_f64() / summary.processed as f64 * remaining as f64);
+
+    let progress = ScanProgress {
+        found: summary.found,
+        processed: summary.processed,
+        failed: summary.failed.len(),
+        current_path,
+        eta,
+    };
+    app_handle()
+        .emit(crate::commands::route::MUSIC_SCAN_PROGRESS, progress)
+        .unwrap_or_else(|_| {
+            crate::error!(
+                "Failed to emit {}",
+                crate::commands::route::MUSIC_SCAN_PROGRESS
+            )
+        });
+}
+
+/// Most analyzed files saved in one transaction.
+#[cfg(desktop)]
+const LOUDNESS_BATCH_SIZE: usize = 50;
+
+#[cfg(desktop)]
+static LOUDNESS_ANALYSIS_RUNNING: AtomicBool = AtomicBool::new(false);
+
+/// Measure loudness in the background for tracks without ReplayGain tags, so normalization
+/// still has a gain to work with. Only one analysis runs at a time.
+#[cfg(desktop)]
+pub fn spawn_loudness_analysis() {
+    if LOUDNESS_ANALYSIS_RUNNING.swap(true, Ordering::SeqCst) {
+        return;
+    }
+
+    tauri::async_runtime::spawn(async {
+        analyze_missing_loudness().await;
+        LOUDNESS_ANALYSIS_RUNNING.store(false, Ordering::SeqCst);
+    });
+}
+
+#[cfg(desktop)]
+async fn analyze_missing_loudness() {
+    let paths = tokio::task::spawn_blocking(|| {
+        let conn_guard = GLOBAL_DATABASE.lock().ok()?;
+        let conn = conn_guard.as_ref()?;
+        let mut stmt = conn
+            .prepare(
+                "SELECT path FROM musics
+                WHERE loudness_analyzed = 0 AND replay_gain_track_gain IS NULL",
+            )
+            .ok()?;
+        let rows = stmt.query_map([], |row| row.get::<_, String>(0)).ok()?;
+        Some(rows.filter_map(|r| r.ok()).collect::<Vec<_>>())
+    })
+    .await
+    .ok()
+    .flatten()
+    .unwrap_or_default();
+
+    if paths.is_empty() {
+        return;
+    }
+
+    let now = std::time::Instant::now();
+    crate::info!("Analyzing loudness for {} files.", paths.len());
+
+    // Decoding whole files is expensive, keep this well below the metadata concurrency.
+    // Results are saved as they come in, so quitting part-way doesn't lose them.
+    let mut results = futures::stream::iter(paths)
+        .map(|path| async move {
+            let loudness = MusicMetadata::get_loudness_with_ffmpeg(&path).await;
+            (path, loudness)
+        })
+        .buffer_unordered(2)
+        .ready_chunks(LOUDNESS_BATCH_SIZE);
+
+    while let Some(batch) = results.next().await {
+        tokio::task::spawn_blocking(move || save_loudness(batch))
+            .await
+            .ok();
+    }
+
+    crate::info!("Loudness analysis took {}s", now.elapsed().as_secs_f64());
+}
+
+#[cfg(desktop)]
+fn save_loudness(results: Vec<(String, Result<Option<f64>, String>)>) -> Option<()> {
+    let mut conn_guard = GLOBAL_DATABASE.lock().ok()?;
+    let conn = conn_guard.as_mut()?;
+    let tx = conn.transaction().ok()?;
+
+    for (path, loudness) in results {
+        // Failed files are still marked so they aren't decoded again on every scan
+        let loudness = loudness.unwrap_or_else(|e| {
+            crate::warn!("Failed to analyze loudness: {}", e);
+            None
+        });
+
+        let res = tx.execute(
+            "UPDATE musics SET loudness = ?1, loudness_analyzed = 1 WHERE path = ?2",
+            rusqlite::params![loudness, path],
+        );
+        if let Err(e) = res {
+            crate::error!("Update music loudness error: {}", e);
+        }
+    }
+
+    tx.commit().ok()
+}
+
+static THUMBNAIL_GENERATION_RUNNING: AtomicBool = AtomicBool::new(false);
+
+/// Cache the covers of new and modified tracks at the base and thumbnail sizes in the
+/// background, so the first requests are served from disk. Albums are done one at a time to
+/// leave the resize permits to covers requested on screen.
+pub fn spawn_thumbnail_generation() {
+    if THUMBNAIL_GENERATION_RUNNING.swap(true, Ordering::SeqCst) {
+        return;
+    }
+
+    tauri::async_runtime::spawn(async {
+        generate_missing_thumbnails().await;
+        THUMBNAIL_GENERATION_RUNNING.store(false, Ordering::SeqCst);
+    });
+}
+
+async fn generate_missing_thumbnails() {
+    type Row = (String, Option<String>, Option<String>);
+    let rows: Vec<Row> = tokio::task::spawn_blocking(|| {
+        let conn_guard = GLOBAL_DATABASE.lock().ok()?;
+        let conn = conn_guard.as_ref()?;
+        let mut stmt = conn
+            .prepare("SELECT path, artist, album FROM musics WHERE image_key IS NULL")
+            .ok()?;
+        let rows = stmt
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))
+            .ok()?;
+        Some(rows.filter_map(|r| r.ok()).collect())
+    })
+    .await
+    .ok()
+    .flatten()
+    .unwrap_or_default();
+
+    if rows.is_empty() {
+        return;
+    }
+
+    let now = std::time::Instant::now();
+    let keys: Vec<String> = rows
+        .iter()
+        .map(|(path, artist, album)| {
+            ImageCache::get_cache_key(artist.as_deref(), album.as_deref(), path)
+        })
+        .collect();
+
+    // Tracks of an album share their cover, the first one stands for the others
+    let mut albums = std::collections::HashSet::new();
+    let mut cached = 0;
+    for ((path, _, _), key) in rows.iter().zip(&keys) {
+        if albums.insert(key.as_str()) && ImageCache::pregenerate(key, path).await {
+            cached += 1;
+        }
+    }
+    crate::info!(
+        "Cached covers of {} of {} albums in {}s",
+        cached,
+        albums.len(),
+        now.elapsed().as_secs_f64()
+    );
+
+    tokio::task::spawn_blocking(move || {
+        let mut conn_guard = GLOBAL_DATABASE.lock().ok()?;
+        let conn = conn_guard.as_mut()?;
+        let tx = conn.transaction().ok()?;
+
+        // Albums without local art are marked too, their cover is looked up online on request.
+        // Tracks whose tags changed in the meantime are left for the next run.
+        for ((path, artist, album), key) in rows.into_iter().zip(keys) {
+            let res = tx.execute(
+                "UPDATE musics SET image_key = ?1
+                WHERE path = ?2 AND artist IS ?3 AND album IS ?4",
+                rusqlite::params![key, path, artist, album],
+            );
+            if let Err(e) = res {
+                crate::error!("Update music image key error: {}", e);
+            }
+        }
+
+        tx.commit().ok()
+    })
+    .await
     .ok();
 }
 
@@ -13,4 +13,37 @@ pub enum FsItemType {
     File,
 }
 
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct ScanProgress {
+    /// Supported files found while walking the music directories.
+    pub found: usize,
+    /// Files checked so far, including unchanged ones and failures.
+    pub processed: usize,
+    pub failed: usize,
+    pub current_path: Option<String>,
+    /// Estimated seconds until all found files are processed.
+    pub eta: Option<f64>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ScanFailure {
+    pub path: String,
+    pub error: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct ScanSummary {
+    pub found: usize,
+    pub processed: usize,
+    pub failed: Vec<ScanFailure>,
+    /// Whether any track was added or updated in the database.
+    pub updated: bool,
+    pub cancelled: bool,
+    /// Seconds spent processing files.
+    pub elapsed: f64,
+}
+
 pub const MUSIC_PATH_SEPARATOR: &str = "||";
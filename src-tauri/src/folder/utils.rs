@@ -8,3 +8,13 @@ pub fn is_not_hidden(entry: &DirEntry) -> bool {
         .map(|s| !s.starts_with('.'))
         .unwrap_or(false)
 }
+
+/// Bounds for `path >= start AND path < end` matching everything inside `dir`. Everything
+/// starting with `dir/` sorts before `dir0`, as '0' follows '/', and a range rather than a
+/// prefix match lets SQLite use the index on `path`.
+pub fn path_prefix_range(dir: &str) -> (String, String) {
+    let separator = std::path::MAIN_SEPARATOR;
+    let dir = dir.trim_end_matches(separator);
+    let next = char::from_u32(separator as u32 + 1).unwrap_or(separator);
+    (format!("{}{}", dir, separator), format!("{}{}", dir, next))
+}
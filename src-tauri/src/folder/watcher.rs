@@ -0,0 +1,241 @@
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+use std::sync::Mutex;
+use std::time::Duration;
+
+use notify::event::{ModifyKind, RenameMode};
+use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use tauri::Emitter;
+
+use crate::folder::rules::{ScanFilter, ScanRules};
+use crate::folder::{database, scanner};
+use crate::playlist::playlist::Playlist;
+use crate::state::app_handle;
+
+/// Events are collected until nothing happened for this long, so copying an album is
+/// processed as one batch.
+const DEBOUNCE: Duration = Duration::from_millis(1000);
+
+struct LibraryWatcher {
+    dirs: Vec<String>,
+    _watcher: RecommendedWatcher,
+}
+
+static WATCHER: Mutex<Option<LibraryWatcher>> = Mutex::new(None);
+
+/// Whether `dirs` are already being watched, in which case the database is up to date.
+pub fn is_watching(dirs: &[String]) -> bool {
+    WATCHER
+        .lock()
+        .map(|w| w.as_ref().is_some_and(|w| w.dirs == dirs))
+        .unwrap_or(false)
+}
+
+/// Watch `dirs` recursively, replacing the previous watcher.
+pub fn watch(dirs: Vec<String>) {
+    let (tx, rx) = mpsc::channel();
+    let mut watcher = match notify::recommended_watcher(tx) {
+        Ok(watcher) => watcher,
+        Err(e) => {
+            crate::error!("Failed to create library watcher: {}", e);
+            return;
+        }
+    };
+
+    for dir in &dirs {
+        if let Err(e) = watcher.watch(Path::new(dir), RecursiveMode::Recursive) {
+            crate::warn!("Failed to watch {}: {}", dir, e);
+        }
+    }
+
+    // Dropping the previous watcher closes its channel, which ends its thread.
+    std::thread::spawn(move || process_events(rx));
+    if let Ok(mut current) = WATCHER.lock() {
+        *current = Some(LibraryWatcher {
+            dirs,
+            _watcher: watcher,
+        });
+    }
+}
+
+/// Stop watching, so the next library request walks the directories again.
+pub fn stop() {
+    if let Ok(mut current) = WATCHER.lock() {
+        *current = None;
+    }
+}
+
+fn process_events(rx: Receiver<notify::Result<Event>>) {
+    while let Ok(event) = rx.recv() {
+        let mut changes = Changes::default();
+        changes.add(event);
+
+        let disconnected = loop {
+            match rx.recv_timeout(DEBOUNCE) {
+                Ok(event) => changes.add(event),
+                Err(RecvTimeoutError::Timeout) => break false,
+                Err(RecvTimeoutError::Disconnected) => break true,
+            }
+        };
+
+        tauri::async_runtime::block_on(changes.apply());
+        if disconnected {
+            return;
+        }
+    }
+}
+
+#[derive(Default)]
+struct Changes {
+    updated: HashSet<PathBuf>,
+    removed: HashSet<PathBuf>,
+}
+
+impl Changes {
+    fn add(&mut self, event: notify::Result<Event>) {
+        let event = match event {
+            Ok(event) => event,
+            Err(e) => {
+                crate::warn!("Library watcher error: {}", e);
+                return;
+            }
+        };
+
+        match event.kind {
+            EventKind::Create(_) => self.update(event.paths),
+            EventKind::Remove(_) => self.remove(event.paths),
+            EventKind::Modify(ModifyKind::Name(RenameMode::From)) => self.remove(event.paths),
+            EventKind::Modify(ModifyKind::Name(RenameMode::To)) => self.update(event.paths),
+            EventKind::Modify(ModifyKind::Name(RenameMode::Both)) => {
+                let mut paths = event.paths.into_iter();
+                self.remove(paths.next());
+                self.update(paths);
+            }
+            // Renames the backend couldn't pair up are decided by what's on disk now
+            EventKind::Modify(ModifyKind::Name(_)) => {
+                let (existing, gone): (Vec<_>, Vec<_>) =
+                    event.paths.into_iter().partition(|p| p.exists());
+                self.remove(gone);
+                self.update(existing);
+            }
+            EventKind::Modify(_) => self.update(event.paths),
+            _ => {}
+        }
+    }
+
+    fn update(&mut self, paths: impl IntoIterator<Item = PathBuf>) {
+        for path in paths {
+            self.removed.remove(&path);
+            self.updated.insert(path);
+        }
+    }
+
+    fn remove(&mut self, paths: impl IntoIterator<Item = PathBuf>) {
+        for path in paths {
+            self.updated.remove(&path);
+            self.removed.insert(path);
+        }
+    }
+
+    /// Feed the changed paths through the same pipeline as a full scan.
+    async fn apply(self) {
+        let roots = WATCHER
+            .lock()
+            .ok()
+            .and_then(|w| w.as_ref().map(|w| w.dirs.clone()))
+            .unwrap_or_default();
+        let filter = ScanRules::load().filter();
+
+        let mut files = Vec::new();
+        for path in self.updated {
+            if is_hidden(&path, &roots) || is_linked(&path, &roots, &filter) {
+                continue;
+            }
+            if path.is_dir() {
+                files.extend(scanner::scan_directories(vec![path.display().to_string()]));
+            } else if scanner::is_supported_file(&path) && filter.allows_file(&path) {
+                files.push(path);
+            }
+        }
+
+        // A path may have been removed and created again within the same batch
+        let removed: Vec<PathBuf> = self.removed.into_iter().filter(|p| !p.exists()).collect();
+
+        let mut changed = false;
+        if !files.is_empty() {
+            changed |= scanner::process_supported_files(&files, false)
+                .await
+                .updated;
+        }
+        if !removed.is_empty() {
+            changed |= database::delete_paths(&removed);
+        }
+        if !changed {
+            return;
+        }
+
+        crate::info!(
+            "Library updated from {} changed and {} removed paths",
+            files.len(),
+            removed.len()
+        );
+        if let Err(e) = Playlist::refresh_smart() {
+            crate::error!("Failed to refresh smart playlists: {}", e);
+        }
+        scanner::spawn_loudness_analysis();
+
+        app_handle()
+            .emit(crate::commands::route::MUSIC_LIBRARY_CHANGED, ())
+            .unwrap_or_else(|_| {
+                crate::error!(
+                    "Failed to emit {}",
+                    crate::commands::route::MUSIC_LIBRARY_CHANGED
+                )
+            });
+    }
+}
+
+/// Whether `path` or a directory between it and its watched root is hidden, as the scan
+/// doesn't enter hidden directories.
+fn is_hidden(path: &Path, roots: &[String]) -> bool {
+    // The innermost root wins when roots are nested
+    let below_root = roots
+        .iter()
+        .filter_map(|root| path.strip_prefix(root).ok())
+        .min_by_key(|relative| relative.components().count())
+        .or_else(|| path.file_name().map(Path::new))
+        .unwrap_or(path);
+    below_root.components().any(|component| {
+        component
+            .as_os_str()
+            .to_str()
+            .is_some_and(|name| name.starts_with('.'))
+    })
+}
+
+/// Whether `path` is reached through a symlinked directory below a root that doesn't follow
+/// symlinks, as the scan wouldn't enter it while the watcher still may.
+fn is_linked(path: &Path, roots: &[String], filter: &ScanFilter) -> bool {
+    let Some(root) = roots
+        .iter()
+        .map(Path::new)
+        .filter(|root| path.starts_with(root))
+        .max_by_key(|root| root.components().count())
+    else {
+        return false;
+    };
+    if filter.walk_options(root).follow_symlinks {
+        return false;
+    }
+
+    let is_symlink =
+        |p: &Path| std::fs::symlink_metadata(p).is_ok_and(|m| m.file_type().is_symlink());
+    // Symlinked files are read like the scan does, only directories are left out
+    (is_symlink(path) && path.is_dir())
+        || path
+            .ancestors()
+            .skip(1)
+            .take_while(|dir| *dir != root)
+            .any(is_symlink)
+}
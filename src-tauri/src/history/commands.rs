@@ -0,0 +1,45 @@
+use crate::history::history::{
+    AlbumPlays, ArtistPlays, PlayCount, PlayHistory, PlayRange, RecentPlay, TrackPlays,
+};
+
+const DEFAULT_LIMIT: u32 = 50;
+
+#[tauri::command]
+pub fn music_play_counts_get() -> Result<Vec<PlayCount>, String> {
+    PlayHistory::get_counts().map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn music_top_tracks_get(
+    since: Option<i64>,
+    until: Option<i64>,
+    limit: Option<u32>,
+) -> Result<Vec<TrackPlays>, String> {
+    PlayHistory::get_top_tracks(PlayRange { since, until }, limit.unwrap_or(DEFAULT_LIMIT))
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn music_top_artists_get(
+    since: Option<i64>,
+    until: Option<i64>,
+    limit: Option<u32>,
+) -> Result<Vec<ArtistPlays>, String> {
+    PlayHistory::get_top_artists(PlayRange { since, until }, limit.unwrap_or(DEFAULT_LIMIT))
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn music_top_albums_get(
+    since: Option<i64>,
+    until: Option<i64>,
+    limit: Option<u32>,
+) -> Result<Vec<AlbumPlays>, String> {
+    PlayHistory::get_top_albums(PlayRange { since, until }, limit.unwrap_or(DEFAULT_LIMIT))
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn music_recently_played_get(limit: Option<u32>) -> Result<Vec<RecentPlay>, String> {
+    PlayHistory::get_recent(limit.unwrap_or(DEFAULT_LIMIT)).map_err(|e| e.to_string())
+}
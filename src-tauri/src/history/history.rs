@@ -0,0 +1,249 @@
+use crate::database::database::GLOBAL_DATABASE;
+use crate::folder::database::{music_from_row, MUSIC_COLUMNS, MUSIC_COLUMN_COUNT};
+use crate::music::metadata::MusicMetadata;
+use rusqlite::params;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Time range in unix seconds, either end left open when `None`.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct PlayRange {
+    pub since: Option<i64>,
+    pub until: Option<i64>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct PlayCount {
+    pub path: String,
+    pub play_count: i64,
+    pub last_played_at: i64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct TrackPlays {
+    pub music: MusicMetadata,
+    pub play_count: i64,
+    pub last_played_at: i64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ArtistPlays {
+    pub artist: String,
+    pub play_count: i64,
+    pub last_played_at: i64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct AlbumPlays {
+    pub album: String,
+    pub album_artist: Option<String>,
+    pub play_count: i64,
+    pub last_played_at: i64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct RecentPlay {
+    pub music: MusicMetadata,
+    pub played_at: i64,
+}
+
+pub struct PlayHistory;
+
+/// Filter on `plays.played_at` for a `PlayRange` bound as ?1 and ?2.
+const RANGE_FILTER: &str = "(?1 IS NULL OR plays.played_at >= ?1)
+    AND (?2 IS NULL OR plays.played_at < ?2)";
+
+impl PlayHistory {
+    /// A track counts as played once it's been listened to for half its length or 4 minutes,
+    /// whichever comes first.
+    pub fn threshold_seconds(duration: f64) -> f64 {
+        (duration / 2.0).min(240.0)
+    }
+
+    pub fn record(path: &str) -> Result<(), rusqlite::Error> {
+        let conn_guard = GLOBAL_DATABASE.lock().unwrap();
+        let conn = conn_guard.as_ref().unwrap();
+
+        conn.execute(
+            "INSERT INTO plays (path, played_at) VALUES (?1, ?2)",
+            params![path, chrono::Utc::now().timestamp()],
+        )?;
+        Ok(())
+    }
+
+    /// Play count and last played time of every track that has been played at least once.
+    pub fn get_counts() -> Result<Vec<PlayCount>, rusqlite::Error> {
+        let conn_guard = GLOBAL_DATABASE.lock().unwrap();
+        let conn = conn_guard.as_ref().unwrap();
+
+        let mut stmt =
+            conn.prepare("SELECT path, COUNT(*), MAX(played_at) FROM plays GROUP BY path")?;
+        let counts = stmt
+            .query_map([], |row| {
+                Ok(PlayCount {
+                    path: row.get(0)?,
+                    play_count: row.get(1)?,
+                    last_played_at: row.get(2)?,
+                })
+            })?
+            .filter_map(|r| r.ok())
+            .collect();
+        Ok(counts)
+    }
+
+    pub fn get_top_tracks(
+        range: PlayRange,
+        limit: u32,
+    ) -> Result<Vec<TrackPlays>, rusqlite::Error> {
+        let conn_guard = GLOBAL_DATABASE.lock().unwrap();
+        let conn = conn_guard.as_ref().unwrap();
+
+        // Columns are picked from a subquery so `path` and `id` aren't ambiguous
+        let query = format!(
+            "SELECT {}, play_count, last_played_at FROM musics
+            JOIN (
+                SELECT plays.path AS play_path, COUNT(*) AS play_count,
+                    MAX(plays.played_at) AS last_played_at
+                FROM plays WHERE {} GROUP BY plays.path
+            ) ON play_path = musics.path
+            ORDER BY play_count DESC, last_played_at DESC
+            LIMIT ?3",
+            MUSIC_COLUMNS, RANGE_FILTER
+        );
+        let mut stmt = conn.prepare(&query)?;
+        let tracks = stmt
+            .query_map(params![range.since, range.until, limit], |row| {
+                Ok(TrackPlays {
+                    music: music_from_row(row)?,
+                    play_count: row.get(MUSIC_COLUMN_COUNT)?,
+                    last_played_at: row.get(MUSIC_COLUMN_COUNT + 1)?,
+                })
+            })?
+            .filter_map(|r| r.ok())
+            .collect();
+        Ok(tracks)
+    }
+
+    pub fn get_top_artists(
+        range: PlayRange,
+        limit: u32,
+    ) -> Result<Vec<ArtistPlays>, rusqlite::Error> {
+        let rows = Self::get_artist_tag_plays(range)?;
+
+        // Tracks with several artists count towards each of them
+        let mut artists: HashMap<String, ArtistPlays> = HashMap::new();
+        for (artist_tag, play_count, last_played_at) in rows {
+            for artist in artist_tag.split(MusicMetadata::artist_separator()) {
+                let artist = artist.trim();
+                if artist.is_empty() {
+                    continue;
+                }
+                let entry = artists
+                    .entry(artist.to_string())
+                    .or_insert_with(|| ArtistPlays {
+                        artist: artist.to_string(),
+                        play_count: 0,
+                        last_played_at: 0,
+                    });
+                entry.play_count += play_count;
+                entry.last_played_at = entry.last_played_at.max(last_played_at);
+            }
+        }
+
+        let mut artists: Vec<ArtistPlays> = artists.into_values().collect();
+        artists.sort_by(|a, b| {
+            b.play_count
+                .cmp(&a.play_count)
+                .then(b.last_played_at.cmp(&a.last_played_at))
+        });
+        artists.truncate(limit as usize);
+        Ok(artists)
+    }
+
+    /// Plays grouped by the raw artist tag, before it's split into single artists.
+    fn get_artist_tag_plays(range: PlayRange) -> Result<Vec<(String, i64, i64)>, rusqlite::Error> {
+        let conn_guard = GLOBAL_DATABASE.lock().unwrap();
+        let conn = conn_guard.as_ref().unwrap();
+
+        let query = format!(
+            "SELECT musics.artist, COUNT(*), MAX(plays.played_at) FROM plays
+            JOIN musics ON musics.path = plays.path
+            WHERE musics.artist IS NOT NULL AND {}
+            GROUP BY musics.artist",
+            RANGE_FILTER
+        );
+        let mut stmt = conn.prepare(&query)?;
+        let rows = stmt
+            .query_map(params![range.since, range.until], |row| {
+                Ok((row.get(0)?, row.get(1)?, row.get(2)?))
+            })?
+            .filter_map(|r| r.ok())
+            .collect();
+        Ok(rows)
+    }
+
+    pub fn get_top_albums(
+        range: PlayRange,
+        limit: u32,
+    ) -> Result<Vec<AlbumPlays>, rusqlite::Error> {
+        let conn_guard = GLOBAL_DATABASE.lock().unwrap();
+        let conn = conn_guard.as_ref().unwrap();
+
+        let query = format!(
+            "SELECT musics.album, COALESCE(musics.album_artist, musics.artist) AS album_artist,
+                COUNT(*) AS play_count, MAX(plays.played_at) AS last_played_at
+            FROM plays
+            JOIN musics ON musics.path = plays.path
+            WHERE musics.album IS NOT NULL AND {}
+            GROUP BY musics.album, album_artist
+            ORDER BY play_count DESC, last_played_at DESC
+            LIMIT ?3",
+            RANGE_FILTER
+        );
+        let mut stmt = conn.prepare(&query)?;
+        let albums = stmt
+            .query_map(params![range.since, range.until, limit], |row| {
+                Ok(AlbumPlays {
+                    album: row.get(0)?,
+                    album_artist: row.get(1)?,
+                    play_count: row.get(2)?,
+                    last_played_at: row.get(3)?,
+                })
+            })?
+            .filter_map(|r| r.ok())
+            .collect();
+        Ok(albums)
+    }
+
+    pub fn get_recent(limit: u32) -> Result<Vec<RecentPlay>, rusqlite::Error> {
+        let conn_guard = GLOBAL_DATABASE.lock().unwrap();
+        let conn = conn_guard.as_ref().unwrap();
+
+        let query = format!(
+            "SELECT {}, played_at FROM musics
+            JOIN (
+                SELECT plays.path AS play_path, plays.played_at, plays.id AS play_id FROM plays
+            ) ON play_path = musics.path
+            ORDER BY played_at DESC, play_id DESC
+            LIMIT ?1",
+            MUSIC_COLUMNS
+        );
+        let mut stmt = conn.prepare(&query)?;
+        let plays = stmt
+            .query_map(params![limit], |row| {
+                Ok(RecentPlay {
+                    music: music_from_row(row)?,
+                    played_at: row.get(MUSIC_COLUMN_COUNT)?,
+                })
+            })?
+            .filter_map(|r| r.ok())
+            .collect();
+        Ok(plays)
+    }
+}
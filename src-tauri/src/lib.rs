@@ -1,10 +1,13 @@
 // Core modules
 pub mod animated_background;
 mod api;
+mod cache;
 pub(crate) mod commands;
 mod coverart;
 mod database;
 mod folder;
+mod history;
+mod library;
 #[cfg(target_os = "linux")]
 mod linux_renderer;
 pub mod logger;
@@ -0,0 +1,31 @@
+use crate::library::library::{AlbumSummary, ArtistSummary, GenreSummary, Library};
+use crate::library::query::{MusicPage, MusicQuery};
+use crate::music::metadata::MusicMetadata;
+
+const DEFAULT_SEARCH_LIMIT: u32 = 100;
+
+#[tauri::command]
+pub fn music_artists_get() -> Result<Vec<ArtistSummary>, String> {
+    Library::get_artists().map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn music_albums_get() -> Result<Vec<AlbumSummary>, String> {
+    Library::get_albums().map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn music_genres_get() -> Result<Vec<GenreSummary>, String> {
+    Library::get_genres().map_err(|e| e.to_string())
+}
+
+/// One page of the library, filtered and sorted by the database.
+#[tauri::command]
+pub fn music_query(query: MusicQuery) -> Result<MusicPage, String> {
+    query.execute().map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn music_search(query: String, limit: Option<u32>) -> Result<Vec<MusicMetadata>, String> {
+    Library::search(&query, limit.unwrap_or(DEFAULT_SEARCH_LIMIT)).map_err(|e| e.to_string())
+}
@@ -0,0 +1,291 @@
+use crate::database::database::GLOBAL_DATABASE;
+use crate::folder::database::{music_from_row, MUSIC_COLUMNS};
+use crate::music::metadata::MusicMetadata;
+use rusqlite::{params, Transaction};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ArtistSummary {
+    pub id: i64,
+    pub name: String,
+    pub track_count: i64,
+    /// Total length of the tracks in milliseconds.
+    pub duration: i64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct AlbumSummary {
+    pub id: i64,
+    pub name: String,
+    pub artist: Option<String>,
+    pub track_count: i64,
+    /// Total length of the tracks in milliseconds.
+    pub duration: i64,
+    pub date: Option<String>,
+    /// First track of the album, for looking up its cover art.
+    pub path: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct GenreSummary {
+    pub id: i64,
+    pub name: String,
+    pub track_count: i64,
+    /// Total length of the tracks in milliseconds.
+    pub duration: i64,
+}
+
+/// A multi-value tag table with its join table on `musics`.
+struct TagTable {
+    table: &'static str,
+    link_table: &'static str,
+    link_column: &'static str,
+}
+
+const ARTISTS: TagTable = TagTable {
+    table: "artists",
+    link_table: "music_artists",
+    link_column: "artist_id",
+};
+
+const GENRES: TagTable = TagTable {
+    table: "genres",
+    link_table: "music_genres",
+    link_column: "genre_id",
+};
+
+pub struct Library;
+
+impl Library {
+    /// Point the track at `metadata.path` to its artists, album and genres, creating them
+    /// as needed. Entries left without tracks are removed by the database triggers.
+    pub fn link_music(tx: &Transaction, metadata: &MusicMetadata) -> rusqlite::Result<()> {
+        let music_id: i64 = tx.query_row(
+            "SELECT id FROM musics WHERE path = ?1",
+            params![metadata.path],
+            |row| row.get(0),
+        )?;
+
+        let album = metadata
+            .album
+            .as_deref()
+            .map(str::trim)
+            .filter(|a| !a.is_empty());
+        let album_id = match album {
+            Some(album) => {
+                // Without an album artist, the album goes under the track's primary artist
+                let artist = metadata
+                    .album_artist
+                    .as_deref()
+                    .map(str::trim)
+                    .filter(|a| !a.is_empty())
+                    .or_else(|| {
+                        split_tag(metadata.artist.as_deref().unwrap_or_default())
+                            .first()
+                            .copied()
+                    })
+                    .unwrap_or_default();
+                tx.prepare_cached("INSERT OR IGNORE INTO albums (name, artist) VALUES (?1, ?2)")?
+                    .execute(params![album, artist])?;
+                let id: i64 = tx
+                    .prepare_cached("SELECT id FROM albums WHERE name = ?1 AND artist = ?2")?
+                    .query_row(params![album, artist], |row| row.get(0))?;
+                Some(id)
+            }
+            None => None,
+        };
+        tx.prepare_cached("UPDATE musics SET album_id = ?1 WHERE id = ?2")?
+            .execute(params![album_id, music_id])?;
+
+        Self::link_tag(tx, &ARTISTS, music_id, metadata.artist.as_deref())?;
+        Self::link_tag(tx, &GENRES, music_id, metadata.genre.as_deref())?;
+        Ok(())
+    }
+
+    fn link_tag(
+        tx: &Transaction,
+        tag: &TagTable,
+        music_id: i64,
+        value: Option<&str>,
+    ) -> rusqlite::Result<()> {
+        let mut ids = Vec::new();
+        for (position, name) in split_tag(value.unwrap_or_default()).into_iter().enumerate() {
+            tx.prepare_cached(&format!(
+                "INSERT OR IGNORE INTO {} (name) VALUES (?1)",
+                tag.table
+            ))?
+            .execute(params![name])?;
+            let id: i64 = tx
+                .prepare_cached(&format!("SELECT id FROM {} WHERE name = ?1", tag.table))?
+                .query_row(params![name], |row| row.get(0))?;
+
+            tx.prepare_cached(&format!(
+                "INSERT INTO {0} (music_id, {1}, position) VALUES (?1, ?2, ?3)
+                ON CONFLICT (music_id, {1}) DO UPDATE SET position = excluded.position",
+                tag.link_table, tag.link_column
+            ))?
+            .execute(params![music_id, id, position as i64])?;
+            ids.push(id);
+        }
+
+        // Links are only dropped after the new ones exist, so kept entries don't get new ids
+        let linked: Vec<i64> = tx
+            .prepare_cached(&format!(
+                "SELECT {} FROM {} WHERE music_id = ?1",
+                tag.link_column, tag.link_table
+            ))?
+            .query_map(params![music_id], |row| row.get(0))?
+            .collect::<rusqlite::Result<_>>()?;
+        for id in linked.into_iter().filter(|id| !ids.contains(id)) {
+            tx.prepare_cached(&format!(
+                "DELETE FROM {} WHERE music_id = ?1 AND {} = ?2",
+                tag.link_table, tag.link_column
+            ))?
+            .execute(params![music_id, id])?;
+        }
+        Ok(())
+    }
+
+    /// Tracks matching every word of `text` as a prefix, ignoring case and diacritics, best
+    /// matches first. Title matches weigh the most, then artist and album.
+    pub fn search(text: &str, limit: u32) -> Result<Vec<MusicMetadata>, rusqlite::Error> {
+        let Some(expression) = match_expression(text) else {
+            return Ok(Vec::new());
+        };
+
+        let conn_guard = GLOBAL_DATABASE.lock().unwrap();
+        let conn = conn_guard.as_ref().unwrap();
+
+        // Columns are picked from a subquery so they aren't ambiguous with the FTS columns
+        let query = format!(
+            "SELECT {} FROM musics
+            JOIN (
+                SELECT rowid AS match_id,
+                    bm25(musics_fts, 10.0, 5.0, 5.0, 3.0, 1.0, 2.0) AS score
+                FROM musics_fts WHERE musics_fts MATCH ?1
+                ORDER BY score LIMIT ?2
+            ) ON match_id = musics.id
+            ORDER BY score",
+            MUSIC_COLUMNS
+        );
+        let mut stmt = conn.prepare(&query)?;
+        let musics = stmt
+            .query_map(params![expression, limit], music_from_row)?
+            .filter_map(|r| r.ok())
+            .collect();
+        Ok(musics)
+    }
+
+    pub fn get_artists() -> Result<Vec<ArtistSummary>, rusqlite::Error> {
+        let conn_guard = GLOBAL_DATABASE.lock().unwrap();
+        let conn = conn_guard.as_ref().unwrap();
+
+        let mut stmt = conn.prepare(
+            "SELECT artists.id, artists.name, COUNT(*), IFNULL(SUM(musics.duration), 0)
+            FROM artists
+            JOIN music_artists ON music_artists.artist_id = artists.id
+            JOIN musics ON musics.id = music_artists.music_id
+            GROUP BY artists.id
+            ORDER BY artists.name",
+        )?;
+        let artists = stmt
+            .query_map([], |row| {
+                Ok(ArtistSummary {
+                    id: row.get(0)?,
+                    name: row.get(1)?,
+                    track_count: row.get(2)?,
+                    duration: row.get(3)?,
+                })
+            })?
+            .filter_map(|r| r.ok())
+            .collect();
+        Ok(artists)
+    }
+
+    pub fn get_albums() -> Result<Vec<AlbumSummary>, rusqlite::Error> {
+        let conn_guard = GLOBAL_DATABASE.lock().unwrap();
+        let conn = conn_guard.as_ref().unwrap();
+
+        let mut stmt = conn.prepare(
+            "SELECT albums.id, albums.name, NULLIF(albums.artist, ''), COUNT(*),
+                IFNULL(SUM(musics.duration), 0), MAX(musics.date), MIN(musics.path)
+            FROM albums
+            JOIN musics ON musics.album_id = albums.id
+            GROUP BY albums.id
+            ORDER BY albums.name, albums.artist",
+        )?;
+        let albums = stmt
+            .query_map([], |row| {
+                Ok(AlbumSummary {
+                    id: row.get(0)?,
+                    name: row.get(1)?,
+                    artist: row.get(2)?,
+                    track_count: row.get(3)?,
+                    duration: row.get(4)?,
+                    date: row.get(5)?,
+                    path: row.get(6)?,
+                })
+            })?
+            .filter_map(|r| r.ok())
+            .collect();
+        Ok(albums)
+    }
+
+    pub fn get_genres() -> Result<Vec<GenreSummary>, rusqlite::Error> {
+        let conn_guard = GLOBAL_DATABASE.lock().unwrap();
+        let conn = conn_guard.as_ref().unwrap();
+
+        let mut stmt = conn.prepare(
+            "SELECT genres.id, genres.name, COUNT(*), IFNULL(SUM(musics.duration), 0)
+            FROM genres
+            JOIN music_genres ON music_genres.genre_id = genres.id
+            JOIN musics ON musics.id = music_genres.music_id
+            GROUP BY genres.id
+            ORDER BY genres.name",
+        )?;
+        let genres = stmt
+            .query_map([], |row| {
+                Ok(GenreSummary {
+                    id: row.get(0)?,
+                    name: row.get(1)?,
+                    track_count: row.get(2)?,
+                    duration: row.get(3)?,
+                })
+            })?
+            .filter_map(|r| r.ok())
+            .collect();
+        Ok(genres)
+    }
+}
+
+/// Build an FTS5 query requiring every word of `text`, each matched as a prefix.
+/// Words are quoted so characters such as `-` or `*` aren't read as query syntax.
+fn match_expression(text: &str) -> Option<String> {
+    let terms: Vec<String> = text
+        .split_whitespace()
+        .filter(|word| word.chars().any(char::is_alphanumeric))
+        .map(|word| format!("\"{}\"*", word.replace('"', "\"\"")))
+        .collect();
+    if terms.is_empty() {
+        return None;
+    }
+    Some(terms.join(" "))
+}
+
+/// Split a multi-value tag on the artist separator, dropping empty and repeated values.
+pub fn split_tag(value: &str) -> Vec<&str> {
+    let mut values: Vec<&str> = Vec::new();
+    for v in value.split(MusicMetadata::artist_separator()) {
+        let v = v.trim();
+        let repeated = values
+            .iter()
+            .any(|existing| existing.eq_ignore_ascii_case(v));
+        if !v.is_empty() && !repeated {
+            values.push(v);
+        }
+    }
+    values
+}
@@ -0,0 +1,162 @@
+use crate::database::database::GLOBAL_DATABASE;
+use crate::folder::database::{music_from_row, MUSIC_COLUMNS};
+use crate::folder::utils::path_prefix_range;
+use crate::music::metadata::MusicMetadata;
+use crate::playlist::smart::{escape_like, SmartField};
+use rusqlite::params_from_iter;
+use rusqlite::types::Value;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(rename_all = "camelCase", default)]
+pub struct MusicQuery {
+    pub filter: MusicFilter,
+    /// Applied in order, with the track id as the final tie-breaker so pages are stable.
+    pub sort: Vec<MusicSortKey>,
+    pub offset: u32,
+    /// All remaining tracks are returned when `None`.
+    pub limit: Option<u32>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(rename_all = "camelCase", default)]
+pub struct MusicFilter {
+    pub artist_id: Option<i64>,
+    pub album_id: Option<i64>,
+    pub genre_id: Option<i64>,
+    /// Only tracks inside this directory, at any depth.
+    pub folder: Option<String>,
+    pub year_from: Option<i32>,
+    pub year_to: Option<i32>,
+    /// File extensions such as `flac`, any of which may match.
+    pub formats: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy)]
+#[serde(rename_all = "camelCase")]
+pub struct MusicSortKey {
+    pub field: SmartField,
+    #[serde(default)]
+    pub descending: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct MusicPage {
+    pub musics: Vec<MusicMetadata>,
+    /// Number of tracks matching the filter, ignoring the paging.
+    pub total: i64,
+}
+
+/// Year of `musics.date`, which holds either a year or a full date. Kept identical to the
+/// expression index so SQLite can use it.
+const YEAR_COLUMN: &str = "CAST(substr(musics.date, 1, 4) AS INTEGER)";
+
+impl MusicFilter {
+    /// Build a `WHERE` expression, pushing the bound values onto `params` in order.
+    fn to_sql(&self, params: &mut Vec<Value>) -> String {
+        let mut conditions = Vec::new();
+
+        if let Some(artist_id) = self.artist_id {
+            conditions.push(
+                "musics.id IN (SELECT music_id FROM music_artists WHERE artist_id = ?)".to_string(),
+            );
+            params.push(Value::Integer(artist_id));
+        }
+        if let Some(album_id) = self.album_id {
+            conditions.push("musics.album_id = ?".to_string());
+            params.push(Value::Integer(album_id));
+        }
+        if let Some(genre_id) = self.genre_id {
+            conditions.push(
+                "musics.id IN (SELECT music_id FROM music_genres WHERE genre_id = ?)".to_string(),
+            );
+            params.push(Value::Integer(genre_id));
+        }
+        if let Some(folder) = &self.folder {
+            let (start, end) = path_prefix_range(folder);
+            conditions.push("musics.path >= ? AND musics.path < ?".to_string());
+            params.push(Value::Text(start));
+            params.push(Value::Text(end));
+        }
+        if let Some(year_from) = self.year_from {
+            conditions.push(format!("{} >= ?", YEAR_COLUMN));
+            params.push(Value::Integer(year_from as i64));
+        }
+        if let Some(year_to) = self.year_to {
+            conditions.push(format!("{} <= ?", YEAR_COLUMN));
+            params.push(Value::Integer(year_to as i64));
+        }
+
+        let formats: Vec<&str> = self
+            .formats
+            .iter()
+            .map(|f| f.trim().trim_start_matches('.'))
+            .filter(|f| !f.is_empty())
+            .collect();
+        if !formats.is_empty() {
+            let patterns = formats
+                .iter()
+                .map(|format| {
+                    params.push(Value::Text(format!("%.{}", escape_like(format))));
+                    "musics.path LIKE ? ESCAPE '\\'"
+                })
+                .collect::<Vec<_>>()
+                .join(" OR ");
+            conditions.push(format!("({})", patterns));
+        }
+
+        if conditions.is_empty() {
+            return "1".to_string();
+        }
+        conditions.join(" AND ")
+    }
+}
+
+impl MusicQuery {
+    pub fn execute(&self) -> Result<MusicPage, rusqlite::Error> {
+        let conn_guard = GLOBAL_DATABASE.lock().unwrap();
+        let conn = conn_guard.as_ref().unwrap();
+
+        let mut params = Vec::new();
+        let filter = self.filter.to_sql(&mut params);
+
+        let total: i64 = conn.query_row(
+            &format!("SELECT COUNT(*) FROM musics WHERE {}", filter),
+            params_from_iter(params.iter()),
+            |row| row.get(0),
+        )?;
+
+        let mut order: Vec<String> = self
+            .sort
+            .iter()
+            .map(|key| {
+                let collate = if key.field.is_text() {
+                    " COLLATE NOCASE"
+                } else {
+                    ""
+                };
+                let direction = if key.descending { "DESC" } else { "ASC" };
+                format!("{}{} {}", key.field.column(), collate, direction)
+            })
+            .collect();
+        order.push("musics.id".to_string());
+
+        // SQLite needs a LIMIT before an OFFSET, where -1 means no limit
+        params.push(Value::Integer(self.limit.map(|l| l as i64).unwrap_or(-1)));
+        params.push(Value::Integer(self.offset as i64));
+        let query = format!(
+            "SELECT {} FROM musics WHERE {} ORDER BY {} LIMIT ? OFFSET ?",
+            MUSIC_COLUMNS,
+            filter,
+            order.join(", ")
+        );
+
+        let mut stmt = conn.prepare(&query)?;
+        let musics = stmt
+            .query_map(params_from_iter(params), music_from_row)?
+            .filter_map(|r| r.ok())
+            .collect();
+        Ok(MusicPage { musics, total })
+    }
+}
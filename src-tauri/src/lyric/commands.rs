@@ -1,4 +1,4 @@
-use crate::lyric::{cache, queue, request, types::*};
+use crate::lyric::{queue, request, types::*};
 use crate::music::metadata::MusicMetadata;
 use regex::Regex;
 use std::fs;
@@ -45,17 +45,16 @@ pub async fn lyric_get(query: LyricQuery) -> Option<String> {
 
     // 3. Try cache/API with queue system
     let cache_key = request::generate_cache_key(&query);
-    let cache_path = format!("{}/{}", cache::get_cache_directory(), cache_key);
 
     // Check if there's an existing queue entry
     if let Some(queue_item) = queue::get_queue(&cache_key) {
         match queue_item.status {
             LyricRequestStatus::Loaded => {
                 // Already fetched, return from cache
-                if let Ok(lyrics) = fs::read_to_string(&cache_path) {
+                if let Some(lyrics) = request::read_cache(&cache_key) {
                     return Some(lyrics);
                 }
-                crate::warn!("Lyrics cache file not found: {}", cache_key);
+                crate::warn!("Lyrics cache entry not found: {}", cache_key);
                 return None;
             }
             LyricRequestStatus::Failed => {
@@ -68,10 +67,10 @@ pub async fn lyric_get(query: LyricQuery) -> Option<String> {
                 let status = queue::wait_for_result(&cache_key).await;
                 match status {
                     LyricRequestStatus::Loaded => {
-                        if let Ok(lyrics) = fs::read_to_string(&cache_path) {
+                        if let Some(lyrics) = request::read_cache(&cache_key) {
                             return Some(lyrics);
                         }
-                        crate::warn!("Lyrics cache file not found: {}", cache_key);
+                        crate::warn!("Lyrics cache entry not found: {}", cache_key);
                         return None;
                     }
                     _ => {
@@ -85,7 +84,7 @@ pub async fn lyric_get(query: LyricQuery) -> Option<String> {
 
     // No queue entry - we're the first request
     // Try cache first
-    if let Ok(lyrics) = fs::read_to_string(&cache_path) {
+    if let Some(lyrics) = request::read_cache(&cache_key) {
         queue::set_status(cache_key.clone(), LyricRequestStatus::Loaded);
         return Some(lyrics);
     }
@@ -1,4 +1,3 @@
-pub mod cache;
 pub mod commands;
 pub mod queue;
 pub mod request;
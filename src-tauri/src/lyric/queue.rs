@@ -51,6 +51,14 @@ pub fn remove(name: &str) {
     LYRIC_QUEUE.lock().unwrap().remove(name);
 }
 
+/// Forget finished requests, so they are read or fetched again
+pub fn clear() {
+    LYRIC_QUEUE
+        .lock()
+        .unwrap()
+        .retain(|_, entry| entry.request.status == LyricRequestStatus::Pending);
+}
+
 /// Wait for a pending request to complete (become Loaded or Failed)
 pub async fn wait_for_result(name: &str) -> LyricRequestStatus {
     let receiver = {
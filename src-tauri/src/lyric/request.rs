@@ -1,68 +1,6 @@
-use crate::lyric::{cache, types::*};
-use std::fs;
-
-/// Normalize string for comparison (lowercase, no punctuation, normalized whitespace)
-fn normalize_string(s: &str) -> String {
-    s.to_lowercase()
-        .chars()
-        .filter(|c| c.is_alphanumeric() || c.is_whitespace())
-        .collect::<String>()
-        .split_whitespace()
-        .collect::<Vec<_>>()
-        .join(" ")
-}
-
-/// Calculate Levenshtein distance between two strings
-fn levenshtein_distance(s1: &str, s2: &str) -> usize {
-    let s1_chars: Vec<char> = s1.chars().collect();
-    let s2_chars: Vec<char> = s2.chars().collect();
-    let m = s1_chars.len();
-    let n = s2_chars.len();
-
-    if m == 0 {
-        return n;
-    }
-    if n == 0 {
-        return m;
-    }
-
-    let mut matrix = vec![vec![0usize; m + 1]; n + 1];
-
-    for i in 0..=m {
-        matrix[0][i] = i;
-    }
-    for j in 0..=n {
-        matrix[j][0] = j;
-    }
-
-    for j in 1..=n {
-        for i in 1..=m {
-            let indicator = if s1_chars[i - 1] == s2_chars[j - 1] {
-                0
-            } else {
-                1
-            };
-            matrix[j][i] = (matrix[j][i - 1] + 1)
-                .min(matrix[j - 1][i] + 1)
-                .min(matrix[j - 1][i - 1] + indicator);
-        }
-    }
-
-    matrix[n][m]
-}
-
-/// Calculate string similarity (0.0 to 1.0)
-fn calculate_similarity(s1: &str, s2: &str) -> f64 {
-    let longer = if s1.len() > s2.len() { s1 } else { s2 };
-    let shorter = if s1.len() > s2.len() { s2 } else { s1 };
-
-    if longer.is_empty() {
-        return 1.0;
-    }
-
-    let distance = levenshtein_distance(longer, shorter);
-    (longer.len() - distance) as f64 / longer.len() as f64
-}
+use crate::cache::cache::{Cache, CacheKind};
+use crate::lyric::types::*;
+use crate::utils::similarity::{calculate_similarity, normalize_string};
 
 /// Score a lyric result against the query (ultra-strict mode with duration check)
 fn score_result(result: &LrcLibResult, query: &LyricQuery) -> f64 {
@@ -174,15 +112,16 @@ pub async fn request_lyrics(query: LyricQuery) -> Option<String> {
 
     // Save to cache
     let cache_key = generate_cache_key(&query);
-    let cache_path = format!("{}/{}", cache::get_cache_directory(), cache_key);
-
-    if let Err(e) = fs::write(&cache_path, &lyrics) {
-        crate::warn!("Failed to cache lyrics: {}", e);
-    }
+    Cache::put(CacheKind::Lyrics, &entry_key(&cache_key), lyrics.as_bytes());
 
     Some(lyrics)
 }
 
+/// Lyrics fetched before for `cache_key`
+pub fn read_cache(cache_key: &str) -> Option<String> {
+    String::from_utf8(Cache::get(&entry_key(cache_key))?).ok()
+}
+
 /// Generate a cache key from the query
 pub fn generate_cache_key(query: &LyricQuery) -> String {
     let primary_artist = query.artist.split(" • ").next().unwrap_or(&query.artist);
@@ -198,3 +137,8 @@ pub fn generate_cache_key(query: &LyricQuery) -> String {
         })
         .collect()
 }
+
+/// Key of the lyrics in the shared cache
+fn entry_key(cache_key: &str) -> String {
+    format!("lyrics:{}", cache_key)
+}
@@ -10,8 +10,19 @@ pub const BASS_ACTIVE_PLAYING: u32 = 1;
 #[allow(dead_code)]
 pub const BASS_ACTIVE_PAUSED: u32 = 3;
 pub const BASS_POS_BYTE: u32 = 0;
+pub const BASS_DEVICE_ENABLED: u32 = 1;
+pub const BASS_DEVICE_DEFAULT: u32 = 2;
+pub const BASS_DEVICE_REINIT: u32 = 128;
+pub const BASS_DEVICE_FREQ: u32 = 0x4000;
+#[cfg(target_os = "linux")]
+pub const BASS_DEVICE_HOG: u32 = 0x10000;
+pub const BASS_ORIGRES_BITS_MASK: u32 = 0xffff;
+pub const BASS_ERROR_ALREADY: i32 = 14;
 pub const BASS_ATTRIB_VOL: u32 = 2;
 
+pub const BASS_DSP_PRIORITY_EQUALIZER: i32 = 0;
+
+pub const BASS_SYNC_POS: u32 = 0;
 pub const BASS_SYNC_END: u32 = 2;
 pub const BASS_SYNC_FREE: u32 = 0x10000;
 pub const BASS_SYNC_MIXTIME: u32 = 0x40000000;
@@ -43,6 +54,19 @@ pub struct BASS_INFO {
     pub freq: u32,
 }
 
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct BASS_CHANNELINFO {
+    pub freq: u32,
+    pub chans: u32,
+    pub flags: u32,
+    pub ctype: u32,
+    pub origres: u32,
+    pub plugin: u32,
+    pub sample: u32,
+    pub filename: *const std::ffi::c_char,
+}
+
 #[cfg(desktop)]
 #[link(name = "bass")]
 #[link(name = "bassmix")]
@@ -56,6 +80,10 @@ extern "C" {
     ) -> i32;
     pub fn BASS_GetDeviceInfo(device: u32, info: *mut BASS_DEVICEINFO) -> u32;
     pub fn BASS_GetInfo(info: *mut BASS_INFO) -> u32;
+    pub fn BASS_GetDevice() -> u32;
+    pub fn BASS_SetDevice(device: u32) -> i32;
+    pub fn BASS_ChannelSetDevice(handle: u32, device: u32) -> i32;
+    pub fn BASS_ChannelGetInfo(handle: u32, info: *mut BASS_CHANNELINFO) -> i32;
     pub fn BASS_PluginLoad(file: *const std::ffi::c_char, flags: u32) -> u32;
     #[allow(dead_code)]
     pub fn BASS_PluginFree(handle: u32) -> i32;
@@ -76,13 +104,13 @@ extern "C" {
     pub fn BASS_ChannelPause(handle: u32) -> i32;
     pub fn BASS_ChannelStop(handle: u32) -> i32;
     pub fn BASS_ChannelIsActive(handle: u32) -> u32;
-    #[allow(dead_code)]
     pub fn BASS_ChannelGetLength(handle: u32, mode: u32) -> u64;
     pub fn BASS_ChannelGetPosition(handle: u32, mode: u32) -> u64;
     pub fn BASS_ChannelSetPosition(handle: u32, pos: u64, mode: u32) -> i32;
     pub fn BASS_ChannelBytes2Seconds(handle: u32, pos: u64) -> f64;
     pub fn BASS_ChannelSeconds2Bytes(handle: u32, pos: f64) -> u64;
     pub fn BASS_ChannelSetAttribute(handle: u32, attrib: u32, value: f32) -> i32;
+    pub fn BASS_ChannelSlideAttribute(handle: u32, attrib: u32, value: f32, time: u32) -> i32;
     #[allow(dead_code)]
     pub fn BASS_ChannelGetAttribute(handle: u32, attrib: u32, value: *mut f32) -> i32;
     pub fn BASS_ErrorGetCode() -> i32;
@@ -96,6 +124,21 @@ extern "C" {
         >,
         user: *mut std::ffi::c_void,
     ) -> u32;
+    pub fn BASS_ChannelSetDSP(
+        handle: u32,
+        proc_: Option<
+            unsafe extern "C" fn(
+                handle: u32,
+                channel: u32,
+                buffer: *mut std::ffi::c_void,
+                length: u32,
+                user: *mut std::ffi::c_void,
+            ),
+        >,
+        user: *mut std::ffi::c_void,
+        priority: i32,
+    ) -> u32;
+    pub fn BASS_ChannelRemoveDSP(handle: u32, dsp: u32) -> i32;
 }
 
 // Android BASS library loaded dynamically
@@ -118,11 +161,13 @@ pub mod bass_android {
         pub bass_channel_pause: unsafe extern "C" fn(u32) -> i32,
         pub bass_channel_stop: unsafe extern "C" fn(u32) -> i32,
         pub bass_channel_is_active: unsafe extern "C" fn(u32) -> u32,
+        pub bass_channel_get_length: unsafe extern "C" fn(u32, u32) -> u64,
         pub bass_channel_get_position: unsafe extern "C" fn(u32, u32) -> u64,
         pub bass_channel_set_position: unsafe extern "C" fn(u32, u64, u32) -> i32,
         pub bass_channel_bytes2seconds: unsafe extern "C" fn(u32, u64) -> f64,
         pub bass_channel_seconds2bytes: unsafe extern "C" fn(u32, f64) -> u64,
         pub bass_channel_set_attribute: unsafe extern "C" fn(u32, u32, f32) -> i32,
+        pub bass_channel_slide_attribute: unsafe extern "C" fn(u32, u32, f32, u32) -> i32,
         pub bass_error_get_code: unsafe extern "C" fn() -> i32,
         pub bass_free: unsafe extern "C" fn() -> i32,
         pub bass_channel_set_sync: unsafe extern "C" fn(
@@ -194,6 +239,9 @@ pub mod bass_android {
             let bass_channel_is_active_fn: unsafe extern "C" fn(u32) -> u32 = *bass
                 .get::<unsafe extern "C" fn(u32) -> u32>(b"BASS_ChannelIsActive")
                 .map_err(|e| format!("Failed to load BASS_ChannelIsActive: {}", e))?;
+            let bass_channel_get_length_fn: unsafe extern "C" fn(u32, u32) -> u64 = *bass
+                .get::<unsafe extern "C" fn(u32, u32) -> u64>(b"BASS_ChannelGetLength")
+                .map_err(|e| format!("Failed to load BASS_ChannelGetLength: {}", e))?;
             let bass_channel_get_position_fn: unsafe extern "C" fn(u32, u32) -> u64 = *bass
                 .get::<unsafe extern "C" fn(u32, u32) -> u64>(b"BASS_ChannelGetPosition")
                 .map_err(|e| format!("Failed to load BASS_ChannelGetPosition: {}", e))?;
@@ -209,6 +257,12 @@ pub mod bass_android {
             let bass_channel_set_attribute_fn: unsafe extern "C" fn(u32, u32, f32) -> i32 = *bass
                 .get::<unsafe extern "C" fn(u32, u32, f32) -> i32>(b"BASS_ChannelSetAttribute")
                 .map_err(|e| format!("Failed to load BASS_ChannelSetAttribute: {}", e))?;
+            let bass_channel_slide_attribute_fn: unsafe extern "C" fn(u32, u32, f32, u32) -> i32 =
+                *bass
+                    .get::<unsafe extern "C" fn(u32, u32, f32, u32) -> i32>(
+                        b"BASS_ChannelSlideAttribute",
+                    )
+                    .map_err(|e| format!("Failed to load BASS_ChannelSlideAttribute: {}", e))?;
             let bass_error_get_code_fn: unsafe extern "C" fn() -> i32 = *bass
                 .get::<unsafe extern "C" fn() -> i32>(b"BASS_ErrorGetCode")
                 .map_err(|e| format!("Failed to load BASS_ErrorGetCode: {}", e))?;
@@ -256,11 +310,13 @@ pub mod bass_android {
                 bass_channel_pause: bass_channel_pause_fn,
                 bass_channel_stop: bass_channel_stop_fn,
                 bass_channel_is_active: bass_channel_is_active_fn,
+                bass_channel_get_length: bass_channel_get_length_fn,
                 bass_channel_get_position: bass_channel_get_position_fn,
                 bass_channel_set_position: bass_channel_set_position_fn,
                 bass_channel_bytes2seconds: bass_channel_bytes2seconds_fn,
                 bass_channel_seconds2bytes: bass_channel_seconds2bytes_fn,
                 bass_channel_set_attribute: bass_channel_set_attribute_fn,
+                bass_channel_slide_attribute: bass_channel_slide_attribute_fn,
                 bass_error_get_code: bass_error_get_code_fn,
                 bass_free: bass_free_fn,
                 bass_channel_set_sync: bass_channel_set_sync_fn,
@@ -5,7 +5,10 @@ use tauri::Emitter;
 #[cfg(desktop)]
 use tauri_plugin_dialog::DialogExt;
 
-use crate::state::{app_handle, app_store};
+#[cfg(desktop)]
+use crate::folder::roots::MusicRoot;
+#[cfg(desktop)]
+use crate::state::app_handle;
 
 pub static MUSIC_STORE_PATH_NAME: &str = "music-path";
 
@@ -21,7 +24,9 @@ pub fn music_directory_request(app: AppHandle) {
             .into_string()
             .expect("Failed to get music dir path.");
 
-        app_store().set(MUSIC_STORE_PATH_NAME, dir);
+        if let Err(e) = MusicRoot::add(&dir, None) {
+            crate::warn!("Failed to add music root: {}", e);
+        }
 
         app_handle()
             .emit(crate::commands::route::MUSIC_DIRECTORY_REQUEST, ())
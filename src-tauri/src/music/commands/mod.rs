@@ -1,5 +1,7 @@
 pub mod directory;
 pub mod metadata;
+#[cfg(desktop)]
+pub mod output;
 pub mod player;
 pub mod queue;
 pub mod visualizer;
@@ -7,6 +9,8 @@ pub mod visualizer;
 // Re-export all command functions for easy access
 pub use directory::*;
 pub use metadata::*;
+#[cfg(desktop)]
+pub use output::*;
 pub use player::*;
 pub use queue::*;
 pub use visualizer::*;
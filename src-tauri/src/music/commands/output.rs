@@ -0,0 +1,14 @@
+use tauri::State;
+
+use crate::music::output_device::OutputDevice;
+use crate::state::AppState;
+
+#[tauri::command]
+pub fn music_output_devices_get() -> Vec<OutputDevice> {
+    OutputDevice::get_all()
+}
+
+#[tauri::command]
+pub fn music_output_device_set(state: State<AppState>, index: Option<u32>) -> Result<(), String> {
+    state.music_player.set_output_device(index)
+}
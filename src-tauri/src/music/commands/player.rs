@@ -2,7 +2,10 @@ use tauri::State;
 
 use crate::state::AppState;
 
+use crate::music::crossfade::CrossfadeSettings;
+use crate::music::equalizer::{EqualizerPreset, EqualizerSettings};
 use crate::music::player::RepeatMode;
+use crate::music::replay_gain::ReplayGainSettings;
 
 #[tauri::command]
 pub fn music_play(state: State<AppState>) {
@@ -34,6 +37,11 @@ pub fn music_repeat_mode_set(state: State<AppState>, mode: RepeatMode) {
     state.music_player.set_repeat_mode(mode);
 }
 
+#[tauri::command]
+pub fn music_shuffle_set(state: State<AppState>, enable: bool) {
+    state.music_player.set_shuffle(enable);
+}
+
 #[tauri::command]
 pub fn music_position_set(state: State<AppState>, position: u64) {
     state.music_player.set_pos(position);
@@ -70,3 +78,70 @@ pub fn music_equalizer(state: State<AppState>, values: Vec<f32>) {
 pub fn music_equalizer_reset(state: State<AppState>) {
     state.music_player.reset_equalizer();
 }
+
+#[cfg(desktop)]
+#[tauri::command]
+pub fn music_equalizer_get(state: State<AppState>) -> EqualizerSettings {
+    state.music_player.get_equalizer()
+}
+
+#[cfg(desktop)]
+#[tauri::command]
+pub fn music_equalizer_set(state: State<AppState>, settings: EqualizerSettings) {
+    state.music_player.set_equalizer(settings);
+}
+
+#[cfg(desktop)]
+#[tauri::command]
+pub fn music_equalizer_preset_all_get() -> Vec<EqualizerPreset> {
+    EqualizerPreset::get_all()
+}
+
+#[cfg(desktop)]
+#[tauri::command]
+pub fn music_equalizer_preset_save(state: State<AppState>, name: String) -> Result<(), String> {
+    let name = name.trim().to_string();
+    if name.is_empty() {
+        return Err("Preset name cannot be empty".to_string());
+    }
+    EqualizerPreset::save(name, state.music_player.get_equalizer());
+    Ok(())
+}
+
+#[cfg(desktop)]
+#[tauri::command]
+pub fn music_equalizer_preset_apply(state: State<AppState>, name: String) -> Result<(), String> {
+    let settings =
+        EqualizerPreset::get(&name).ok_or_else(|| format!("Preset not found: {}", name))?;
+    state.music_player.set_equalizer(settings);
+    Ok(())
+}
+
+#[cfg(desktop)]
+#[tauri::command]
+pub fn music_equalizer_preset_delete(name: String) -> Result<(), String> {
+    if !EqualizerPreset::delete(&name) {
+        return Err(format!("Preset not found: {}", name));
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub fn music_replay_gain_get(state: State<AppState>) -> ReplayGainSettings {
+    state.music_player.get_replay_gain()
+}
+
+#[tauri::command]
+pub fn music_replay_gain_set(state: State<AppState>, settings: ReplayGainSettings) {
+    state.music_player.set_replay_gain(settings);
+}
+
+#[tauri::command]
+pub fn music_crossfade_get(state: State<AppState>) -> CrossfadeSettings {
+    state.music_player.get_crossfade()
+}
+
+#[tauri::command]
+pub fn music_crossfade_set(state: State<AppState>, settings: CrossfadeSettings) {
+    state.music_player.set_crossfade(settings);
+}
@@ -2,6 +2,11 @@ use tauri::State;
 
 use crate::{music::metadata::MusicMetadata, state::AppState};
 
+#[tauri::command]
+pub fn music_queue_get(state: State<AppState>) -> Vec<MusicMetadata> {
+    state.music_player.get_queue()
+}
+
 #[tauri::command]
 pub fn music_queue_add(state: State<AppState>, playlist: Vec<MusicMetadata>) {
     state.music_player.add_playlist(playlist);
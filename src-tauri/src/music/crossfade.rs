@@ -0,0 +1,55 @@
+use serde::{Deserialize, Serialize};
+
+use crate::music::metadata::MusicMetadata;
+use crate::state::app_store;
+
+const STORE_KEY_CROSSFADE: &str = "crossfade";
+
+const MAX_DURATION_MS: u32 = 12_000;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CrossfadeSettings {
+    /// Overlap between tracks in milliseconds, 0 keeps gapless playback.
+    pub duration: u32,
+    /// Play consecutive tracks of the same album gaplessly, e.g. live or concept albums.
+    pub skip_same_album: bool,
+}
+
+impl CrossfadeSettings {
+    pub fn load() -> Self {
+        app_store()
+            .get(STORE_KEY_CROSSFADE)
+            .and_then(|v| serde_json::from_value::<Self>(v).ok())
+            .map(Self::clamped)
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self) {
+        let store = app_store();
+        store.set(STORE_KEY_CROSSFADE, serde_json::json!(self));
+        let _ = store.save();
+    }
+
+    pub fn clamped(self) -> Self {
+        Self {
+            duration: self.duration.min(MAX_DURATION_MS),
+            ..self
+        }
+    }
+
+    pub fn should_crossfade(&self, from: &MusicMetadata, to: &MusicMetadata) -> bool {
+        if self.duration == 0 {
+            return false;
+        }
+        !(self.skip_same_album && Self::is_same_album(from, to))
+    }
+
+    fn is_same_album(from: &MusicMetadata, to: &MusicMetadata) -> bool {
+        let album_artist = |m: &MusicMetadata| m.album_artist.clone().or_else(|| m.artist.clone());
+        match (&from.album, &to.album) {
+            (Some(a), Some(b)) => a == b && album_artist(from) == album_artist(to),
+            _ => false,
+        }
+    }
+}
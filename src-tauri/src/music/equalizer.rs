@@ -0,0 +1,424 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use crate::state::app_store;
+
+const STORE_KEY_SETTINGS: &str = "equalizer-settings";
+const STORE_KEY_PRESETS: &str = "equalizer-presets";
+
+/// Center frequencies of the default 18-band layout used by the frontend.
+pub const EQUALIZER_FREQUENCIES: [f32; 18] = [
+    65.0, 92.0, 131.0, 185.0, 262.0, 370.0, 523.0, 740.0, 1047.0, 1480.0, 2093.0, 2960.0, 4186.0,
+    5920.0, 8372.0, 11840.0, 16744.0, 20000.0,
+];
+
+/// Roughly half an octave wide, which matches the spacing of the default bands.
+pub const EQUALIZER_DEFAULT_Q: f32 = 2.0;
+
+const MAX_GAIN_DB: f32 = 24.0;
+const MIN_Q: f32 = 0.1;
+const MAX_Q: f32 = 18.0;
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EqualizerBand {
+    pub frequency: f32,
+    pub gain: f32,
+    pub q: f32,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EqualizerSettings {
+    /// Preamp in dB. `None` picks enough headroom to cancel the largest boost.
+    pub preamp: Option<f32>,
+    pub bands: Vec<EqualizerBand>,
+}
+
+impl Default for EqualizerSettings {
+    fn default() -> Self {
+        Self {
+            preamp: None,
+            bands: EQUALIZER_FREQUENCIES
+                .iter()
+                .map(|&frequency| EqualizerBand {
+                    frequency,
+                    gain: 0.0,
+                    q: EQUALIZER_DEFAULT_Q,
+                })
+                .collect(),
+        }
+    }
+}
+
+impl EqualizerSettings {
+    /// Preamp gain in dB that is actually applied to the signal.
+    pub fn effective_preamp(&self) -> f32 {
+        match self.preamp {
+            Some(preamp) => preamp.clamp(-MAX_GAIN_DB, MAX_GAIN_DB),
+            None => {
+                let max_boost = self
+                    .bands
+                    .iter()
+                    .map(|b| b.gain)
+                    .fold(0.0_f32, |acc, g| acc.max(g));
+                -max_boost.min(MAX_GAIN_DB)
+            }
+        }
+    }
+
+    pub fn is_flat(&self) -> bool {
+        self.effective_preamp().abs() < f32::EPSILON
+            && self.bands.iter().all(|b| b.gain.abs() < f32::EPSILON)
+    }
+
+    pub fn load() -> Self {
+        app_store()
+            .get(STORE_KEY_SETTINGS)
+            .and_then(|v| serde_json::from_value(v).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self) {
+        let store = app_store();
+        store.set(STORE_KEY_SETTINGS, serde_json::json!(self));
+        let _ = store.save();
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EqualizerPreset {
+    pub name: String,
+    pub settings: EqualizerSettings,
+}
+
+impl EqualizerPreset {
+    fn load_map() -> HashMap<String, EqualizerSettings> {
+        app_store()
+            .get(STORE_KEY_PRESETS)
+            .and_then(|v| serde_json::from_value(v).ok())
+            .unwrap_or_default()
+    }
+
+    fn save_map(presets: &HashMap<String, EqualizerSettings>) {
+        let store = app_store();
+        store.set(STORE_KEY_PRESETS, serde_json::json!(presets));
+        let _ = store.save();
+    }
+
+    pub fn get_all() -> Vec<EqualizerPreset> {
+        let mut presets: Vec<EqualizerPreset> = Self::load_map()
+            .into_iter()
+            .map(|(name, settings)| EqualizerPreset { name, settings })
+            .collect();
+        presets.sort_by_key(|p| p.name.to_lowercase());
+        presets
+    }
+
+    pub fn get(name: &str) -> Option<EqualizerSettings> {
+        Self::load_map().remove(name)
+    }
+
+    pub fn save(name: String, settings: EqualizerSettings) {
+        let mut presets = Self::load_map();
+        presets.insert(name, settings);
+        Self::save_map(&presets);
+    }
+
+    pub fn delete(name: &str) -> bool {
+        let mut presets = Self::load_map();
+        let removed = presets.remove(name).is_some();
+        if removed {
+            Self::save_map(&presets);
+        }
+        removed
+    }
+}
+
+/// Normalized biquad coefficients (a0 == 1).
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Biquad {
+    b0: f64,
+    b1: f64,
+    b2: f64,
+    a1: f64,
+    a2: f64,
+}
+
+impl Biquad {
+    /// RBJ Audio EQ Cookbook peaking filter.
+    pub fn peaking(sample_rate: u32, frequency: f32, gain_db: f32, q: f32) -> Self {
+        let sample_rate = sample_rate.max(1) as f64;
+        let nyquist_limit = sample_rate * 0.49;
+        let frequency = (frequency as f64).clamp(1.0, nyquist_limit);
+        let q = (q as f64).clamp(MIN_Q as f64, MAX_Q as f64);
+        let gain_db = (gain_db as f64).clamp(-MAX_GAIN_DB as f64, MAX_GAIN_DB as f64);
+
+        let a = 10f64.powf(gain_db / 40.0);
+        let w0 = 2.0 * std::f64::consts::PI * frequency / sample_rate;
+        let (sin_w0, cos_w0) = w0.sin_cos();
+        let alpha = sin_w0 / (2.0 * q);
+
+        let a0 = 1.0 + alpha / a;
+        Self {
+            b0: (1.0 + alpha * a) / a0,
+            b1: (-2.0 * cos_w0) / a0,
+            b2: (1.0 - alpha * a) / a0,
+            a1: (-2.0 * cos_w0) / a0,
+            a2: (1.0 - alpha / a) / a0,
+        }
+    }
+}
+
+/// Transposed direct form II state for a single channel.
+#[derive(Clone, Copy, Debug, Default)]
+struct BiquadState {
+    z1: f64,
+    z2: f64,
+}
+
+impl BiquadState {
+    #[inline]
+    fn process(&mut self, filter: &Biquad, input: f64) -> f64 {
+        let output = filter.b0 * input + self.z1;
+        self.z1 = filter.b1 * input - filter.a1 * output + self.z2;
+        self.z2 = filter.b2 * input - filter.a2 * output;
+        output
+    }
+}
+
+/// Multi-band peaking equalizer running over interleaved float samples.
+pub struct Equalizer {
+    settings: EqualizerSettings,
+    sample_rate: u32,
+    channels: usize,
+    filters: Vec<Biquad>,
+    states: Vec<Vec<BiquadState>>,
+    preamp: f32,
+    bypass: bool,
+}
+
+impl Equalizer {
+    pub fn new(settings: EqualizerSettings) -> Self {
+        let mut equalizer = Self {
+            settings,
+            sample_rate: 44100,
+            channels: 2,
+            filters: Vec::new(),
+            states: Vec::new(),
+            preamp: 1.0,
+            bypass: true,
+        };
+        equalizer.rebuild();
+        equalizer
+    }
+
+    pub fn settings(&self) -> &EqualizerSettings {
+        &self.settings
+    }
+
+    pub fn set_settings(&mut self, settings: EqualizerSettings) {
+        self.settings = settings;
+        self.rebuild();
+    }
+
+    /// Update band gains, keeping the current frequencies and Q when the layout matches.
+    pub fn set_gains(&mut self, gains: &[f32]) {
+        if gains.len() != self.settings.bands.len() {
+            self.settings.bands = gains
+                .iter()
+                .enumerate()
+                .map(|(i, &gain)| EqualizerBand {
+                    frequency: EQUALIZER_FREQUENCIES
+                        .get(i)
+                        .copied()
+                        .unwrap_or(*EQUALIZER_FREQUENCIES.last().unwrap()),
+                    gain,
+                    q: EQUALIZER_DEFAULT_Q,
+                })
+                .collect();
+        } else {
+            for (band, &gain) in self.settings.bands.iter_mut().zip(gains) {
+                band.gain = gain;
+            }
+        }
+        self.rebuild();
+    }
+
+    pub fn set_format(&mut self, sample_rate: u32, channels: usize) {
+        if self.sample_rate == sample_rate && self.channels == channels {
+            return;
+        }
+        self.sample_rate = sample_rate;
+        self.channels = channels.max(1);
+        self.rebuild();
+    }
+
+    pub fn reset(&mut self) {
+        self.set_settings(EqualizerSettings::default());
+    }
+
+    fn rebuild(&mut self) {
+        self.filters = self
+            .settings
+            .bands
+            .iter()
+            .filter(|b| b.gain.abs() >= f32::EPSILON)
+            .map(|b| Biquad::peaking(self.sample_rate, b.frequency, b.gain, b.q))
+            .collect();
+        self.states = vec![vec![BiquadState::default(); self.channels]; self.filters.len()];
+        self.preamp = 10f32.powf(self.settings.effective_preamp() / 20.0);
+        self.bypass = self.settings.is_flat();
+    }
+
+    /// Filter interleaved samples in place.
+    pub fn process(&mut self, samples: &mut [f32]) {
+        if self.bypass {
+            return;
+        }
+
+        let channels = self.channels;
+        for frame in samples.chunks_exact_mut(channels) {
+            for (channel, sample) in frame.iter_mut().enumerate() {
+                let mut value = (*sample * self.preamp) as f64;
+                for (filter, states) in self.filters.iter().zip(self.states.iter_mut()) {
+                    value = states[channel].process(filter, value);
+                }
+                *sample = (value as f32).clamp(-1.0, 1.0);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE_RATE: u32 = 48000;
+
+    /// Magnitude of the filter's response at `frequency`.
+    fn magnitude(filter: &Biquad, frequency: f64) -> f64 {
+        let w = 2.0 * std::f64::consts::PI * frequency / SAMPLE_RATE as f64;
+        // z^-1 and z^-2 on the unit circle
+        let (z1, z2) = ((w.cos(), -w.sin()), ((2.0 * w).cos(), -(2.0 * w).sin()));
+        let eval = |c0: f64, c1: f64, c2: f64| {
+            let re = c0 + c1 * z1.0 + c2 * z2.0;
+            let im = c1 * z1.1 + c2 * z2.1;
+            (re * re + im * im).sqrt()
+        };
+        eval(filter.b0, filter.b1, filter.b2) / eval(1.0, filter.a1, filter.a2)
+    }
+
+    fn db(gain: f64) -> f64 {
+        20.0 * gain.log10()
+    }
+
+    fn settings(preamp: Option<f32>, gains: &[(f32, f32)]) -> EqualizerSettings {
+        EqualizerSettings {
+            preamp,
+            bands: gains
+                .iter()
+                .map(|&(frequency, gain)| EqualizerBand {
+                    frequency,
+                    gain,
+                    q: EQUALIZER_DEFAULT_Q,
+                })
+                .collect(),
+        }
+    }
+
+    /// A mono equalizer at the test sample rate.
+    fn equalizer(settings: EqualizerSettings) -> Equalizer {
+        let mut equalizer = Equalizer::new(settings);
+        equalizer.set_format(SAMPLE_RATE, 1);
+        equalizer
+    }
+
+    fn impulse(len: usize) -> Vec<f32> {
+        let mut samples = vec![0.0; len];
+        samples[0] = 0.5;
+        samples
+    }
+
+    #[test]
+    fn center_frequency_gets_the_configured_gain() {
+        for gain in [-12.0, -3.0, 6.0, 12.0] {
+            let filter = Biquad::peaking(SAMPLE_RATE, 1000.0, gain, EQUALIZER_DEFAULT_Q);
+            assert!((db(magnitude(&filter, 1000.0)) - gain as f64).abs() < 1e-6);
+            // Far from the band the signal is left alone
+            assert!(db(magnitude(&filter, 20.0)).abs() < 0.05);
+        }
+    }
+
+    #[test]
+    fn flat_band_is_unity() {
+        let filter = Biquad::peaking(SAMPLE_RATE, 1000.0, 0.0, EQUALIZER_DEFAULT_Q);
+        for frequency in [20.0, 1000.0, 15000.0] {
+            assert!((magnitude(&filter, frequency) - 1.0).abs() < 1e-12);
+        }
+
+        let mut state = BiquadState::default();
+        for i in 0..256 {
+            let input = (i as f64 * 0.37).sin() * 0.8;
+            assert!((state.process(&filter, input) - input).abs() < 1e-12);
+        }
+
+        // Flat settings skip processing entirely
+        let mut equalizer = equalizer(settings(Some(0.0), &[(1000.0, 0.0), (4000.0, 0.0)]));
+        let mut samples = vec![0.25, -0.5, 0.75, -1.0];
+        equalizer.process(&mut samples);
+        assert_eq!(samples, [0.25, -0.5, 0.75, -1.0]);
+    }
+
+    #[test]
+    fn preamp_scales_the_signal() {
+        let mut equalizer = equalizer(settings(Some(-6.0), &[(1000.0, 0.0)]));
+        let mut samples = vec![0.5, -0.25, 1.0];
+        equalizer.process(&mut samples);
+
+        let scale = 10f32.powf(-6.0 / 20.0);
+        for (sample, input) in samples.iter().zip([0.5, -0.25, 1.0]) {
+            assert!((sample - input * scale).abs() < 1e-6);
+        }
+    }
+
+    #[test]
+    fn automatic_preamp_cancels_the_largest_boost() {
+        let settings = settings(None, &[(100.0, 3.0), (1000.0, 9.0), (8000.0, -4.0)]);
+        assert_eq!(settings.effective_preamp(), -9.0);
+    }
+
+    #[test]
+    fn rebuilding_resets_the_filter_state() {
+        let settings = settings(Some(0.0), &[(1000.0, 9.0)]);
+        let mut expected = impulse(64);
+        equalizer(settings.clone()).process(&mut expected);
+
+        // Leave energy in the delay line, then rebuild with the same settings
+        let mut equalizer = equalizer(settings.clone());
+        equalizer.process(&mut impulse(8));
+        assert!(equalizer.states[0][0].z1 != 0.0);
+        equalizer.set_settings(settings);
+        assert_eq!(
+            (equalizer.states[0][0].z1, equalizer.states[0][0].z2),
+            (0.0, 0.0)
+        );
+
+        let mut samples = impulse(64);
+        equalizer.process(&mut samples);
+        assert_eq!(samples, expected);
+    }
+
+    #[test]
+    fn format_change_resets_the_filter_state() {
+        let mut equalizer = equalizer(settings(Some(0.0), &[(1000.0, 9.0)]));
+        equalizer.process(&mut impulse(8));
+
+        equalizer.set_format(44100, 2);
+        assert_eq!(equalizer.states[0].len(), 2);
+        assert!(equalizer.states[0]
+            .iter()
+            .all(|state| state.z1 == 0.0 && state.z2 == 0.0));
+    }
+}
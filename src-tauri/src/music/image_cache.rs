@@ -1,16 +1,15 @@
 use std::collections::HashMap;
-use std::path::PathBuf;
 use std::sync::{Mutex, OnceLock};
 use std::thread::available_parallelism;
-use tauri::Manager;
 use tokio::sync::{watch, Semaphore};
 
-use crate::state::{app_handle, app_store};
-
-const STORE_KEY_DPI: &str = "music_cover_art_dpi";
+use crate::cache::cache::{Cache, CacheKind};
+use crate::coverart::lookup::{CoverArtLookup, CoverArtSource};
+use crate::coverart::types::CoverArtQuery;
 
 static RESIZE_SEMAPHORE: OnceLock<Semaphore> = OnceLock::new();
 static BASE_COVER_SIZE: OnceLock<u32> = OnceLock::new();
+static THUMBNAIL_SIZE: OnceLock<u32> = OnceLock::new();
 
 lazy_static::lazy_static! {
     static ref IMAGE_CACHE_QUEUE: Mutex<HashMap<String, CacheEntry>> =
@@ -34,28 +33,11 @@ pub struct ImageCache;
 
 impl ImageCache {
     pub fn init_base_cover_size(scale_factor: f64) {
+        // Covers are cached per size, so a new size only adds entries next to the old ones
         let size = (300.0 * scale_factor).round() as u32;
-
-        let store = app_store();
-        let prior: Option<u32> = store
-            .get(STORE_KEY_DPI)
-            .and_then(|v| serde_json::from_value(v).ok());
-
-        if let Some(prior_size) = prior {
-            if prior_size != size {
-                crate::info!(
-                    "DPI changed ({} → {}). Invalidating music image cache.",
-                    prior_size,
-                    size
-                );
-                Self::invalidate_cache();
-            }
-        }
-
-        store.set(STORE_KEY_DPI.to_string(), serde_json::json!(size));
-        let _ = store.save();
-
         let _ = BASE_COVER_SIZE.set(size);
+        // Track rows, the queue and the player bar show covers at 72px
+        let _ = THUMBNAIL_SIZE.set((72.0 * scale_factor).round() as u32);
 
         let permits = available_parallelism()
             .map(|n| n.get())
@@ -69,34 +51,33 @@ impl ImageCache {
         *BASE_COVER_SIZE.get().unwrap_or(&300)
     }
 
-    pub fn resize_semaphore() -> &'static Semaphore {
-        RESIZE_SEMAPHORE.get_or_init(|| Semaphore::new(1))
+    pub fn thumbnail_size() -> u32 {
+        *THUMBNAIL_SIZE.get().unwrap_or(&72)
     }
 
-    pub fn get_cache_dir() -> PathBuf {
-        let dir = app_handle()
-            .path()
-            .app_cache_dir()
-            .expect("Failed to get app cache dir")
-            .join("coverarts")
-            .join("local");
-        std::fs::create_dir_all(&dir).expect("Failed to create music image cache dir");
-        dir
+    pub fn resize_semaphore() -> &'static Semaphore {
+        RESIZE_SEMAPHORE.get_or_init(|| Semaphore::new(1))
     }
 
+    /// Drop all cached covers, so they are looked up again.
     pub fn invalidate_cache() {
-        let dir = Self::get_cache_dir();
-        if let Ok(entries) = std::fs::read_dir(&dir) {
-            for entry in entries.flatten() {
-                let _ = std::fs::remove_file(entry.path());
-            }
+        if let Err(e) = Cache::clear(Some(CacheKind::Cover)) {
+            crate::error!("Failed to clear cover cache: {}", e);
         }
+        Self::clear_queue();
+    }
+
+    /// Forget finished lookups, so they are read from the cache or looked up again.
+    pub fn clear_queue() {
+        IMAGE_CACHE_QUEUE
+            .lock()
+            .unwrap()
+            .retain(|_, entry| entry.status == CacheStatus::Pending);
     }
 
     pub fn get_cache_key(artist: Option<&str>, album: Option<&str>, path: &str) -> String {
         if let (Some(a), Some(b)) = (artist, album) {
-            let raw = format!("{} {}", a, b);
-            Self::sanitize_filename(&raw)
+            format!("{} {}", a, b)
         } else {
             let mut hash: u64 = 5381;
             for byte in path.bytes() {
@@ -106,25 +87,10 @@ impl ImageCache {
         }
     }
 
-    fn sanitize_filename(name: &str) -> String {
-        name.chars()
-            .map(|c| match c {
-                '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|' => '_',
-                c => c,
-            })
-            .collect()
-    }
-
-    pub fn read_cache(key: &str) -> Option<Vec<u8>> {
-        let path = Self::get_cache_dir().join(key);
-        std::fs::read(path).ok()
-    }
-
-    pub fn write_cache(key: &str, bytes: &[u8]) {
-        let path = Self::get_cache_dir().join(key);
-        if let Err(e) = std::fs::write(&path, bytes) {
-            crate::warn!("Failed to write music image cache {}: {}", key, e);
-        }
+    /// Drop a cached cover, so it's looked up again on the next request.
+    pub fn remove_cache(key: &str) {
+        Cache::unlink(&Self::lookup_key(key));
+        IMAGE_CACHE_QUEUE.lock().unwrap().remove(key);
     }
 
     pub fn queue_get(key: &str) -> Option<CacheStatus> {
@@ -175,4 +141,137 @@ impl ImageCache {
 
         CacheStatus::Failed
     }
+
+    /// Cover art for the track at `path`, resized to `size`. The image is looked up once
+    /// through the configured sources, then cached by content at each requested size.
+    pub async fn get_image(
+        key: &str,
+        path: &str,
+        query: Option<&CoverArtQuery>,
+        size: Option<u32>,
+    ) -> Vec<u8> {
+        let lookup = CoverArtLookup::load();
+        let Some(size) = size else {
+            return lookup.find(Some(path), query).await.unwrap_or_default();
+        };
+        let base_size = Self::base_cover_size();
+
+        match Self::queue_get(key) {
+            Some(CacheStatus::Pending) => {
+                if Self::queue_wait(key).await != CacheStatus::Loaded {
+                    return Vec::new();
+                }
+            }
+            Some(CacheStatus::Failed) => return Vec::new(),
+            Some(CacheStatus::Loaded) | None => {}
+        }
+
+        if let Some(image) = Self::serve_from_cache(key, size, base_size).await {
+            Self::queue_set(key, CacheStatus::Loaded);
+            return image;
+        }
+
+        Self::queue_set(key, CacheStatus::Pending);
+
+        let raw_bytes = match lookup.find(Some(path), query).await {
+            Ok(b) => b,
+            Err(e) => {
+                crate::warn!("{}", e);
+                Self::queue_set(key, CacheStatus::Failed);
+                return Vec::new();
+            }
+        };
+
+        let (hash, base_bytes) = Self::store_base(key, raw_bytes, base_size).await;
+        Self::queue_set(key, CacheStatus::Loaded);
+
+        Self::resize(&hash, base_bytes, size, base_size).await
+    }
+
+    /// Cache the cover of `key` at the base and thumbnail sizes ahead of the first request.
+    /// Only local sources are tried, online art is left to requests from the UI. Returns
+    /// whether the cover is cached.
+    pub async fn pregenerate(key: &str, path: &str) -> bool {
+        let base_size = Self::base_cover_size();
+        let thumbnail_size = Self::thumbnail_size();
+
+        if Self::serve_from_cache(key, thumbnail_size, base_size)
+            .await
+            .is_some()
+        {
+            return true;
+        }
+        // Already being looked up, or known to have no art
+        if Self::queue_get(key).is_some() {
+            return false;
+        }
+
+        let mut lookup = CoverArtLookup::load();
+        lookup.sources.retain(|s| *s != CoverArtSource::Online);
+        let Ok(raw_bytes) = lookup.find(Some(path), None).await else {
+            return false;
+        };
+
+        let (hash, base_bytes) = Self::store_base(key, raw_bytes, base_size).await;
+        Self::resize(&hash, base_bytes, thumbnail_size, base_size).await;
+        true
+    }
+
+    /// Cache the looked up image at the base size and link `key` to it.
+    async fn store_base(key: &str, raw_bytes: Vec<u8>, base_size: u32) -> (String, Vec<u8>) {
+        let hash = Cache::content_hash(&raw_bytes);
+        let base_bytes = {
+            let _permit = Self::resize_semaphore()
+                .acquire()
+                .await
+                .expect("Semaphore closed");
+            match crate::utils::image::compress_image(&raw_bytes, base_size) {
+                Ok(resized) => resized,
+                Err(e) => {
+                    crate::warn!("Failed to resize to base size: {}", e);
+                    raw_bytes
+                }
+            }
+        };
+        Cache::write(CacheKind::Cover, &hash, Some(base_size), &base_bytes);
+        Cache::link(&Self::lookup_key(key), &hash);
+        (hash, base_bytes)
+    }
+
+    async fn serve_from_cache(key: &str, size: u32, base_size: u32) -> Option<Vec<u8>> {
+        let hash = Cache::hash_of(&Self::lookup_key(key))?;
+        if size < base_size {
+            if let Some(bytes) = Cache::read(&hash, Some(size)) {
+                return Some(bytes);
+            }
+        }
+        let base_bytes = Cache::read(&hash, Some(base_size))?;
+        Some(Self::resize(&hash, base_bytes, size, base_size).await)
+    }
+
+    /// Downscale the base cover to `size`, caching the result for the next request.
+    async fn resize(hash: &str, base_bytes: Vec<u8>, size: u32, base_size: u32) -> Vec<u8> {
+        if size >= base_size {
+            return base_bytes;
+        }
+
+        let _permit = Self::resize_semaphore()
+            .acquire()
+            .await
+            .expect("Semaphore closed");
+        match crate::utils::image::compress_image(&base_bytes, size) {
+            Ok(small) => {
+                Cache::write(CacheKind::Cover, hash, Some(size), &small);
+                small
+            }
+            Err(e) => {
+                crate::warn!("Failed to downscale to {}: {}", size, e);
+                base_bytes
+            }
+        }
+    }
+
+    fn lookup_key(key: &str) -> String {
+        format!("cover:{}", key)
+    }
 }
@@ -18,6 +18,7 @@ use tokio::process::Command;
 #[cfg(target_os = "android")]
 use tauri_plugin_fluyer::FluyerExt;
 
+use crate::music::replay_gain;
 use crate::state::app_handle;
 
 static FFMPEG_PATH: OnceLock<PathBuf> = OnceLock::new();
@@ -36,12 +37,39 @@ pub struct MusicMetadata {
     pub album: Option<String>,
     pub album_artist: Option<String>,
     pub track_number: Option<String>,
+    pub track_total: Option<u32>,
+    pub disc_number: Option<u32>,
+    pub disc_total: Option<u32>,
     pub genre: Option<String>,
     pub date: Option<String>,
     pub bits_per_sample: Option<u32>,
     pub sample_rate: Option<u32>,
     pub image: Option<String>,
 
+    pub composer: Option<String>,
+    pub conductor: Option<String>,
+    pub label: Option<String>,
+    pub isrc: Option<String>,
+    pub musicbrainz_recording_id: Option<String>,
+    pub musicbrainz_album_id: Option<String>,
+    pub musicbrainz_artist_id: Option<String>,
+    pub musicbrainz_release_group_id: Option<String>,
+
+    pub codec: Option<String>,
+    /// Bitrate in bits per second, averaged over the file when the stream doesn't state it.
+    pub bitrate: Option<u32>,
+    pub channels: Option<u32>,
+    /// File size in bytes.
+    pub file_size: Option<u64>,
+
+    /// ReplayGain values from tags, gain in dB and peak as linear amplitude.
+    pub replay_gain_track_gain: Option<f64>,
+    pub replay_gain_track_peak: Option<f64>,
+    pub replay_gain_album_gain: Option<f64>,
+    pub replay_gain_album_peak: Option<f64>,
+    /// Integrated loudness in LUFS, measured when the file has no ReplayGain tags.
+    pub loudness: Option<f64>,
+
     pub extra_tags: Option<HashMap<String, Option<String>>>,
 }
 
@@ -174,6 +202,7 @@ impl MusicMetadata {
             }
         }
 
+        metadata.apply_file_info();
         Ok(metadata)
     }
 
@@ -217,6 +246,10 @@ impl MusicMetadata {
         if let Some(CodecParameters::Audio(audio_params)) = &track.codec_params {
             metadata.sample_rate = audio_params.sample_rate;
             metadata.bits_per_sample = audio_params.bits_per_sample;
+            metadata.channels = audio_params.channels.as_ref().map(|c| c.count() as u32);
+            metadata.codec = symphonia::default::get_codecs()
+                .get_audio_decoder(audio_params.codec)
+                .map(|decoder| decoder.codec.info.short_name.to_string());
         }
 
         // Extract metadata tags from format metadata
@@ -232,6 +265,25 @@ impl MusicMetadata {
                             metadata.album_artist = Some(v.as_ref().clone())
                         }
                         StandardTag::TrackNumber(n) => metadata.track_number = Some(n.to_string()),
+                        StandardTag::TrackTotal(n) => metadata.track_total = Some(*n as u32),
+                        StandardTag::DiscNumber(n) => metadata.disc_number = Some(*n as u32),
+                        StandardTag::DiscTotal(n) => metadata.disc_total = Some(*n as u32),
+                        StandardTag::Composer(v) => metadata.composer = Some(v.as_ref().clone()),
+                        StandardTag::Conductor(v) => metadata.conductor = Some(v.as_ref().clone()),
+                        StandardTag::Label(v) => metadata.label = Some(v.as_ref().clone()),
+                        StandardTag::IdentIsrc(v) => metadata.isrc = Some(v.as_ref().clone()),
+                        StandardTag::MusicBrainzRecordingId(v) => {
+                            metadata.musicbrainz_recording_id = Some(v.as_ref().clone())
+                        }
+                        StandardTag::MusicBrainzAlbumId(v) => {
+                            metadata.musicbrainz_album_id = Some(v.as_ref().clone())
+                        }
+                        StandardTag::MusicBrainzArtistId(v) => {
+                            metadata.musicbrainz_artist_id = Some(v.as_ref().clone())
+                        }
+                        StandardTag::MusicBrainzReleaseGroupId(v) => {
+                            metadata.musicbrainz_release_group_id = Some(v.as_ref().clone())
+                        }
                         StandardTag::Genre(v) => metadata.genre = Some(v.as_ref().clone()),
                         StandardTag::RecordingDate(v) => metadata.date = Some(v.as_ref().clone()),
                         StandardTag::ReleaseDate(v) if metadata.date.is_none() => {
@@ -240,6 +292,8 @@ impl MusicMetadata {
                         _ => {}
                     }
                 }
+
+                metadata.apply_replay_gain_tag(&tag.raw.key, &tag.raw.value.to_string());
             }
         };
 
@@ -277,6 +331,43 @@ impl MusicMetadata {
         Self::parse_json_metadata(json, path)
     }
 
+    /// Measure integrated loudness (EBU R128) in LUFS with FFmpeg's ebur128 filter
+    #[cfg(desktop)]
+    pub async fn get_loudness_with_ffmpeg(path: &str) -> Result<Option<f64>, String> {
+        let output = Self::create_command(FFMPEG_PATH.get().unwrap())
+            .args(&[
+                "-hide_banner",
+                "-nostats",
+                "-i",
+                path,
+                "-vn",
+                "-af",
+                "ebur128=framelog=quiet",
+                "-f",
+                "null",
+                "-",
+            ])
+            .output()
+            .await
+            .map_err(|e| format!("Failed to execute ffmpeg: {}", e))?;
+
+        if !output.status.success() {
+            return Err(format!("ffmpeg failed with status: {}", output.status));
+        }
+
+        // The summary is printed last, with the integrated loudness on its own "I:" line
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        let loudness = stderr
+            .lines()
+            .rev()
+            .find_map(|line| line.trim().strip_prefix("I:"))
+            .and_then(|v| v.trim().trim_end_matches("LUFS").trim().parse::<f64>().ok())
+            .ok_or_else(|| format!("No loudness summary for {}", path))?;
+
+        // -70 LUFS is the absolute gate, anything at or below it is silence
+        Ok(Some(loudness).filter(|v| v.is_finite() && *v > -70.0))
+    }
+
     fn parse_json_metadata(json: Value, path: String) -> Result<MusicMetadata, String> {
         // Verify this is actually an audio file with at least one audio stream
         let has_audio_stream = json
@@ -305,6 +396,8 @@ impl MusicMetadata {
                     Self::extract_tag(tags, &["album_artist", "ALBUM_ARTIST", "ALBUMARTIST"]);
                 metadata.album = Self::extract_tag(tags, &["album", "ALBUM", "Album"]);
                 metadata.track_number = Self::extract_tag(tags, &["track", "TRACK", "TRACKNUMBER"]);
+                metadata.apply_extended_tags(tags);
+                metadata.apply_replay_gain_tags(tags);
             }
 
             // Extract duration
@@ -313,12 +406,33 @@ impl MusicMetadata {
                     metadata.duration = Some((duration_secs * 1000.0) as u128);
                 }
             }
+
+            metadata.bitrate = Self::parse_json_number(format, "bit_rate");
         }
 
         // Extract stream information (bit depth, sample rate)
         if let Some(streams) = json.get("streams").and_then(|v| v.as_array()) {
             for stream in streams {
                 if stream.get("codec_type").and_then(|v| v.as_str()) == Some("audio") {
+                    // Vorbis comments in Ogg/Opus live on the stream rather than the format
+                    if let Some(tags) = stream.get("tags") {
+                        metadata.apply_extended_tags(tags);
+                        metadata.apply_replay_gain_tags(tags);
+                    }
+
+                    metadata.codec = stream
+                        .get("codec_name")
+                        .and_then(|v| v.as_str())
+                        .map(|v| v.to_string());
+                    metadata.channels = stream
+                        .get("channels")
+                        .and_then(|v| v.as_u64())
+                        .map(|v| v as u32);
+                    // The stream bitrate is the audio alone, without container overhead
+                    if let Some(bitrate) = Self::parse_json_number(stream, "bit_rate") {
+                        metadata.bitrate = Some(bitrate);
+                    }
+
                     // Sample rate
                     if let Some(sample_rate) = stream.get("sample_rate").and_then(|v| v.as_str()) {
                         if let Ok(rate) = sample_rate.parse::<f64>() {
@@ -348,9 +462,141 @@ impl MusicMetadata {
             }
         }
 
+        // "3/12" style values carry the total as well
+        if let Some(track) = metadata.track_number.take() {
+            let (number, total) = Self::parse_number_total(&track);
+            metadata.track_number = number.map(|n| n.to_string()).or(Some(track));
+            metadata.track_total = metadata.track_total.or(total);
+        }
+
         Ok(metadata)
     }
 
+    /// Fill the tags beyond the basic ones from an ffprobe tag object, keeping values already
+    /// found.
+    fn apply_extended_tags(&mut self, tags: &Value) {
+        if let Some(disc) = Self::extract_tag(tags, &["disc", "DISCNUMBER", "TPOS"]) {
+            let (number, total) = Self::parse_number_total(&disc);
+            self.disc_number = self.disc_number.or(number);
+            self.disc_total = self.disc_total.or(total);
+        }
+        self.disc_total = self
+            .disc_total
+            .or_else(|| Self::extract_number(tags, &["DISCTOTAL", "TOTALDISCS"]));
+        self.track_total = self
+            .track_total
+            .or_else(|| Self::extract_number(tags, &["TRACKTOTAL", "TOTALTRACKS"]));
+
+        let fields: [(&mut Option<String>, &[&str]); 8] = [
+            (&mut self.composer, &["composer", "COMPOSER", "TCOM"]),
+            (&mut self.conductor, &["conductor", "CONDUCTOR", "TPE3"]),
+            (
+                &mut self.label,
+                &["label", "LABEL", "publisher", "ORGANIZATION", "TPUB"],
+            ),
+            (&mut self.isrc, &["isrc", "ISRC", "TSRC"]),
+            (
+                &mut self.musicbrainz_recording_id,
+                &["MusicBrainz Track Id", "MUSICBRAINZ_TRACKID"],
+            ),
+            (
+                &mut self.musicbrainz_album_id,
+                &["MusicBrainz Album Id", "MUSICBRAINZ_ALBUMID"],
+            ),
+            (
+                &mut self.musicbrainz_artist_id,
+                &["MusicBrainz Artist Id", "MUSICBRAINZ_ARTISTID"],
+            ),
+            (
+                &mut self.musicbrainz_release_group_id,
+                &["MusicBrainz Release Group Id", "MUSICBRAINZ_RELEASEGROUPID"],
+            ),
+        ];
+        for (field, keys) in fields {
+            if field.is_none() {
+                *field = Self::extract_tag(tags, keys);
+            }
+        }
+    }
+
+    fn extract_number(tags: &Value, keys: &[&str]) -> Option<u32> {
+        Self::extract_tag(tags, keys).and_then(|v| Self::parse_number_total(&v).0)
+    }
+
+    /// Parse "3" or "3/12" into the number and the total, if present.
+    fn parse_number_total(value: &str) -> (Option<u32>, Option<u32>) {
+        let (number, total) = match value.split_once('/') {
+            Some((number, total)) => (number, Some(total)),
+            None => (value, None),
+        };
+        (
+            number.trim().parse().ok(),
+            total.and_then(|t| t.trim().parse().ok()),
+        )
+    }
+
+    /// ffprobe prints most numbers as strings.
+    fn parse_json_number(value: &Value, key: &str) -> Option<u32> {
+        value
+            .get(key)
+            .and_then(|v| v.as_str())
+            .and_then(|v| v.parse::<u64>().ok())
+            .map(|v| v.min(u32::MAX as u64) as u32)
+    }
+
+    /// Fill the file size, and the bitrate from it when the decoder didn't report one.
+    fn apply_file_info(&mut self) {
+        self.file_size = std::fs::metadata(&self.path).ok().map(|m| m.len());
+
+        if self.bitrate.is_none() {
+            if let (Some(size), Some(duration)) = (self.file_size, self.duration) {
+                self.bitrate = (size as u128 * 8 * 1000)
+                    .checked_div(duration)
+                    .map(|bitrate| bitrate as u32);
+            }
+        }
+    }
+
+    /// Fill ReplayGain fields from an ffprobe tag object, keeping values already found.
+    fn apply_replay_gain_tags(&mut self, tags: &Value) {
+        if let Some(obj) = tags.as_object() {
+            for (key, value) in obj {
+                if let Some(value) = value.as_str() {
+                    self.apply_replay_gain_tag(key, value);
+                }
+            }
+        }
+    }
+
+    /// Match a single raw tag against the ReplayGain keys. ID3v2 stores them as TXXX frames and
+    /// Opus uses R128 gains in Q7.8 relative to -23 LUFS.
+    fn apply_replay_gain_tag(&mut self, key: &str, value: &str) {
+        let key = key.to_lowercase();
+        let key = key.trim_start_matches("txxx:");
+
+        match key {
+            "replaygain_track_gain" if self.replay_gain_track_gain.is_none() => {
+                self.replay_gain_track_gain = replay_gain::parse_gain(value)
+            }
+            "replaygain_track_peak" if self.replay_gain_track_peak.is_none() => {
+                self.replay_gain_track_peak = replay_gain::parse_peak(value)
+            }
+            "replaygain_album_gain" if self.replay_gain_album_gain.is_none() => {
+                self.replay_gain_album_gain = replay_gain::parse_gain(value)
+            }
+            "replaygain_album_peak" if self.replay_gain_album_peak.is_none() => {
+                self.replay_gain_album_peak = replay_gain::parse_peak(value)
+            }
+            "r128_track_gain" if self.replay_gain_track_gain.is_none() => {
+                self.replay_gain_track_gain = replay_gain::parse_r128_gain(value)
+            }
+            "r128_album_gain" if self.replay_gain_album_gain.is_none() => {
+                self.replay_gain_album_gain = replay_gain::parse_r128_gain(value)
+            }
+            _ => {}
+        }
+    }
+
     /// Extract tag value from multiple possible keys (case-insensitive)
     fn extract_tag(tags: &Value, keys: &[&str]) -> Option<String> {
         // First try exact case-sensitive match
@@ -551,7 +797,9 @@ impl MusicMetadata {
                 Some(json_str) => {
                     let json: Value = serde_json::from_str(&json_str)
                         .map_err(|e| format!("Failed to parse JSON from Android: {}", e))?;
-                    Self::parse_json_metadata(json, path_clone)
+                    let mut metadata = Self::parse_json_metadata(json, path_clone)?;
+                    metadata.apply_file_info();
+                    Ok(metadata)
                 }
                 None => Err("No metadata returned from Android".to_string()),
             }
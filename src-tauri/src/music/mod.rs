@@ -1,6 +1,13 @@
 pub mod bass;
 pub mod commands;
+pub mod crossfade;
+pub mod equalizer;
 pub mod image_cache;
 pub mod media_session;
 pub mod metadata;
+#[cfg(desktop)]
+pub mod output_device;
 pub mod player;
+pub mod replay_gain;
+pub mod session;
+pub mod tag_editor;
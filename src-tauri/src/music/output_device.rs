@@ -0,0 +1,114 @@
+use serde::{Deserialize, Serialize};
+
+use crate::state::app_store;
+
+use super::bass::*;
+
+const STORE_KEY_OUTPUT_DEVICE: &str = "music-output-device";
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct OutputDevice {
+    pub index: u32,
+    pub name: String,
+    pub driver: String,
+    pub is_default: bool,
+    pub is_enabled: bool,
+    pub is_current: bool,
+}
+
+/// Device the user picked, stored by name/driver since BASS indices shift when devices are
+/// plugged in or removed.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct OutputDevicePreference {
+    pub name: String,
+    pub driver: String,
+}
+
+impl OutputDevice {
+    /// Enumerate real output devices, skipping BASS's "no sound" device 0.
+    pub fn get_all() -> Vec<OutputDevice> {
+        let mut devices = Vec::new();
+
+        unsafe {
+            let current = BASS_GetDevice();
+            let mut i = 1;
+            let mut info = std::mem::zeroed::<BASS_DEVICEINFO>();
+            while BASS_GetDeviceInfo(i, &mut info) != 0 {
+                let name = if info.name.is_null() {
+                    "Unknown".to_string()
+                } else {
+                    std::ffi::CStr::from_ptr(info.name)
+                        .to_string_lossy()
+                        .into_owned()
+                };
+                let driver = if info.driver.is_null() {
+                    "Unknown".to_string()
+                } else {
+                    std::ffi::CStr::from_ptr(info.driver)
+                        .to_string_lossy()
+                        .into_owned()
+                };
+
+                devices.push(OutputDevice {
+                    index: i,
+                    name,
+                    driver,
+                    is_default: (info.flags & BASS_DEVICE_DEFAULT) != 0,
+                    is_enabled: (info.flags & BASS_DEVICE_ENABLED) != 0,
+                    is_current: i == current,
+                });
+                i += 1;
+            }
+        }
+
+        devices
+    }
+
+    /// Index of the stored device if it's still connected, `None` for the system default.
+    pub fn find_preferred() -> Option<u32> {
+        let preference = Self::load_preference()?;
+        let devices = Self::get_all();
+
+        let found = devices
+            .iter()
+            .filter(|d| d.is_enabled)
+            .find(|d| d.driver == preference.driver && d.name == preference.name)
+            .or_else(|| {
+                devices
+                    .iter()
+                    .filter(|d| d.is_enabled)
+                    .find(|d| d.name == preference.name)
+            });
+
+        match found {
+            Some(device) => Some(device.index),
+            None => {
+                crate::warn!(
+                    "Preferred output device not found: {} ({}), using default",
+                    preference.name,
+                    preference.driver
+                );
+                None
+            }
+        }
+    }
+
+    pub fn load_preference() -> Option<OutputDevicePreference> {
+        app_store()
+            .get(STORE_KEY_OUTPUT_DEVICE)
+            .and_then(|v| serde_json::from_value(v).ok())
+    }
+
+    pub fn save_preference(preference: Option<OutputDevicePreference>) {
+        let store = app_store();
+        match preference {
+            Some(preference) => store.set(STORE_KEY_OUTPUT_DEVICE, serde_json::json!(preference)),
+            None => {
+                store.delete(STORE_KEY_OUTPUT_DEVICE);
+            }
+        }
+        let _ = store.save();
+    }
+}
@@ -1,19 +1,28 @@
+use crate::history::history::PlayHistory;
+use crate::music::crossfade::CrossfadeSettings;
+use crate::music::equalizer::{Equalizer, EqualizerSettings};
 use crate::music::metadata::MusicMetadata;
+#[cfg(desktop)]
+use crate::music::output_device::{OutputDevice, OutputDevicePreference};
+use crate::music::replay_gain::ReplayGainSettings;
+use crate::music::session::{self, PlayerSession};
 use crate::state::{app_handle, main_window};
+use rand::seq::SliceRandom;
+use rand::RngExt;
 use serde::{Deserialize, Serialize};
 use std::ffi::CString;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::ptr;
 use std::sync::atomic::{AtomicU32, Ordering};
 use std::sync::{Arc, Mutex};
 use std::thread;
+use std::time::{Duration, Instant};
 use tauri::{Emitter, Manager};
 #[cfg(target_os = "android")]
 use tauri_plugin_fluyer::FluyerExt;
 
 use super::bass::*;
 
-
 #[derive(Clone, Debug)]
 struct PlaylistItem {
     metadata: MusicMetadata,
@@ -36,13 +45,147 @@ pub struct MusicPlayerSync {
     current_position: Option<f64>,
     is_playing: bool,
     repeat_mode: RepeatMode,
+    is_shuffle: bool,
+    is_bit_perfect: bool,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct MixerFormat {
+    sample_rate: u32,
+    channels: u32,
+    float: bool,
 }
 
+/// Format of the mixer outside bit-perfect mode.
+const STANDARD_MIXER_FORMAT: MixerFormat = MixerFormat {
+    sample_rate: 44100,
+    channels: 2,
+    float: true,
+};
+
 #[derive(Debug, Clone)]
 struct PlayerState {
     playlist: Vec<PlaylistItem>,
     current_index: Option<usize>,
     repeat_mode: RepeatMode,
+    /// Play order as playlist indices while shuffle is on. The playlist itself keeps the
+    /// original order, so turning shuffle off restores it.
+    shuffle_order: Option<Vec<usize>>,
+    volume: f32,
+    /// Bit-perfect output requested by the user.
+    bit_perfect: bool,
+    /// Whether the current track is actually reaching the device untouched.
+    is_bit_perfect: bool,
+    mixer_format: Option<MixerFormat>,
+    /// Equalizer DSP on the current mixer, 0 when it isn't attached.
+    equalizer_dsp: u32,
+    replay_gain: ReplayGainSettings,
+    crossfade: CrossfadeSettings,
+    /// Fade-in for the track being loaded, set only while crossfading into it.
+    fade_in: Option<u32>,
+    /// Track that is still fading in and when its slide ends.
+    fading_in: Option<(u32, Instant)>,
+    /// Previous track that is still fading out in the mixer.
+    fading_stream: Option<u32>,
+}
+
+impl PlayerState {
+    /// Track that follows the current one when it ends on its own.
+    fn auto_next_index(&self) -> Option<usize> {
+        match (self.current_index, self.repeat_mode) {
+            (Some(current), RepeatMode::One) => Some(current),
+            _ => self.following_index(self.repeat_mode == RepeatMode::All),
+        }
+    }
+
+    /// Next track in play order, wrapping around to the start when `wrap` is set.
+    fn following_index(&self, wrap: bool) -> Option<usize> {
+        let current = self.current_index?;
+        match &self.shuffle_order {
+            Some(order) => {
+                let next = order
+                    .iter()
+                    .position(|&i| i == current)
+                    .and_then(|pos| order.get(pos + 1));
+                next.or(if wrap { order.first() } else { None }).copied()
+            }
+            None if current + 1 < self.playlist.len() => Some(current + 1),
+            None if wrap && !self.playlist.is_empty() => Some(0),
+            None => None,
+        }
+    }
+
+    /// Previous track in play order, which walks back through the shuffle history.
+    fn preceding_index(&self) -> Option<usize> {
+        let current = self.current_index?;
+        match &self.shuffle_order {
+            Some(order) => match order.iter().position(|&i| i == current) {
+                Some(pos) if pos > 0 => Some(order[pos - 1]),
+                _ => order.last().copied(),
+            },
+            None if current > 0 => Some(current - 1),
+            None => self.playlist.len().checked_sub(1),
+        }
+    }
+
+    /// Shuffle the whole queue, keeping the current track first so it becomes the start of
+    /// the history.
+    fn build_shuffle_order(&mut self) {
+        let mut order: Vec<usize> = (0..self.playlist.len())
+            .filter(|&i| Some(i) != self.current_index)
+            .collect();
+        order.shuffle(&mut rand::rng());
+        if let Some(current) = self.current_index {
+            order.insert(0, current);
+        }
+        self.shuffle_order = Some(order);
+    }
+
+    /// Scatter newly queued tracks among the ones that haven't been played yet.
+    fn shuffle_in(&mut self, indices: std::ops::Range<usize>) {
+        let current = self.current_index;
+        let Some(order) = self.shuffle_order.as_mut() else {
+            return;
+        };
+        let played = current
+            .and_then(|c| order.iter().position(|&i| i == c))
+            .map_or(0, |pos| pos + 1);
+
+        let mut rng = rand::rng();
+        for index in indices {
+            let at = rng.random_range(played..=order.len());
+            order.insert(at, index);
+        }
+    }
+
+    /// Make `index` the next track in the shuffle order, e.g. when picked from the queue.
+    fn shuffle_promote(&mut self, index: usize) {
+        let current = self.current_index;
+        let Some(order) = self.shuffle_order.as_mut() else {
+            return;
+        };
+        if current == Some(index) {
+            return;
+        }
+        order.retain(|&i| i != index);
+        let at = current
+            .and_then(|c| order.iter().position(|&i| i == c))
+            .map_or(0, |pos| pos + 1);
+        order.insert(at, index);
+    }
+}
+
+/// New position of the item at `index` after the item at `from` moved to `to`.
+fn moved_index(index: usize, from: usize, to: usize) -> usize {
+    if index == from {
+        to
+    } else if from < index && to >= index {
+        index - 1
+    } else if from > index && to <= index {
+        index + 1
+    } else {
+        index
+    }
 }
 
 pub struct MusicPlayer {
@@ -50,6 +193,7 @@ pub struct MusicPlayer {
     current_stream: Arc<AtomicU32>,
     state: Arc<Mutex<PlayerState>>,
     temp_wav_path: Arc<Mutex<Option<PathBuf>>>,
+    equalizer: Arc<Mutex<Equalizer>>,
 }
 
 struct SyncData {
@@ -61,7 +205,7 @@ struct SyncData {
 
 extern "C" fn end_sync_callback(
     _handle: u32,
-    _channel: u32,
+    channel: u32,
     _data: u32,
     user: *mut std::ffi::c_void,
 ) {
@@ -75,6 +219,12 @@ extern "C" fn end_sync_callback(
     let st = Arc::clone(&sync_data.state);
     let twp = Arc::clone(&sync_data.temp_wav_path);
 
+    // A track that was crossfaded out has already handed over to the next one.
+    if channel != cs_arc.load(Ordering::SeqCst) {
+        tauri::async_runtime::spawn_blocking(move || MusicPlayer::free_fading_stream(&st));
+        return;
+    }
+
     cs_arc.store(0, Ordering::SeqCst);
     crate::info!("Track ended, playing next");
 
@@ -87,12 +237,7 @@ extern "C" fn end_sync_callback(
                     return;
                 }
             };
-            match (state.current_index, state.repeat_mode) {
-                (Some(current), RepeatMode::One) => Some(current),
-                (Some(current), _) if current + 1 < state.playlist.len() => Some(current + 1),
-                (Some(_), RepeatMode::All) => Some(0),
-                _ => None,
-            }
+            state.auto_next_index()
         };
 
         if let Some(index) = next_index {
@@ -184,6 +329,142 @@ extern "C" fn end_sync_callback(
     });
 }
 
+/// Fires `duration` before the end of a track and starts the next one on top of it, sliding
+/// the outgoing channel down while the incoming one slides up.
+extern "C" fn crossfade_sync_callback(
+    _handle: u32,
+    channel: u32,
+    _data: u32,
+    user: *mut std::ffi::c_void,
+) {
+    if user.is_null() {
+        return;
+    }
+
+    let sync_data = unsafe { &*(user as *const SyncData) };
+    let bm = Arc::clone(&sync_data.bass_mixer);
+    let cs_arc = Arc::clone(&sync_data.current_stream);
+    let st = Arc::clone(&sync_data.state);
+    let twp = Arc::clone(&sync_data.temp_wav_path);
+
+    tauri::async_runtime::spawn_blocking(move || {
+        if channel != cs_arc.load(Ordering::SeqCst) {
+            return;
+        }
+
+        let (index, music, total_count, duration) = {
+            let mut state = match st.lock() {
+                Ok(s) => s,
+                Err(e) => {
+                    crate::error!("Failed to lock player state: {}", e);
+                    return;
+                }
+            };
+            let (Some(current), Some(index)) = (state.current_index, state.auto_next_index())
+            else {
+                return;
+            };
+            // Repeating a single track or mixing in bit-perfect mode stays gapless.
+            if index == current || state.bit_perfect {
+                return;
+            }
+            let current_music = &state.playlist[current].metadata;
+            let music = state.playlist[index].metadata.clone();
+            if !state.crossfade.should_crossfade(current_music, &music) {
+                return;
+            }
+
+            let duration = state.crossfade.duration;
+            state.fade_in = Some(duration);
+            (index, music, state.playlist.len(), duration)
+        };
+
+        crate::info!("Crossfading into next track");
+        let loaded =
+            MusicPlayer::load_music_inner(&bm, &cs_arc, &st, &twp, music, index, total_count);
+        if let Ok(mut state) = st.lock() {
+            state.fade_in = None;
+            if loaded {
+                state.current_index = Some(index);
+                state.fading_stream = Some(channel);
+            }
+        }
+        if !loaded {
+            // The outgoing track keeps playing and its end sync takes over.
+            return;
+        }
+
+        MusicPlayer::fade_out(channel, duration);
+        MusicPlayer::emit_sync_inner(&bm, &cs_arc, &st, true);
+    });
+}
+
+/// Fires once the current track has been listened to long enough to count as played.
+extern "C" fn played_sync_callback(
+    _handle: u32,
+    channel: u32,
+    _data: u32,
+    user: *mut std::ffi::c_void,
+) {
+    if user.is_null() {
+        return;
+    }
+
+    let sync_data = unsafe { &*(user as *const SyncData) };
+    let cs_arc = Arc::clone(&sync_data.current_stream);
+    let st = Arc::clone(&sync_data.state);
+
+    tauri::async_runtime::spawn_blocking(move || {
+        if channel != cs_arc.load(Ordering::SeqCst) {
+            return;
+        }
+
+        let path = match st.lock() {
+            Ok(state) => state
+                .current_index
+                .and_then(|i| state.playlist.get(i))
+                .map(|item| item.metadata.path.clone()),
+            Err(e) => {
+                crate::error!("Failed to lock player state: {}", e);
+                return;
+            }
+        };
+
+        if let Some(path) = path {
+            if let Err(e) = PlayHistory::record(&path) {
+                crate::error!("Failed to record play for {}: {}", path, e);
+            }
+        }
+    });
+}
+
+/// Runs the equalizer over the mixer output. `user` points at the player's `Mutex<Equalizer>`,
+/// which outlives the mixer it is attached to.
+#[cfg(desktop)]
+extern "C" fn equalizer_dsp_callback(
+    _handle: u32,
+    _channel: u32,
+    buffer: *mut std::ffi::c_void,
+    length: u32,
+    user: *mut std::ffi::c_void,
+) {
+    if user.is_null() || buffer.is_null() {
+        return;
+    }
+
+    let equalizer = unsafe { &*(user as *const Mutex<Equalizer>) };
+    let samples = unsafe {
+        std::slice::from_raw_parts_mut(
+            buffer as *mut f32,
+            length as usize / std::mem::size_of::<f32>(),
+        )
+    };
+
+    if let Ok(mut equalizer) = equalizer.lock() {
+        equalizer.process(samples);
+    }
+}
+
 extern "C" fn free_sync_callback(_: u32, _: u32, _: u32, user: *mut std::ffi::c_void) {
     if !user.is_null() {
         unsafe {
@@ -207,8 +488,20 @@ impl MusicPlayer {
                 playlist: Vec::new(),
                 current_index: None,
                 repeat_mode: RepeatMode::None,
+                shuffle_order: None,
+                volume: 1.0,
+                bit_perfect: false,
+                is_bit_perfect: false,
+                equalizer_dsp: 0,
+                mixer_format: None,
+                replay_gain: ReplayGainSettings::load(),
+                crossfade: CrossfadeSettings::load(),
+                fade_in: None,
+                fading_in: None,
+                fading_stream: None,
             })),
             temp_wav_path: Arc::new(Mutex::new(None)),
+            equalizer: Arc::new(Mutex::new(Equalizer::new(EqualizerSettings::load()))),
         };
 
         player.start_focus_listener();
@@ -225,35 +518,32 @@ impl MusicPlayer {
     fn init_bass(&self) {
         #[cfg(desktop)]
         unsafe {
-            let mut i = 0;
-            let mut info = std::mem::zeroed::<BASS_DEVICEINFO>();
-            while BASS_GetDeviceInfo(i, &mut info) != 0 {
-                let name = if info.name.is_null() {
-                    "Unknown".to_string()
-                } else {
-                    std::ffi::CStr::from_ptr(info.name)
-                        .to_string_lossy()
-                        .into_owned()
-                };
-                let driver = if info.driver.is_null() {
-                    "Unknown".to_string()
-                } else {
-                    std::ffi::CStr::from_ptr(info.driver)
-                        .to_string_lossy()
-                        .into_owned()
-                };
-
-                if (info.flags & 2) != 0 {
-                    // BASS_DEVICE_DEFAULT
-                    crate::info!("Default Audio Device: {} ({})", name, driver);
-                } else if (info.flags & 1) != 0 {
-                    // BASS_DEVICE_ENABLED
-                    crate::debug!("Available Audio Device {}: {} ({})", i, name, driver);
+            for device in OutputDevice::get_all() {
+                if device.is_default {
+                    crate::info!("Default Audio Device: {} ({})", device.name, device.driver);
+                } else if device.is_enabled {
+                    crate::debug!(
+                        "Available Audio Device {}: {} ({})",
+                        device.index,
+                        device.name,
+                        device.driver
+                    );
                 }
-                i += 1;
             }
 
-            if BASS_Init(-1, 192000, 0, ptr::null_mut(), ptr::null_mut()) == 0 {
+            let mut device = OutputDevice::find_preferred()
+                .map(|i| i as i32)
+                .unwrap_or(-1);
+            if device != -1 && BASS_Init(device, 192000, 0, ptr::null_mut(), ptr::null_mut()) == 0 {
+                crate::warn!(
+                    "Failed to initialize preferred output device {}, error: {}. Using default.",
+                    device,
+                    BASS_ErrorGetCode()
+                );
+                device = -1;
+            }
+
+            if device == -1 && BASS_Init(-1, 192000, 0, ptr::null_mut(), ptr::null_mut()) == 0 {
                 crate::error!("Failed to initialize BASS, error: {}", BASS_ErrorGetCode());
             } else {
                 let mut info = std::mem::zeroed::<BASS_INFO>();
@@ -300,15 +590,14 @@ impl MusicPlayer {
                 }
             }
 
-            let mixer = BASS_Mixer_StreamCreate(44100, 2, BASS_SAMPLE_FLOAT);
-            if mixer == 0 {
-                crate::error!(
-                    "Failed to create BASS mixer stream, error: {}",
-                    BASS_ErrorGetCode()
-                );
-            } else {
+            let mixer = Self::create_mixer(STANDARD_MIXER_FORMAT);
+            if mixer != 0 {
                 crate::info!("BASS mixer created successfully");
                 self.bass_mixer.store(mixer, Ordering::SeqCst);
+                Self::attach_equalizer(mixer, STANDARD_MIXER_FORMAT, &self.equalizer, &self.state);
+                if let Ok(mut state) = self.state.lock() {
+                    state.mixer_format = Some(STANDARD_MIXER_FORMAT);
+                }
             }
         }
 
@@ -367,6 +656,7 @@ impl MusicPlayer {
 
     pub fn pause(&self) {
         self.play_pause(false);
+        self.save_session();
     }
 
     pub fn next(&self) {
@@ -388,6 +678,20 @@ impl MusicPlayer {
         self.emit_sync(false);
     }
 
+    pub fn set_shuffle(&self, enable: bool) {
+        if let Ok(mut state) = self.state.lock() {
+            if state.shuffle_order.is_some() == enable {
+                return;
+            }
+            if enable {
+                state.build_shuffle_order();
+            } else {
+                state.shuffle_order = None;
+            }
+        }
+        self.emit_sync(false);
+    }
+
     pub fn set_pos(&self, position: u64) {
         let bass_mixer = self.bass_mixer.load(Ordering::SeqCst);
         let current_stream = self.current_stream.load(Ordering::SeqCst);
@@ -437,6 +741,8 @@ impl MusicPlayer {
                 }
             }
         }
+
+        Self::save_session_inner(&self.state, position as f64);
     }
 
     pub fn get_current_duration(&self) -> f64 {
@@ -509,22 +815,26 @@ impl MusicPlayer {
             }
         };
 
-        let (index, repeat_mode) = self
+        let (index, repeat_mode, is_shuffle, is_bit_perfect) = self
             .state
             .lock()
             .map(|s| {
                 (
                     s.current_index.map(|i| i as i64).unwrap_or(-1),
                     s.repeat_mode,
+                    s.shuffle_order.is_some(),
+                    s.is_bit_perfect,
                 )
             })
-            .unwrap_or((-1, RepeatMode::None));
+            .unwrap_or((-1, RepeatMode::None, false, false));
 
         MusicPlayerSync {
             index,
             current_position,
             is_playing,
             repeat_mode,
+            is_shuffle,
+            is_bit_perfect,
         }
     }
 
@@ -539,10 +849,14 @@ impl MusicPlayer {
                 }
             };
             was_empty = state.playlist.is_empty();
+            let start = state.playlist.len();
             for music in playlist {
                 state.playlist.push(PlaylistItem { metadata: music });
             }
+            let end = state.playlist.len();
+            state.shuffle_in(start..end);
         }
+        self.save_queue();
 
         if was_empty {
             self.goto_playlist(0);
@@ -572,6 +886,13 @@ impl MusicPlayer {
             return;
         }
 
+        if let Some(order) = state.shuffle_order.as_mut() {
+            order.retain(|&i| i != index);
+            for i in order.iter_mut().filter(|i| **i > index) {
+                *i -= 1;
+            }
+        }
+
         if let Some(current) = state.current_index {
             if current == index {
                 drop(state);
@@ -580,6 +901,7 @@ impl MusicPlayer {
                     state.current_index = None;
                     state.playlist.remove(index);
                 }
+                self.save_queue();
                 return;
             }
         }
@@ -591,12 +913,16 @@ impl MusicPlayer {
                 state.current_index = Some(current - 1);
             }
         }
+        drop(state);
+        self.save_queue();
 
         #[cfg(target_os = "android")]
         {
-            let current_index = state.current_index;
-            let total_count = state.playlist.len();
-            drop(state);
+            let (current_index, total_count) = self
+                .state
+                .lock()
+                .map(|s| (s.current_index, s.playlist.len()))
+                .unwrap_or((None, 0));
             self.update_android_media_boundaries(current_index, total_count);
         }
     }
@@ -624,6 +950,10 @@ impl MusicPlayer {
 
             Self::stop_stream(&bass_mixer, &current_stream, &temp_wav_path);
 
+            if let Ok(mut state) = state_arc.lock() {
+                state.shuffle_promote(index);
+            }
+
             if Self::load_music_inner(
                 &bass_mixer,
                 &current_stream,
@@ -659,10 +989,9 @@ impl MusicPlayer {
                 };
                 match (state.current_index, state.repeat_mode) {
                     (Some(current), RepeatMode::One) if !from_user => Some(current),
-                    (Some(current), _) if current + 1 < state.playlist.len() => Some(current + 1),
-                    (Some(_), RepeatMode::All) => Some(0),
-                    (Some(_), _) if from_user => Some(0),
-                    _ => None,
+                    (_, repeat_mode) => {
+                        state.following_index(from_user || repeat_mode == RepeatMode::All)
+                    }
                 }
             };
 
@@ -678,36 +1007,45 @@ impl MusicPlayer {
                     (state.playlist[index].metadata.clone(), state.playlist.len())
                 };
 
-                // Remove old stream from mixer. Flush buffer only on user-initiated skip
-                // to preserve gapless auto-advance.
-                let bm = bass_mixer.load(Ordering::SeqCst);
-                let cs = current_stream.load(Ordering::SeqCst);
-                #[cfg(desktop)]
-                unsafe {
-                    if cs != 0 {
-                        BASS_Mixer_ChannelRemove(cs);
-                        BASS_StreamFree(cs);
-                        current_stream.store(0, Ordering::SeqCst);
-                    }
-                    if from_user && bm != 0 {
-                        BASS_ChannelSetPosition(bm, 0, BASS_POS_BYTE);
-                    }
-                }
-                #[cfg(target_os = "android")]
-                if let Some(bass) = bass_android::get_bass() {
+                // A user-initiated skip fades over like a crossfade when one applies.
+                let fade = if from_user {
+                    Self::start_skip_fade(&bass_mixer, &current_stream, &state_arc, &music)
+                } else {
+                    None
+                };
+
+                // Otherwise remove old stream from mixer. Flush buffer only on user-initiated
+                // skip to preserve gapless auto-advance.
+                if fade.is_none() {
+                    let bm = bass_mixer.load(Ordering::SeqCst);
+                    let cs = current_stream.load(Ordering::SeqCst);
+                    #[cfg(desktop)]
                     unsafe {
                         if cs != 0 {
-                            (bass.bass_mixer_channel_remove)(cs);
-                            (bass.bass_stream_free)(cs);
+                            BASS_Mixer_ChannelRemove(cs);
+                            BASS_StreamFree(cs);
                             current_stream.store(0, Ordering::SeqCst);
                         }
                         if from_user && bm != 0 {
-                            (bass.bass_channel_set_position)(bm, 0, BASS_POS_BYTE);
+                            BASS_ChannelSetPosition(bm, 0, BASS_POS_BYTE);
+                        }
+                    }
+                    #[cfg(target_os = "android")]
+                    if let Some(bass) = bass_android::get_bass() {
+                        unsafe {
+                            if cs != 0 {
+                                (bass.bass_mixer_channel_remove)(cs);
+                                (bass.bass_stream_free)(cs);
+                                current_stream.store(0, Ordering::SeqCst);
+                            }
+                            if from_user && bm != 0 {
+                                (bass.bass_channel_set_position)(bm, 0, BASS_POS_BYTE);
+                            }
                         }
                     }
                 }
 
-                if Self::load_music_inner(
+                let loaded = Self::load_music_inner(
                     &bass_mixer,
                     &current_stream,
                     &state_arc,
@@ -715,7 +1053,11 @@ impl MusicPlayer {
                     music,
                     index,
                     total_count,
-                ) {
+                );
+                if let Some((channel, duration)) = fade {
+                    Self::finish_skip_fade(&state_arc, channel, duration, loaded);
+                }
+                if loaded {
                     if let Ok(mut state) = state_arc.lock() {
                         state.current_index = Some(index);
                     }
@@ -816,11 +1158,7 @@ impl MusicPlayer {
                         return;
                     }
                 };
-                match state.current_index {
-                    Some(current) if current > 0 => Some(current - 1),
-                    Some(_) if !state.playlist.is_empty() => Some(state.playlist.len() - 1),
-                    _ => None,
-                }
+                state.preceding_index()
             };
 
             if let Some(index) = prev_index {
@@ -835,9 +1173,12 @@ impl MusicPlayer {
                     (state.playlist[index].metadata.clone(), state.playlist.len())
                 };
 
-                Self::stop_stream(&bass_mixer, &current_stream, &temp_wav_path);
+                let fade = Self::start_skip_fade(&bass_mixer, &current_stream, &state_arc, &music);
+                if fade.is_none() {
+                    Self::stop_stream(&bass_mixer, &current_stream, &temp_wav_path);
+                }
 
-                if Self::load_music_inner(
+                let loaded = Self::load_music_inner(
                     &bass_mixer,
                     &current_stream,
                     &state_arc,
@@ -845,7 +1186,11 @@ impl MusicPlayer {
                     music,
                     index,
                     total_count,
-                ) {
+                );
+                if let Some((channel, duration)) = fade {
+                    Self::finish_skip_fade(&state_arc, channel, duration, loaded);
+                }
+                if loaded {
                     if let Ok(mut state) = state_arc.lock() {
                         state.current_index = Some(index);
                     }
@@ -874,17 +1219,15 @@ impl MusicPlayer {
             state.playlist.insert(to, item);
 
             if let Some(current) = state.current_index {
-                state.current_index = Some(if current == from {
-                    to
-                } else if from < current && to >= current {
-                    current - 1
-                } else if from > current && to <= current {
-                    current + 1
-                } else {
-                    current
-                });
+                state.current_index = Some(moved_index(current, from, to));
+            }
+            if let Some(order) = state.shuffle_order.as_mut() {
+                for i in order.iter_mut() {
+                    *i = moved_index(*i, from, to);
+                }
             }
         }
+        self.save_queue();
         self.emit_sync(false);
     }
 
@@ -892,6 +1235,18 @@ impl MusicPlayer {
         let bass_mixer = self.bass_mixer.load(Ordering::SeqCst);
         let clamped = volume.clamp(0.0, 1.0);
 
+        // Bit-perfect output keeps the mixer at unity gain; the volume is applied once it's off.
+        let bit_perfect = match self.state.lock() {
+            Ok(mut state) => {
+                state.volume = clamped;
+                state.bit_perfect
+            }
+            Err(_) => false,
+        };
+        if bit_perfect {
+            return;
+        }
+
         #[cfg(desktop)]
         unsafe {
             if bass_mixer != 0
@@ -917,68 +1272,939 @@ impl MusicPlayer {
     }
 
     pub fn equalizer(&self, values: Vec<f32>) {
-        crate::info!(
-            "Equalizer called with {} bands (not yet implemented)",
-            values.len()
-        );
-        // TODO: Implement BASS_FX equalizer with proper DSP chain
+        self.update_equalizer(|equalizer| equalizer.set_gains(&values));
     }
 
     pub fn reset_equalizer(&self) {
-        crate::info!("Reset equalizer (not yet implemented)");
-        // TODO: Clear BASS_FX equalizer DSP chain
+        self.update_equalizer(|equalizer| equalizer.reset());
     }
 
-    pub fn toggle_bit_perfect(&self, enable: bool) {
-        crate::info!(
-            "Bit-perfect mode toggle (not yet implemented for BASS): {}",
-            enable
-        );
+    pub fn get_equalizer(&self) -> EqualizerSettings {
+        self.equalizer
+            .lock()
+            .map(|equalizer| equalizer.settings().clone())
+            .unwrap_or_default()
     }
 
-    pub fn request_sync(&self) {
-        self.emit_sync(false);
+    pub fn set_equalizer(&self, settings: EqualizerSettings) {
+        self.update_equalizer(|equalizer| equalizer.set_settings(settings));
     }
 
-    pub fn emit_sync(&self, is_reset: bool) {
-        app_handle()
-            .emit(
-                crate::commands::route::MUSIC_PLAYER_SYNC,
-                self.get_sync_info(is_reset),
-            )
-            .unwrap();
+    fn update_equalizer(&self, update: impl FnOnce(&mut Equalizer)) {
+        let settings = match self.equalizer.lock() {
+            Ok(mut equalizer) => {
+                update(&mut equalizer);
+                equalizer.settings().clone()
+            }
+            Err(e) => {
+                crate::error!("Failed to lock equalizer: {}", e);
+                return;
+            }
+        };
+        settings.save();
     }
 
-    fn play_pause(&self, play: bool) {
-        Self::play_pause_inner(&self.bass_mixer, &self.current_stream, play);
+    pub fn get_replay_gain(&self) -> ReplayGainSettings {
+        self.state
+            .lock()
+            .map(|state| state.replay_gain)
+            .unwrap_or_default()
     }
 
-    fn play_pause_inner(bass_mixer: &Arc<AtomicU32>, _current_stream: &Arc<AtomicU32>, play: bool) {
-        let bm = bass_mixer.load(Ordering::SeqCst);
+    pub fn set_replay_gain(&self, settings: ReplayGainSettings) {
+        match self.state.lock() {
+            Ok(mut state) => state.replay_gain = settings,
+            Err(e) => {
+                crate::error!("Failed to lock player state: {}", e);
+                return;
+            }
+        }
+        settings.save();
+        self.reapply_replay_gain();
+    }
+
+    /// Apply the current ReplayGain settings to the track that is already playing. A track that
+    /// is still fading in slides to the new level over the rest of its fade instead of jumping.
+    fn reapply_replay_gain(&self) {
+        let stream = self.current_stream.load(Ordering::SeqCst);
+        if stream == 0 {
+            return;
+        }
+
+        let (volume, slide) = match self.state.lock() {
+            Ok(state) => {
+                let Some(music) = state
+                    .current_index
+                    .and_then(|i| state.playlist.get(i))
+                    .map(|item| &item.metadata)
+                else {
+                    return;
+                };
+                let slide = state
+                    .fading_in
+                    .filter(|(channel, _)| *channel == stream)
+                    .map(|(_, end)| {
+                        end.saturating_duration_since(Instant::now()).as_millis() as u32
+                    })
+                    .filter(|remaining| *remaining > 0);
+                (Self::replay_gain_volume(&state, music), slide)
+            }
+            Err(_) => return,
+        };
+
+        Self::set_stream_volume(stream, None, volume, slide);
+    }
+
+    fn replay_gain_volume(state: &PlayerState, music: &MusicMetadata) -> f32 {
+        if state.bit_perfect {
+            1.0
+        } else {
+            state.replay_gain.volume_for(music)
+        }
+    }
+
+    /// Scale a track's channel in the mixer by its ReplayGain. Bit-perfect output leaves the
+    /// samples untouched, like the volume. When crossfading into the track, the channel starts
+    /// silent and slides up to that level instead.
+    fn apply_replay_gain(stream: u32, state: &Arc<Mutex<PlayerState>>, music: &MusicMetadata) {
+        let (volume, fade_in) = match state.lock() {
+            Ok(mut state) => {
+                let volume = Self::replay_gain_volume(&state, music);
+                let fade_in = state.fade_in.take();
+                state.fading_in = fade_in.map(|duration| {
+                    (
+                        stream,
+                        Instant::now() + Duration::from_millis(duration as u64),
+                    )
+                });
+                (volume, fade_in)
+            }
+            Err(_) => return,
+        };
+
+        match fade_in {
+            Some(duration) => Self::set_stream_volume(stream, Some(0.0), volume, Some(duration)),
+            None => Self::set_stream_volume(stream, None, volume, None),
+        }
+    }
 
+    /// Set a channel's volume, or slide it there over `slide` milliseconds starting from `from`
+    /// (or wherever it currently is).
+    fn set_stream_volume(stream: u32, from: Option<f32>, volume: f32, slide: Option<u32>) {
         #[cfg(desktop)]
         unsafe {
-            if bm == 0 {
-                return;
-            }
-            if play {
-                if BASS_ChannelPlay(bm, 0) == 0 {
-                    crate::error!("Failed to play, error: {}", BASS_ErrorGetCode());
+            let ok = match slide {
+                Some(duration) => {
+                    if let Some(from) = from {
+                        BASS_ChannelSetAttribute(stream, BASS_ATTRIB_VOL, from);
+                    }
+                    BASS_ChannelSlideAttribute(stream, BASS_ATTRIB_VOL, volume, duration) != 0
                 }
-            } else if BASS_ChannelPause(bm) == 0 {
-                crate::error!("Failed to pause, error: {}", BASS_ErrorGetCode());
+                None => BASS_ChannelSetAttribute(stream, BASS_ATTRIB_VOL, volume) != 0,
+            };
+            if !ok {
+                crate::error!("Failed to set ReplayGain, error: {}", BASS_ErrorGetCode());
             }
         }
 
         #[cfg(target_os = "android")]
         if let Some(bass) = bass_android::get_bass() {
             unsafe {
-                if bm == 0 {
-                    return;
-                }
-                if play {
-                    if (bass.bass_channel_play)(bm, 0) == 0 {
-                        crate::error!("Failed to play, error: {}", (bass.bass_error_get_code)());
+                let ok = match slide {
+                    Some(duration) => {
+                        if let Some(from) = from {
+                            (bass.bass_channel_set_attribute)(stream, BASS_ATTRIB_VOL, from);
+                        }
+                        (bass.bass_channel_slide_attribute)(
+                            stream,
+                            BASS_ATTRIB_VOL,
+                            volume,
+                            duration,
+                        ) != 0
+                    }
+                    None => (bass.bass_channel_set_attribute)(stream, BASS_ATTRIB_VOL, volume) != 0,
+                };
+                if !ok {
+                    crate::error!(
+                        "Failed to set ReplayGain, error: {}",
+                        (bass.bass_error_get_code)()
+                    );
+                }
+            }
+        }
+    }
+
+    pub fn get_crossfade(&self) -> CrossfadeSettings {
+        self.state
+            .lock()
+            .map(|state| state.crossfade)
+            .unwrap_or_default()
+    }
+
+    /// Takes effect from the next track, since the crossfade point is set when a track loads.
+    pub fn set_crossfade(&self, settings: CrossfadeSettings) {
+        let settings = settings.clamped();
+        match self.state.lock() {
+            Ok(mut state) => state.crossfade = settings,
+            Err(e) => {
+                crate::error!("Failed to lock player state: {}", e);
+                return;
+            }
+        }
+        settings.save();
+    }
+
+    /// Keep the playing track in the mixer so a user-initiated skip to `next` can crossfade,
+    /// returning the outgoing channel and the fade duration. Nothing fades while paused or in
+    /// bit-perfect mode.
+    fn start_skip_fade(
+        bass_mixer: &Arc<AtomicU32>,
+        current_stream: &Arc<AtomicU32>,
+        state: &Arc<Mutex<PlayerState>>,
+        next: &MusicMetadata,
+    ) -> Option<(u32, u32)> {
+        let channel = current_stream.load(Ordering::SeqCst);
+        if channel == 0 || !Self::is_mixer_playing(bass_mixer.load(Ordering::SeqCst)) {
+            return None;
+        }
+
+        // Skipping again mid-fade cuts the older track short.
+        Self::free_fading_stream(state);
+
+        let mut state = state.lock().ok()?;
+        if state.bit_perfect {
+            return None;
+        }
+        let current = &state.playlist.get(state.current_index?)?.metadata;
+        if !state.crossfade.should_crossfade(current, next) {
+            return None;
+        }
+        let duration = state.crossfade.duration;
+        state.fade_in = Some(duration);
+        Some((channel, duration))
+    }
+
+    /// Fade the outgoing track out once the next one is in the mixer. If it failed to load,
+    /// the outgoing track keeps playing.
+    fn finish_skip_fade(
+        state: &Arc<Mutex<PlayerState>>,
+        channel: u32,
+        duration: u32,
+        loaded: bool,
+    ) {
+        if let Ok(mut state) = state.lock() {
+            state.fade_in = None;
+            if loaded {
+                state.fading_stream = Some(channel);
+            }
+        }
+        if loaded {
+            Self::fade_out(channel, duration);
+        }
+    }
+
+    fn fade_out(channel: u32, duration: u32) {
+        #[cfg(desktop)]
+        unsafe {
+            BASS_ChannelSlideAttribute(channel, BASS_ATTRIB_VOL, 0.0, duration);
+        }
+        #[cfg(target_os = "android")]
+        if let Some(bass) = bass_android::get_bass() {
+            unsafe {
+                (bass.bass_channel_slide_attribute)(channel, BASS_ATTRIB_VOL, 0.0, duration);
+            }
+        }
+    }
+
+    fn is_mixer_playing(bass_mixer: u32) -> bool {
+        if bass_mixer == 0 {
+            return false;
+        }
+        #[cfg(desktop)]
+        unsafe {
+            BASS_ChannelIsActive(bass_mixer) == BASS_ACTIVE_PLAYING
+        }
+        #[cfg(target_os = "android")]
+        {
+            bass_android::get_bass()
+                .map(|bass| unsafe {
+                    (bass.bass_channel_is_active)(bass_mixer) == BASS_ACTIVE_PLAYING
+                })
+                .unwrap_or(false)
+        }
+    }
+
+    /// Drop the track that is still fading out after a crossfade.
+    fn free_fading_stream(state: &Arc<Mutex<PlayerState>>) {
+        let fading = match state.lock() {
+            Ok(mut state) => state.fading_stream.take(),
+            Err(_) => None,
+        };
+        let Some(stream) = fading else {
+            return;
+        };
+
+        #[cfg(desktop)]
+        unsafe {
+            BASS_Mixer_ChannelRemove(stream);
+            BASS_StreamFree(stream);
+        }
+
+        #[cfg(target_os = "android")]
+        if let Some(bass) = bass_android::get_bass() {
+            unsafe {
+                (bass.bass_mixer_channel_remove)(stream);
+                (bass.bass_stream_free)(stream);
+            }
+        }
+    }
+
+    /// Attach the equalizer DSP to a freshly created mixer.
+    #[cfg(desktop)]
+    fn attach_equalizer(
+        mixer: u32,
+        format: MixerFormat,
+        equalizer: &Arc<Mutex<Equalizer>>,
+        state: &Arc<Mutex<PlayerState>>,
+    ) {
+        if let Ok(mut eq) = equalizer.lock() {
+            eq.set_format(format.sample_rate, format.channels as usize);
+        }
+
+        let dsp = unsafe {
+            BASS_ChannelSetDSP(
+                mixer,
+                Some(equalizer_dsp_callback),
+                Arc::as_ptr(equalizer) as *mut _,
+                BASS_DSP_PRIORITY_EQUALIZER,
+            )
+        };
+        if dsp == 0 {
+            crate::error!("Failed to attach equalizer DSP, error: {}", unsafe {
+                BASS_ErrorGetCode()
+            });
+        }
+        if let Ok(mut state) = state.lock() {
+            state.equalizer_dsp = dsp;
+        }
+    }
+
+    /// Take the equalizer and the volume off `mixer`, so samples pass through it untouched.
+    /// Returns whether both are gone.
+    #[cfg(desktop)]
+    fn detach_processing(mixer: u32, state: &Arc<Mutex<PlayerState>>) -> bool {
+        let dsp = state
+            .lock()
+            .map(|mut s| std::mem::take(&mut s.equalizer_dsp))
+            .unwrap_or(0);
+
+        unsafe {
+            let detached = dsp == 0 || BASS_ChannelRemoveDSP(mixer, dsp) != 0;
+            if !detached {
+                crate::error!(
+                    "Failed to remove equalizer DSP, error: {}",
+                    BASS_ErrorGetCode()
+                );
+            }
+            let unity = BASS_ChannelSetAttribute(mixer, BASS_ATTRIB_VOL, 1.0) != 0;
+            if !unity {
+                crate::error!(
+                    "Failed to reset mixer volume, error: {}",
+                    BASS_ErrorGetCode()
+                );
+            }
+            detached && unity
+        }
+    }
+
+    pub fn toggle_bit_perfect(&self, enable: bool) {
+        let (current_bits, volume) = match self.state.lock() {
+            Ok(mut state) => {
+                if state.bit_perfect == enable {
+                    return;
+                }
+                state.bit_perfect = enable;
+                if !enable {
+                    state.is_bit_perfect = false;
+                }
+                (
+                    state
+                        .current_index
+                        .and_then(|i| state.playlist.get(i))
+                        .and_then(|item| item.metadata.bits_per_sample),
+                    state.volume,
+                )
+            }
+            Err(e) => {
+                crate::error!("Failed to lock player state: {}", e);
+                return;
+            }
+        };
+        crate::info!("Bit-perfect mode: {}", enable);
+        Self::free_fading_stream(&self.state);
+
+        #[cfg(desktop)]
+        {
+            let cs = self.current_stream.load(Ordering::SeqCst);
+            if enable {
+                if cs != 0 {
+                    Self::prepare_bit_perfect_mixer(
+                        &self.bass_mixer,
+                        &self.state,
+                        cs,
+                        current_bits,
+                        true,
+                    );
+                }
+            } else {
+                let mixer = Self::replace_mixer(&self.bass_mixer, STANDARD_MIXER_FORMAT, cs);
+                if mixer != 0 {
+                    Self::attach_equalizer(
+                        mixer,
+                        STANDARD_MIXER_FORMAT,
+                        &self.equalizer,
+                        &self.state,
+                    );
+                    unsafe {
+                        BASS_ChannelSetAttribute(mixer, BASS_ATTRIB_VOL, volume);
+                    }
+                }
+                if let Ok(mut state) = self.state.lock() {
+                    state.mixer_format = Some(STANDARD_MIXER_FORMAT);
+                }
+            }
+        }
+
+        #[cfg(target_os = "android")]
+        {
+            let _ = (current_bits, volume);
+            crate::warn!("Bit-perfect output is not supported on Android");
+        }
+
+        self.reapply_replay_gain();
+        self.emit_sync(false);
+    }
+
+    /// Move playback to another output device. The mixer is carried over to the new device so
+    /// the queue and position survive; `index` of `None` selects the system default.
+    #[cfg(desktop)]
+    pub fn set_output_device(&self, index: Option<u32>) -> Result<(), String> {
+        let devices = OutputDevice::get_all();
+        let target = match index {
+            Some(i) => devices
+                .iter()
+                .find(|d| d.index == i && d.is_enabled)
+                .ok_or_else(|| format!("Output device {} is not available", i))?,
+            None => devices
+                .iter()
+                .find(|d| d.is_default)
+                .ok_or_else(|| "No default output device".to_string())?,
+        };
+
+        let (bit_perfect, current_bits) = self
+            .state
+            .lock()
+            .map(|s| {
+                (
+                    s.bit_perfect,
+                    s.current_index
+                        .and_then(|i| s.playlist.get(i))
+                        .and_then(|item| item.metadata.bits_per_sample),
+                )
+            })
+            .unwrap_or((false, None));
+
+        unsafe {
+            let old = BASS_GetDevice();
+            if old != target.index {
+                let mut info = std::mem::zeroed::<BASS_INFO>();
+                let freq = if BASS_GetInfo(&mut info) != 0 {
+                    info.freq
+                } else {
+                    192000
+                };
+                let flags = if bit_perfect { BASS_DEVICE_FREQ } else { 0 };
+
+                // A device that was already initialised is left as it is if the move fails
+                let initialized = BASS_Init(
+                    target.index as i32,
+                    freq,
+                    flags,
+                    ptr::null_mut(),
+                    ptr::null_mut(),
+                ) != 0;
+                if !initialized && BASS_ErrorGetCode() != BASS_ERROR_ALREADY {
+                    return Err(format!(
+                        "Failed to initialize output device {}, error: {}",
+                        target.name,
+                        BASS_ErrorGetCode()
+                    ));
+                }
+
+                let bm = self.bass_mixer.load(Ordering::SeqCst);
+                if bm != 0 && BASS_ChannelSetDevice(bm, target.index) == 0 {
+                    let error = BASS_ErrorGetCode();
+                    if initialized {
+                        BASS_SetDevice(target.index);
+                        BASS_Free();
+                    }
+                    BASS_SetDevice(old);
+                    return Err(format!(
+                        "Failed to move playback to {}, error: {}",
+                        target.name, error
+                    ));
+                }
+
+                let position = self.get_current_duration();
+                let was_playing = bm != 0 && BASS_ChannelIsActive(bm) == BASS_ACTIVE_PLAYING;
+
+                BASS_SetDevice(old);
+                BASS_Free();
+                BASS_SetDevice(target.index);
+
+                // Freeing the old device can take the decoding stream with it; reload the
+                // track at the same position if that happened.
+                let cs = self.current_stream.load(Ordering::SeqCst);
+                let mut channel_info = std::mem::zeroed::<BASS_CHANNELINFO>();
+                if cs != 0 && BASS_ChannelGetInfo(cs, &mut channel_info) == 0 {
+                    self.current_stream.store(0, Ordering::SeqCst);
+                    self.reload_current(position as u64, was_playing);
+                }
+
+                crate::info!(
+                    "Output device switched to {} ({})",
+                    target.name,
+                    target.driver
+                );
+            }
+        }
+
+        if bit_perfect {
+            let cs = self.current_stream.load(Ordering::SeqCst);
+            if cs != 0 {
+                Self::prepare_bit_perfect_mixer(
+                    &self.bass_mixer,
+                    &self.state,
+                    cs,
+                    current_bits,
+                    true,
+                );
+            }
+        }
+
+        OutputDevice::save_preference(index.map(|_| OutputDevicePreference {
+            name: target.name.clone(),
+            driver: target.driver.clone(),
+        }));
+        self.emit_sync(false);
+        Ok(())
+    }
+
+    /// Load the current track again and seek to `position` (ms).
+    #[cfg(desktop)]
+    fn reload_current(&self, position: u64, play: bool) {
+        let (music, index, total_count) = match self.state.lock() {
+            Ok(state) => match state.current_index {
+                Some(i) if i < state.playlist.len() => {
+                    (state.playlist[i].metadata.clone(), i, state.playlist.len())
+                }
+                _ => return,
+            },
+            Err(e) => {
+                crate::error!("Failed to lock player state: {}", e);
+                return;
+            }
+        };
+
+        Self::cleanup_temp_wav_inner(&self.temp_wav_path);
+        if Self::load_music_inner(
+            &self.bass_mixer,
+            &self.current_stream,
+            &self.state,
+            &self.temp_wav_path,
+            music,
+            index,
+            total_count,
+        ) {
+            self.set_pos(position);
+            if !play {
+                self.pause();
+            }
+        }
+    }
+
+    /// Create a mixer stream in the given output format.
+    #[cfg(desktop)]
+    fn create_mixer(format: MixerFormat) -> u32 {
+        let flags = if format.float { BASS_SAMPLE_FLOAT } else { 0 };
+        let mixer = unsafe { BASS_Mixer_StreamCreate(format.sample_rate, format.channels, flags) };
+        if mixer == 0 {
+            crate::error!(
+                "Failed to create BASS mixer stream ({} Hz, {} ch), error: {}",
+                format.sample_rate,
+                format.channels,
+                unsafe { BASS_ErrorGetCode() }
+            );
+        }
+        mixer
+    }
+
+    /// Swap the mixer for one in a new format, carrying over `stream` (if any) and the
+    /// playing state. Returns the new mixer handle, or 0 on failure.
+    #[cfg(desktop)]
+    fn replace_mixer(bass_mixer: &Arc<AtomicU32>, format: MixerFormat, stream: u32) -> u32 {
+        let old = bass_mixer.load(Ordering::SeqCst);
+
+        unsafe {
+            let was_playing = old != 0 && BASS_ChannelIsActive(old) == BASS_ACTIVE_PLAYING;
+            if stream != 0 {
+                BASS_Mixer_ChannelRemove(stream);
+            }
+            if old != 0 {
+                BASS_ChannelStop(old);
+                BASS_StreamFree(old);
+            }
+
+            let mixer = Self::create_mixer(format);
+            bass_mixer.store(mixer, Ordering::SeqCst);
+            if mixer == 0 {
+                return 0;
+            }
+
+            if stream != 0 && BASS_Mixer_StreamAddChannel(mixer, stream, BASS_MIXER_NORAMPIN) == 0 {
+                crate::error!(
+                    "Failed to move channel to the new mixer, error: {}",
+                    BASS_ErrorGetCode()
+                );
+            }
+            if was_playing {
+                BASS_ChannelPlay(mixer, 0);
+            }
+
+            crate::info!(
+                "BASS mixer recreated at {} Hz, {} ch, {}",
+                format.sample_rate,
+                format.channels,
+                if format.float { "float" } else { "16-bit" }
+            );
+            mixer
+        }
+    }
+
+    /// Match the mixer and the output device to `stream`'s native format when bit-perfect
+    /// output is requested, and record whether the resulting path is actually bit-perfect.
+    /// `attached` tells whether `stream` is already plugged into the current mixer.
+    #[cfg(desktop)]
+    fn prepare_bit_perfect_mixer(
+        bass_mixer: &Arc<AtomicU32>,
+        state: &Arc<Mutex<PlayerState>>,
+        stream: u32,
+        bits_per_sample: Option<u32>,
+        attached: bool,
+    ) {
+        let (requested, mixer_format) = state
+            .lock()
+            .map(|s| (s.bit_perfect, s.mixer_format))
+            .unwrap_or((false, None));
+
+        if !requested {
+            if let Ok(mut state) = state.lock() {
+                state.is_bit_perfect = false;
+            }
+            return;
+        }
+
+        let mut info = unsafe { std::mem::zeroed::<BASS_CHANNELINFO>() };
+        if unsafe { BASS_ChannelGetInfo(stream, &mut info) } == 0 {
+            crate::error!("Failed to get channel info, error: {}", unsafe {
+                BASS_ErrorGetCode()
+            });
+            if let Ok(mut state) = state.lock() {
+                state.is_bit_perfect = false;
+            }
+            return;
+        }
+
+        let bits = bits_per_sample
+            .filter(|b| *b > 0)
+            .unwrap_or(info.origres & BASS_ORIGRES_BITS_MASK);
+        let format = MixerFormat {
+            sample_rate: info.freq,
+            channels: info.chans,
+            float: bits != 16,
+        };
+
+        let device_rate = Self::set_output_rate(info.freq);
+
+        let mixer = if mixer_format != Some(format) {
+            // The equalizer goes with the old mixer
+            if let Ok(mut state) = state.lock() {
+                state.equalizer_dsp = 0;
+            }
+            Self::replace_mixer(bass_mixer, format, if attached { stream } else { 0 })
+        } else {
+            bass_mixer.load(Ordering::SeqCst)
+        };
+        // A reused mixer still carries the equalizer and the user's volume
+        let untouched = mixer != 0 && Self::detach_processing(mixer, state);
+
+        if let Ok(mut state) = state.lock() {
+            state.mixer_format = if mixer != 0 { Some(format) } else { None };
+            state.is_bit_perfect = untouched && device_rate == info.freq;
+            if !state.is_bit_perfect {
+                crate::warn!(
+                    "Output is not bit-perfect: track {} Hz, device {} Hz",
+                    info.freq,
+                    device_rate
+                );
+            }
+        }
+    }
+
+    /// Reinitialise the output device at `sample_rate`, keeping all existing channels.
+    /// Returns the rate the device actually runs at.
+    #[cfg(desktop)]
+    fn set_output_rate(sample_rate: u32) -> u32 {
+        unsafe {
+            let mut info = std::mem::zeroed::<BASS_INFO>();
+            if BASS_GetInfo(&mut info) != 0 && info.freq == sample_rate {
+                return info.freq;
+            }
+
+            let device = BASS_GetDevice() as i32;
+            let flags = BASS_DEVICE_REINIT | BASS_DEVICE_FREQ;
+
+            // Linux can take the device exclusively so nothing else gets mixed in.
+            #[cfg(target_os = "linux")]
+            let reinitialized = BASS_Init(
+                device,
+                sample_rate,
+                flags | BASS_DEVICE_HOG,
+                ptr::null_mut(),
+                ptr::null_mut(),
+            ) != 0
+                || BASS_Init(device, sample_rate, flags, ptr::null_mut(), ptr::null_mut()) != 0;
+            #[cfg(not(target_os = "linux"))]
+            let reinitialized =
+                BASS_Init(device, sample_rate, flags, ptr::null_mut(), ptr::null_mut()) != 0;
+
+            if !reinitialized {
+                crate::error!(
+                    "Failed to reinitialize output at {} Hz, error: {}",
+                    sample_rate,
+                    BASS_ErrorGetCode()
+                );
+            }
+
+            if BASS_GetInfo(&mut info) != 0 {
+                info.freq
+            } else {
+                0
+            }
+        }
+    }
+
+    pub fn request_sync(&self) {
+        self.emit_sync(false);
+    }
+
+    pub fn emit_sync(&self, is_reset: bool) {
+        let sync_info = self.get_sync_info(is_reset);
+        Self::save_session_inner(&self.state, sync_info.current_position.unwrap_or(0.0));
+        app_handle()
+            .emit(crate::commands::route::MUSIC_PLAYER_SYNC, sync_info)
+            .unwrap();
+    }
+
+    /// Save the queue paths along with the session, after the playlist itself changed.
+    fn save_queue(&self) {
+        let paths: Vec<String> = match self.state.lock() {
+            Ok(state) => state
+                .playlist
+                .iter()
+                .map(|item| item.metadata.path.clone())
+                .collect(),
+            Err(_) => return,
+        };
+        session::save_queue(&paths);
+        self.save_session();
+    }
+
+    /// Save the current index, position, repeat mode and shuffle order.
+    pub fn save_session(&self) {
+        Self::save_session_inner(&self.state, self.get_current_duration());
+    }
+
+    fn save_session_inner(state: &Arc<Mutex<PlayerState>>, position: f64) {
+        let saved = match state.lock() {
+            Ok(state) => PlayerSession {
+                current_index: state.current_index,
+                position,
+                repeat_mode: state.repeat_mode,
+                shuffle_order: state.shuffle_order.clone(),
+            },
+            Err(_) => return,
+        };
+        session::save_session(&saved);
+    }
+
+    /// Restore the queue saved by the previous run, paused at the saved position. Tracks whose
+    /// files no longer exist are dropped.
+    pub fn restore_session(&self) {
+        let Some((paths, saved)) = session::load() else {
+            return;
+        };
+
+        // Old queue index to new index, `None` for dropped tracks
+        let mut remap: Vec<Option<usize>> = Vec::with_capacity(paths.len());
+        let mut playlist = Vec::new();
+        for path in paths {
+            if !Path::new(&path).exists() {
+                remap.push(None);
+                continue;
+            }
+            let metadata = crate::folder::database::get_music_from_db(&path).unwrap_or_else(|| {
+                let file = Path::new(&path);
+                MusicMetadata {
+                    filename: file.file_name().map(|s| s.to_string_lossy().to_string()),
+                    title: file.file_stem().map(|s| s.to_string_lossy().to_string()),
+                    path: path.clone(),
+                    ..Default::default()
+                }
+            });
+            remap.push(Some(playlist.len()));
+            playlist.push(PlaylistItem { metadata });
+        }
+
+        let dropped = remap.iter().filter(|i| i.is_none()).count();
+        if dropped > 0 {
+            crate::warn!("Dropped {} missing tracks from the saved queue", dropped);
+        }
+        if playlist.is_empty() {
+            return;
+        }
+
+        // A missing current track continues from the next one that still exists
+        let (current_index, position) = match saved.current_index {
+            Some(i) => match remap.get(i).copied().flatten() {
+                Some(index) => (Some(index), saved.position),
+                None => (
+                    remap.iter().skip(i).flatten().next().copied().or(Some(0)),
+                    0.0,
+                ),
+            },
+            None => (None, 0.0),
+        };
+
+        let shuffle_order = saved.shuffle_order.map(|order| {
+            let mut order: Vec<usize> = order
+                .iter()
+                .filter_map(|&i| remap.get(i).copied().flatten())
+                .collect();
+            let mut seen = vec![false; playlist.len()];
+            order.retain(|&i| !std::mem::replace(&mut seen[i], true));
+            // Anything the saved order doesn't cover is played last
+            order.extend((0..playlist.len()).filter(|&i| !seen[i]));
+            order
+        });
+
+        let total_count = playlist.len();
+        let music = current_index.map(|i| playlist[i].metadata.clone());
+        match self.state.lock() {
+            Ok(mut state) => {
+                state.playlist = playlist;
+                state.current_index = current_index;
+                state.repeat_mode = saved.repeat_mode;
+                state.shuffle_order = shuffle_order;
+            }
+            Err(e) => {
+                crate::error!("Failed to lock player state: {}", e);
+                return;
+            }
+        }
+        crate::info!("Restored play queue with {} tracks", total_count);
+
+        if dropped > 0 {
+            self.save_queue();
+        }
+
+        let (Some(index), Some(music)) = (current_index, music) else {
+            return;
+        };
+        if !Self::load_music_inner(
+            &self.bass_mixer,
+            &self.current_stream,
+            &self.state,
+            &self.temp_wav_path,
+            music,
+            index,
+            total_count,
+        ) {
+            return;
+        }
+
+        // The mixer hasn't been started yet, so the track stays paused at this position
+        let stream = self.current_stream.load(Ordering::SeqCst);
+        if position > 0.0 && stream != 0 {
+            #[cfg(desktop)]
+            unsafe {
+                let byte_pos = BASS_ChannelSeconds2Bytes(stream, position / 1000.0);
+                BASS_ChannelSetPosition(stream, byte_pos, BASS_POS_BYTE);
+            }
+
+            #[cfg(target_os = "android")]
+            if let Some(bass) = bass_android::get_bass() {
+                unsafe {
+                    let byte_pos = (bass.bass_channel_seconds2bytes)(stream, position / 1000.0);
+                    (bass.bass_channel_set_position)(stream, byte_pos, BASS_POS_BYTE);
+                }
+            }
+        }
+    }
+
+    pub fn get_queue(&self) -> Vec<MusicMetadata> {
+        self.state
+            .lock()
+            .map(|state| {
+                state
+                    .playlist
+                    .iter()
+                    .map(|item| item.metadata.clone())
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    fn play_pause(&self, play: bool) {
+        Self::play_pause_inner(&self.bass_mixer, &self.current_stream, play);
+    }
+
+    fn play_pause_inner(bass_mixer: &Arc<AtomicU32>, _current_stream: &Arc<AtomicU32>, play: bool) {
+        let bm = bass_mixer.load(Ordering::SeqCst);
+
+        #[cfg(desktop)]
+        unsafe {
+            if bm == 0 {
+                return;
+            }
+            if play {
+                if BASS_ChannelPlay(bm, 0) == 0 {
+                    crate::error!("Failed to play, error: {}", BASS_ErrorGetCode());
+                }
+            } else if BASS_ChannelPause(bm) == 0 {
+                crate::error!("Failed to pause, error: {}", BASS_ErrorGetCode());
+            }
+        }
+
+        #[cfg(target_os = "android")]
+        if let Some(bass) = bass_android::get_bass() {
+            unsafe {
+                if bm == 0 {
+                    return;
+                }
+                if play {
+                    if (bass.bass_channel_play)(bm, 0) == 0 {
+                        crate::error!("Failed to play, error: {}", (bass.bass_error_get_code)());
                     } else {
                         let cs = current_stream.load(Ordering::SeqCst);
                         let pos = {
@@ -1030,10 +2256,15 @@ impl MusicPlayer {
         }
 
         self.stop_current_stream();
+        Self::free_fading_stream(&self.state);
         if let Ok(mut state) = self.state.lock() {
             state.playlist.clear();
             state.current_index = None;
+            if let Some(order) = state.shuffle_order.as_mut() {
+                order.clear();
+            }
         }
+        self.save_queue();
     }
 
     fn stop_current_stream(&self) {
@@ -1138,15 +2369,19 @@ impl MusicPlayer {
             }
         };
 
-        let (index, repeat_mode) = state
+        let (index, repeat_mode, is_shuffle, is_bit_perfect) = state
             .lock()
             .map(|s| {
                 (
                     s.current_index.map(|i| i as i64).unwrap_or(-1),
                     s.repeat_mode,
+                    s.shuffle_order.is_some(),
+                    s.is_bit_perfect,
                 )
             })
-            .unwrap_or((-1, RepeatMode::None));
+            .unwrap_or((-1, RepeatMode::None, false, false));
+
+        Self::save_session_inner(state, current_position.unwrap_or(0.0));
 
         app_handle()
             .emit(
@@ -1156,6 +2391,8 @@ impl MusicPlayer {
                     current_position,
                     is_playing,
                     repeat_mode,
+                    is_shuffle,
+                    is_bit_perfect,
                 },
             )
             .unwrap();
@@ -1180,6 +2417,14 @@ impl MusicPlayer {
             temp_wav_path: Arc::clone(temp_wav_path),
         }));
 
+        // Whether to crossfade is decided when the sync fires, once the next track is known.
+        let crossfade_duration = state
+            .lock()
+            .ok()
+            .filter(|s| !s.bit_perfect)
+            .map(|s| s.crossfade.duration)
+            .unwrap_or(0);
+
         #[cfg(desktop)]
         unsafe {
             BASS_ChannelSetSync(
@@ -1189,6 +2434,32 @@ impl MusicPlayer {
                 Some(end_sync_callback),
                 sync_data as *mut _,
             );
+            if crossfade_duration > 0 {
+                let length = BASS_ChannelGetLength(stream, BASS_POS_BYTE);
+                let fade = BASS_ChannelSeconds2Bytes(stream, crossfade_duration as f64 / 1000.0);
+                // Short tracks would start fading before they are properly under way
+                if length != u64::MAX && fade != u64::MAX && length / 2 > fade {
+                    BASS_ChannelSetSync(
+                        stream,
+                        BASS_SYNC_POS | BASS_SYNC_MIXTIME | 0x80000000,
+                        length - fade,
+                        Some(crossfade_sync_callback),
+                        sync_data as *mut _,
+                    );
+                }
+            }
+            let length = BASS_ChannelGetLength(stream, BASS_POS_BYTE);
+            if length != u64::MAX {
+                let seconds = BASS_ChannelBytes2Seconds(stream, length);
+                let threshold = PlayHistory::threshold_seconds(seconds);
+                BASS_ChannelSetSync(
+                    stream,
+                    BASS_SYNC_POS | BASS_SYNC_MIXTIME | 0x80000000,
+                    BASS_ChannelSeconds2Bytes(stream, threshold),
+                    Some(played_sync_callback),
+                    sync_data as *mut _,
+                );
+            }
             BASS_ChannelSetSync(
                 stream,
                 BASS_SYNC_FREE | 0x80000000,
@@ -1208,6 +2479,35 @@ impl MusicPlayer {
                     Some(end_sync_callback),
                     sync_data as *mut _,
                 );
+                if crossfade_duration > 0 {
+                    let length = (bass.bass_channel_get_length)(stream, BASS_POS_BYTE);
+                    let fade = (bass.bass_channel_seconds2bytes)(
+                        stream,
+                        crossfade_duration as f64 / 1000.0,
+                    );
+                    // Short tracks would start fading before they are properly under way
+                    if length != u64::MAX && fade != u64::MAX && length / 2 > fade {
+                        (bass.bass_channel_set_sync)(
+                            stream,
+                            BASS_SYNC_POS | BASS_SYNC_MIXTIME | 0x80000000,
+                            length - fade,
+                            Some(crossfade_sync_callback),
+                            sync_data as *mut _,
+                        );
+                    }
+                }
+                let length = (bass.bass_channel_get_length)(stream, BASS_POS_BYTE);
+                if length != u64::MAX {
+                    let seconds = (bass.bass_channel_bytes2seconds)(stream, length);
+                    let threshold = PlayHistory::threshold_seconds(seconds);
+                    (bass.bass_channel_set_sync)(
+                        stream,
+                        BASS_SYNC_POS | BASS_SYNC_MIXTIME | 0x80000000,
+                        (bass.bass_channel_seconds2bytes)(stream, threshold),
+                        Some(played_sync_callback),
+                        sync_data as *mut _,
+                    );
+                }
                 (bass.bass_channel_set_sync)(
                     stream,
                     BASS_SYNC_FREE | 0x80000000,
@@ -1228,13 +2528,21 @@ impl MusicPlayer {
         _index: usize,
         _total_count: usize,
     ) -> bool {
-        let bm = bass_mixer.load(Ordering::SeqCst);
+        let crossfading = state.lock().map(|s| s.fade_in.is_some()).unwrap_or(false);
+        if !crossfading {
+            Self::free_fading_stream(state);
+        }
 
         #[cfg(desktop)]
         unsafe {
             let path = CString::new(music.path.clone()).unwrap();
-            let stream =
-                BASS_StreamCreateFile(false, path.as_ptr() as *const _, 0, 0, BASS_STREAM_DECODE);
+            let stream = BASS_StreamCreateFile(
+                false,
+                path.as_ptr() as *const _,
+                0,
+                0,
+                BASS_STREAM_DECODE | BASS_SAMPLE_FLOAT,
+            );
 
             if stream == 0 {
                 let bass_error = BASS_ErrorGetCode();
@@ -1251,10 +2559,23 @@ impl MusicPlayer {
                         wav_cstring.as_ptr() as *const _,
                         0,
                         0,
-                        BASS_STREAM_DECODE,
+                        BASS_STREAM_DECODE | BASS_SAMPLE_FLOAT,
                     );
 
                     if wav_stream != 0 {
+                        Self::prepare_bit_perfect_mixer(
+                            bass_mixer,
+                            state,
+                            wav_stream,
+                            Some(16),
+                            false,
+                        );
+                        // A transcoded file is never bit-perfect, whatever the mixer says.
+                        if let Ok(mut state) = state.lock() {
+                            state.is_bit_perfect = false;
+                        }
+                        Self::apply_replay_gain(wav_stream, state, &music);
+                        let bm = bass_mixer.load(Ordering::SeqCst);
                         let ok = BASS_Mixer_StreamAddChannel(bm, wav_stream, BASS_MIXER_NORAMPIN);
                         if ok != 0 {
                             current_stream.store(wav_stream, Ordering::SeqCst);
@@ -1295,6 +2616,15 @@ impl MusicPlayer {
                 return false;
             }
 
+            Self::prepare_bit_perfect_mixer(
+                bass_mixer,
+                state,
+                stream,
+                music.bits_per_sample,
+                false,
+            );
+            Self::apply_replay_gain(stream, state, &music);
+            let bm = bass_mixer.load(Ordering::SeqCst);
             let ok = BASS_Mixer_StreamAddChannel(bm, stream, BASS_MIXER_NORAMPIN);
             if ok == 0 {
                 crate::error!(
@@ -1336,6 +2666,7 @@ impl MusicPlayer {
 
         #[cfg(target_os = "android")]
         {
+            let bm = bass_mixer.load(Ordering::SeqCst);
             if let Some(bass) = bass_android::get_bass() {
                 unsafe {
                     let path = CString::new(music.path.clone()).unwrap();
@@ -1366,6 +2697,7 @@ impl MusicPlayer {
                             );
 
                             if wav_stream != 0 {
+                                Self::apply_replay_gain(wav_stream, state, &music);
                                 let ok = (bass.bass_mixer_stream_add_channel)(
                                     bm,
                                     wav_stream,
@@ -1403,6 +2735,7 @@ impl MusicPlayer {
                         return false;
                     }
 
+                    Self::apply_replay_gain(stream, state, &music);
                     let ok = (bass.bass_mixer_stream_add_channel)(bm, stream, BASS_MIXER_NORAMPIN);
                     if ok == 0 {
                         crate::error!(
@@ -0,0 +1,114 @@
+use serde::{Deserialize, Serialize};
+
+use crate::music::metadata::MusicMetadata;
+use crate::state::app_store;
+
+const STORE_KEY_REPLAY_GAIN: &str = "replay-gain";
+
+/// ReplayGain 2.0 reference level, used to turn a measured loudness into a gain.
+pub const REPLAY_GAIN_REFERENCE_LUFS: f64 = -18.0;
+const R128_REFERENCE_LUFS: f64 = -23.0;
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ReplayGainMode {
+    Off,
+    Track,
+    Album,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReplayGainSettings {
+    pub mode: ReplayGainMode,
+    /// Extra gain in dB applied on top of the stored gain.
+    pub preamp: f32,
+    /// Gain in dB for tracks with neither tags nor a measured loudness.
+    pub fallback_gain: f32,
+    /// Limit the gain so the track peak never goes over full scale.
+    pub prevent_clipping: bool,
+}
+
+impl Default for ReplayGainSettings {
+    fn default() -> Self {
+        Self {
+            mode: ReplayGainMode::Off,
+            preamp: 0.0,
+            fallback_gain: 0.0,
+            prevent_clipping: true,
+        }
+    }
+}
+
+impl ReplayGainSettings {
+    pub fn load() -> Self {
+        app_store()
+            .get(STORE_KEY_REPLAY_GAIN)
+            .and_then(|v| serde_json::from_value(v).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self) {
+        let store = app_store();
+        store.set(STORE_KEY_REPLAY_GAIN, serde_json::json!(self));
+        let _ = store.save();
+    }
+
+    /// Linear volume to apply to a track's channel in the mixer.
+    pub fn volume_for(&self, music: &MusicMetadata) -> f32 {
+        if self.mode == ReplayGainMode::Off {
+            return 1.0;
+        }
+
+        let track_gain = music
+            .replay_gain_track_gain
+            .or_else(|| music.loudness.map(|lufs| REPLAY_GAIN_REFERENCE_LUFS - lufs));
+
+        let (gain, peak) = match self.mode {
+            ReplayGainMode::Album if music.replay_gain_album_gain.is_some() => (
+                music.replay_gain_album_gain,
+                music
+                    .replay_gain_album_peak
+                    .or(music.replay_gain_track_peak),
+            ),
+            _ => (track_gain, music.replay_gain_track_peak),
+        };
+
+        let gain_db = gain.unwrap_or(self.fallback_gain as f64) + self.preamp as f64;
+        let mut volume = 10f64.powf(gain_db / 20.0);
+
+        if self.prevent_clipping {
+            if let Some(peak) = peak.filter(|p| *p > 0.0) {
+                volume = volume.min(1.0 / peak);
+            }
+        }
+
+        volume as f32
+    }
+}
+
+/// Parse a ReplayGain gain value such as `"-6.54 dB"`.
+pub fn parse_gain(value: &str) -> Option<f64> {
+    value
+        .trim()
+        .trim_end_matches(|c: char| c.is_alphabetic() || c.is_whitespace())
+        .trim()
+        .parse::<f64>()
+        .ok()
+        .filter(|v| v.is_finite())
+}
+
+/// Parse an Opus `R128_*_GAIN` value and convert it to the ReplayGain reference level.
+pub fn parse_r128_gain(value: &str) -> Option<f64> {
+    let q78 = value.trim().parse::<i32>().ok()?;
+    Some(q78 as f64 / 256.0 + (REPLAY_GAIN_REFERENCE_LUFS - R128_REFERENCE_LUFS))
+}
+
+/// Parse a ReplayGain peak value such as `"0.988831"`.
+pub fn parse_peak(value: &str) -> Option<f64> {
+    value
+        .trim()
+        .parse::<f64>()
+        .ok()
+        .filter(|v| v.is_finite() && *v >= 0.0)
+}
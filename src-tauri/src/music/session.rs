@@ -0,0 +1,214 @@
+use std::sync::{Mutex, Once};
+use std::time::Duration;
+
+use rusqlite::{params, OptionalExtension};
+
+use crate::database::database::GLOBAL_DATABASE;
+use crate::music::player::RepeatMode;
+
+/// Minimum time between two saves that only move the position.
+const POSITION_SAVE_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Last session written to the database.
+static SAVED: Mutex<Option<PlayerSession>> = Mutex::new(None);
+/// Session whose position changed since, left to the position writer.
+static PENDING: Mutex<Option<PlayerSession>> = Mutex::new(None);
+static POSITION_WRITER: Once = Once::new();
+/// Queue paths as stored in `player_queue`.
+static SAVED_QUEUE: Mutex<Option<Vec<String>>> = Mutex::new(None);
+
+/// Playback state that survives a restart, next to the queue paths in `player_queue`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PlayerSession {
+    pub current_index: Option<usize>,
+    /// Position in the current track, in milliseconds.
+    pub position: f64,
+    pub repeat_mode: RepeatMode,
+    pub shuffle_order: Option<Vec<usize>>,
+}
+
+/// Save the queue paths, writing only the positions that changed since the last save.
+pub fn save_queue(paths: &[String]) {
+    let Ok(mut saved) = SAVED_QUEUE.lock() else {
+        return;
+    };
+    if saved.as_deref() == Some(paths) {
+        return;
+    }
+
+    let mut conn_guard = match GLOBAL_DATABASE.lock() {
+        Ok(guard) => guard,
+        Err(_) => return,
+    };
+    let Some(conn) = conn_guard.as_mut() else {
+        return;
+    };
+
+    let result = (|| -> rusqlite::Result<()> {
+        let tx = conn.transaction()?;
+        // Without a known saved queue every row is rewritten
+        let previous = saved.as_deref().unwrap_or_default();
+        let kept = if saved.is_some() { paths.len() } else { 0 };
+        tx.execute(
+            "DELETE FROM player_queue WHERE position >= ?1",
+            params![kept as i64],
+        )?;
+        {
+            let mut stmt =
+                tx.prepare("INSERT OR REPLACE INTO player_queue (position, path) VALUES (?1, ?2)")?;
+            for (i, path) in paths.iter().enumerate() {
+                if previous.get(i) != Some(path) {
+                    stmt.execute(params![i as i64, path])?;
+                }
+            }
+        }
+        tx.commit()
+    })();
+
+    match result {
+        Ok(()) => *saved = Some(paths.to_vec()),
+        Err(e) => crate::error!("Failed to save play queue: {}", e),
+    }
+}
+
+/// Save the session. Changes of the index, repeat mode or shuffle order are written right
+/// away, while position updates are written in the background at most every few seconds, so
+/// playback doesn't wait on the database during scans.
+pub fn save_session(session: &PlayerSession) {
+    let Ok(mut saved) = SAVED.lock() else {
+        return;
+    };
+    let structural = saved.as_ref().is_none_or(|saved| {
+        PlayerSession {
+            position: session.position,
+            ..saved.clone()
+        } != *session
+    });
+
+    if let Ok(mut pending) = PENDING.lock() {
+        *pending = (!structural).then(|| session.clone());
+    }
+    if structural {
+        write(&mut saved, session);
+        return;
+    }
+    drop(saved);
+
+    POSITION_WRITER.call_once(|| {
+        std::thread::spawn(|| loop {
+            std::thread::sleep(POSITION_SAVE_INTERVAL);
+            flush();
+        });
+    });
+}
+
+/// Write the position update left for the background writer, such as on exit.
+pub fn flush() {
+    // Taken under the saved lock, so it can't overwrite a newer session written meanwhile
+    let Ok(mut saved) = SAVED.lock() else {
+        return;
+    };
+    let pending = PENDING.lock().ok().and_then(|mut pending| pending.take());
+    if let Some(session) = pending {
+        write(&mut saved, &session);
+    }
+}
+
+fn write(saved: &mut Option<PlayerSession>, session: &PlayerSession) {
+    if saved.as_ref() == Some(session) {
+        return;
+    }
+
+    let conn_guard = match GLOBAL_DATABASE.lock() {
+        Ok(guard) => guard,
+        Err(_) => return,
+    };
+    let Some(conn) = conn_guard.as_ref() else {
+        return;
+    };
+
+    let repeat_mode = serde_json::to_value(session.repeat_mode)
+        .ok()
+        .and_then(|v| v.as_str().map(str::to_string));
+    let shuffle_order = session
+        .shuffle_order
+        .as_ref()
+        .and_then(|order| serde_json::to_string(order).ok());
+
+    let res = conn.execute(
+        "INSERT INTO player_session (id, current_index, position, repeat_mode, shuffle_order)
+        VALUES (1, ?1, ?2, ?3, ?4)
+        ON CONFLICT(id) DO UPDATE SET
+            current_index = excluded.current_index,
+            position = excluded.position,
+            repeat_mode = excluded.repeat_mode,
+            shuffle_order = excluded.shuffle_order",
+        params![
+            session.current_index.map(|i| i as i64),
+            session.position,
+            repeat_mode,
+            shuffle_order
+        ],
+    );
+
+    match res {
+        Ok(_) => *saved = Some(session.clone()),
+        Err(e) => crate::error!("Failed to save player session: {}", e),
+    }
+}
+
+/// Saved queue paths and session, `None` when nothing has been saved yet.
+pub fn load() -> Option<(Vec<String>, PlayerSession)> {
+    let conn_guard = GLOBAL_DATABASE.lock().ok()?;
+    let conn = conn_guard.as_ref()?;
+
+    let row = conn
+        .query_row(
+            "SELECT current_index, position, repeat_mode, shuffle_order
+            FROM player_session WHERE id = 1",
+            [],
+            |row| {
+                Ok((
+                    row.get::<_, Option<i64>>(0)?,
+                    row.get::<_, f64>(1)?,
+                    row.get::<_, Option<String>>(2)?,
+                    row.get::<_, Option<String>>(3)?,
+                ))
+            },
+        )
+        .optional();
+
+    let (current_index, position, repeat_mode, shuffle_order) = match row {
+        Ok(Some(row)) => row,
+        Ok(None) => return None,
+        Err(e) => {
+            crate::error!("Failed to load player session: {}", e);
+            return None;
+        }
+    };
+
+    let session = PlayerSession {
+        current_index: current_index.map(|i| i as usize),
+        position,
+        repeat_mode: repeat_mode
+            .and_then(|mode| serde_json::from_value(serde_json::Value::String(mode)).ok())
+            .unwrap_or(RepeatMode::None),
+        shuffle_order: shuffle_order.and_then(|order| serde_json::from_str(&order).ok()),
+    };
+
+    let mut stmt = conn
+        .prepare("SELECT path FROM player_queue ORDER BY position")
+        .ok()?;
+    let paths = stmt
+        .query_map([], |row| row.get::<_, String>(0))
+        .ok()?
+        .filter_map(|r| r.ok())
+        .collect::<Vec<_>>();
+    drop(stmt);
+    drop(conn_guard);
+
+    if let Ok(mut saved) = SAVED_QUEUE.lock() {
+        *saved = Some(paths.clone());
+    }
+    Some((paths, session))
+}
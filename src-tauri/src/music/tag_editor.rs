@@ -0,0 +1,227 @@
+use std::path::PathBuf;
+
+use lofty::config::WriteOptions;
+use lofty::picture::{Picture, PictureType};
+use lofty::prelude::{Accessor, AudioFile, ItemKey, TaggedFileExt};
+use lofty::tag::Tag;
+use serde::{Deserialize, Serialize};
+use tauri::Emitter;
+
+use crate::folder::{database, scanner};
+use crate::music::image_cache::ImageCache;
+use crate::music::metadata::MusicMetadata;
+use crate::playlist::playlist::Playlist;
+use crate::state::app_handle;
+
+/// Tag changes applied to every selected track. Fields left out are kept as they are, while
+/// an empty string or a number of 0 removes the tag.
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(rename_all = "camelCase", default)]
+pub struct TagEdit {
+    pub title: Option<String>,
+    pub artist: Option<String>,
+    pub album: Option<String>,
+    pub album_artist: Option<String>,
+    pub genre: Option<String>,
+    pub date: Option<String>,
+    pub track_number: Option<u32>,
+    pub track_total: Option<u32>,
+    pub disc_number: Option<u32>,
+    pub disc_total: Option<u32>,
+    /// Path of an image to embed as the front cover.
+    pub image: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct TagWriteFailure {
+    pub path: String,
+    pub error: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct TagWriteResult {
+    /// The written tracks as stored in the library afterwards.
+    pub musics: Vec<MusicMetadata>,
+    pub failed: Vec<TagWriteFailure>,
+}
+
+impl TagEdit {
+    /// Write the changes to each file, then read them back into the library so the next scan
+    /// sees an up to date `modified_at`.
+    pub async fn apply(self, paths: Vec<String>) -> Result<TagWriteResult, String> {
+        let previous: Vec<Option<MusicMetadata>> = paths
+            .iter()
+            .map(|path| database::get_music_from_db(path))
+            .collect();
+
+        let edit = self.clone();
+        let results = tokio::task::spawn_blocking(move || {
+            let picture = edit.read_picture()?;
+            Ok::<_, String>(
+                paths
+                    .into_iter()
+                    .map(|path| {
+                        let result = edit.write(&path, picture.as_ref());
+                        (path, result)
+                    })
+                    .collect::<Vec<_>>(),
+            )
+        })
+        .await
+        .map_err(|e| e.to_string())??;
+
+        let mut written = Vec::new();
+        let mut failed = Vec::new();
+        for (path, result) in results {
+            match result {
+                Ok(()) => written.push(PathBuf::from(path)),
+                Err(error) => {
+                    crate::warn!("Failed to write tags to {}: {}", path, error);
+                    failed.push(TagWriteFailure { path, error });
+                }
+            }
+        }
+        if written.is_empty() {
+            return Ok(TagWriteResult {
+                musics: Vec::new(),
+                failed,
+            });
+        }
+
+        scanner::process_supported_files(&written, false).await;
+        if let Err(e) = Playlist::refresh_smart() {
+            crate::error!("Failed to refresh smart playlists: {}", e);
+        }
+
+        let musics: Vec<MusicMetadata> = written
+            .iter()
+            .filter_map(|path| database::get_music_from_db(&path.display().to_string()))
+            .collect();
+
+        // Covers are cached by artist and album, which may just have changed as well
+        if self.image.is_some() {
+            for music in previous.iter().flatten().chain(musics.iter()) {
+                ImageCache::remove_cache(&ImageCache::get_cache_key(
+                    music.artist.as_deref(),
+                    music.album.as_deref(),
+                    &music.path,
+                ));
+            }
+        }
+
+        app_handle()
+            .emit(crate::commands::route::MUSIC_LIBRARY_CHANGED, ())
+            .unwrap_or_else(|_| {
+                crate::error!(
+                    "Failed to emit {}",
+                    crate::commands::route::MUSIC_LIBRARY_CHANGED
+                )
+            });
+
+        Ok(TagWriteResult { musics, failed })
+    }
+
+    /// The cover to embed, read once for the whole selection. `None` when the cover is kept
+    /// or removed.
+    fn read_picture(&self) -> Result<Option<Picture>, String> {
+        let Some(path) = self.image.as_deref().filter(|p| !p.is_empty()) else {
+            return Ok(None);
+        };
+
+        let mut file =
+            std::fs::File::open(path).map_err(|e| format!("Failed to open image: {}", e))?;
+        let mut picture =
+            Picture::from_reader(&mut file).map_err(|e| format!("Unsupported image: {}", e))?;
+        picture.set_pic_type(PictureType::CoverFront);
+        Ok(Some(picture))
+    }
+
+    fn write(&self, path: &str, picture: Option<&Picture>) -> Result<(), String> {
+        let mut tagged_file = lofty::read_from_path(path).map_err(|e| e.to_string())?;
+
+        // Files without tags get one of the format's preferred type, e.g. ID3v2 for MP3
+        if tagged_file.primary_tag().is_none() {
+            let tag_type = tagged_file.primary_tag_type();
+            tagged_file.insert_tag(Tag::new(tag_type));
+        }
+        let tag = tagged_file
+            .primary_tag_mut()
+            .ok_or_else(|| "File doesn't support tags".to_string())?;
+
+        if let Some(title) = &self.title {
+            match title.trim() {
+                "" => tag.remove_title(),
+                v => tag.set_title(v.to_string()),
+            }
+        }
+        if let Some(artist) = &self.artist {
+            match artist.trim() {
+                "" => tag.remove_artist(),
+                v => tag.set_artist(v.to_string()),
+            }
+        }
+        if let Some(album) = &self.album {
+            match album.trim() {
+                "" => tag.remove_album(),
+                v => tag.set_album(v.to_string()),
+            }
+        }
+        if let Some(genre) = &self.genre {
+            match genre.trim() {
+                "" => tag.remove_genre(),
+                v => tag.set_genre(v.to_string()),
+            }
+        }
+        for (key, value) in [
+            (ItemKey::AlbumArtist, &self.album_artist),
+            (ItemKey::RecordingDate, &self.date),
+        ] {
+            if let Some(value) = value {
+                match value.trim() {
+                    "" => tag.remove_key(&key),
+                    v => {
+                        tag.insert_text(key, v.to_string());
+                    }
+                }
+            }
+        }
+
+        if let Some(track) = self.track_number {
+            match track {
+                0 => tag.remove_track(),
+                n => tag.set_track(n),
+            }
+        }
+        if let Some(total) = self.track_total {
+            match total {
+                0 => tag.remove_track_total(),
+                n => tag.set_track_total(n),
+            }
+        }
+        if let Some(disc) = self.disc_number {
+            match disc {
+                0 => tag.remove_disk(),
+                n => tag.set_disk(n),
+            }
+        }
+        if let Some(total) = self.disc_total {
+            match total {
+                0 => tag.remove_disk_total(),
+                n => tag.set_disk_total(n),
+            }
+        }
+
+        if self.image.is_some() {
+            tag.remove_picture_type(PictureType::CoverFront);
+            if let Some(picture) = picture {
+                tag.push_picture(picture.clone());
+            }
+        }
+
+        tagged_file
+            .save_to_path(path, WriteOptions::default())
+            .map_err(|e| e.to_string())
+    }
+}
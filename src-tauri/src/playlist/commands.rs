@@ -1,4 +1,7 @@
-use crate::playlist::playlist::Playlist;
+use crate::playlist::file::{self, PlaylistImport};
+use crate::playlist::playlist::{Playlist, PlaylistField};
+use crate::playlist::smart::SmartPlaylistRules;
+use std::path::PathBuf;
 
 #[tauri::command]
 pub fn playlist_all_get() -> Vec<Playlist> {
@@ -10,11 +13,48 @@ pub fn playlist_create(playlist: Playlist) -> Result<i64, String> {
     Playlist::create(playlist).map_err(|e| e.to_string())
 }
 
+/// Paths the rules would currently match, for previewing a smart playlist before saving it.
+#[tauri::command]
+pub fn playlist_smart_preview(rules: SmartPlaylistRules) -> Result<Vec<String>, String> {
+    Playlist::preview_smart(&rules).map_err(|e| e.to_string())
+}
+
 #[tauri::command]
 pub fn playlist_delete(id: i64) -> Result<(), String> {
     Playlist::delete(id).map_err(|e| e.to_string())
 }
 
+#[tauri::command]
+pub fn playlist_update(
+    id: i64,
+    name: Option<String>,
+    image: Option<String>,
+    title: Option<String>,
+    artist: Option<String>,
+    clear: Option<Vec<PlaylistField>>,
+) -> Result<(), String> {
+    Playlist::update(id, name, image, title, artist, &clear.unwrap_or_default())
+}
+
+#[tauri::command]
+pub fn playlist_musics_insert(
+    id: i64,
+    paths: Vec<String>,
+    position: Option<usize>,
+) -> Result<(), String> {
+    Playlist::insert_musics(id, paths, position)
+}
+
+#[tauri::command]
+pub fn playlist_musics_remove(id: i64, positions: Vec<usize>) -> Result<(), String> {
+    Playlist::remove_musics(id, positions)
+}
+
+#[tauri::command]
+pub fn playlist_musics_move(id: i64, from: usize, to: usize) -> Result<(), String> {
+    Playlist::move_music(id, from, to)
+}
+
 #[tauri::command]
 pub async fn playlist_image_upload() -> Result<String, String> {
     Playlist::upload_image().await
@@ -30,3 +70,42 @@ pub async fn playlist_image_read(id: u8) -> tauri::ipc::Response {
         }
     }
 }
+
+/// Import an M3U, M3U8, PLS or XSPF file, asking for one when `path` isn't given.
+#[tauri::command]
+pub async fn playlist_import(path: Option<String>) -> Result<PlaylistImport, String> {
+    let path = match path {
+        Some(path) => PathBuf::from(path),
+        None => file::pick_import_path().await?,
+    };
+    tokio::task::spawn_blocking(move || file::import(&path))
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+/// Export a playlist, asking where to save it when `path` isn't given. The format follows
+/// the file extension. Returns the written path.
+#[tauri::command]
+pub async fn playlist_export(
+    id: i64,
+    path: Option<String>,
+    relative: bool,
+) -> Result<String, String> {
+    let path = match path {
+        Some(path) => PathBuf::from(path),
+        None => {
+            let name = Playlist::get_all()
+                .into_iter()
+                .find(|p| p.id == Some(id))
+                .map(|p| p.name)
+                .ok_or_else(|| format!("Playlist {} not found", id))?;
+            file::pick_export_path(name).await?
+        }
+    };
+
+    let written = path.display().to_string();
+    tokio::task::spawn_blocking(move || file::export(id, &path, relative))
+        .await
+        .map_err(|e| e.to_string())??;
+    Ok(written)
+}
@@ -0,0 +1,575 @@
+use std::collections::{HashMap, HashSet};
+use std::path::{Component, Path, PathBuf};
+
+use rusqlite::params;
+use serde::{Deserialize, Serialize};
+use tauri_plugin_dialog::DialogExt;
+
+use crate::database::database::GLOBAL_DATABASE;
+use crate::folder::database::get_music_from_db;
+use crate::music::metadata::MusicMetadata;
+use crate::playlist::playlist::Playlist;
+use crate::playlist::{m3u, pls, xspf};
+use crate::state::app_handle;
+use crate::utils::similarity::{calculate_similarity, normalize_string};
+
+/// Tracks whose durations differ by more seconds than this are never matched by their tags.
+const MATCH_DURATION_TOLERANCE: f64 = 3.0;
+/// Minimum tag similarity for a track to be matched, from 0.0 to 1.0.
+const MATCH_THRESHOLD: f64 = 0.8;
+/// Most tracks scored for an entry without a duration, picked by full-text search.
+const MATCH_SEARCH_LIMIT: u32 = 100;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PlaylistFormat {
+    M3u,
+    Pls,
+    Xspf,
+}
+
+impl PlaylistFormat {
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let extension = path.extension()?.to_str()?.to_lowercase();
+        match extension.as_str() {
+            "m3u" | "m3u8" => Some(PlaylistFormat::M3u),
+            "pls" => Some(PlaylistFormat::Pls),
+            "xspf" => Some(PlaylistFormat::Xspf),
+            _ => None,
+        }
+    }
+}
+
+/// A track as written in a playlist file, before it's matched against the library.
+#[derive(Debug, Clone)]
+pub struct PlaylistFileEntry {
+    /// Path or URI, empty when the entry is only identified by its tags.
+    pub path: String,
+    pub title: Option<String>,
+    pub artist: Option<String>,
+    pub album: Option<String>,
+    /// Duration in seconds.
+    pub duration: Option<f64>,
+}
+
+impl PlaylistFileEntry {
+    /// Title shown by players that only keep a single line per entry.
+    pub fn display_title(&self) -> Option<String> {
+        match (&self.artist, &self.title) {
+            (Some(artist), Some(title)) => Some(format!("{} - {}", artist, title)),
+            (None, Some(title)) => Some(title.clone()),
+            _ => None,
+        }
+    }
+
+    /// How the entry is reported when it can't be matched.
+    fn describe(&self) -> String {
+        if !self.path.is_empty() {
+            return self.path.clone();
+        }
+        self.display_title().unwrap_or_default()
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct ParsedPlaylist {
+    pub name: Option<String>,
+    pub entries: Vec<PlaylistFileEntry>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct PlaylistImport {
+    pub id: i64,
+    pub imported: usize,
+    /// Paths, or "Artist - Title" for entries without one, that couldn't be found on disk
+    /// or in the library.
+    pub missing: Vec<String>,
+}
+
+pub fn import(path: &Path) -> Result<PlaylistImport, String> {
+    let format = PlaylistFormat::from_path(path)
+        .ok_or_else(|| format!("Unsupported playlist file: {}", path.display()))?;
+    let bytes = std::fs::read(path).map_err(|e| e.to_string())?;
+    let text = decode_text(&bytes);
+
+    let parsed = match format {
+        PlaylistFormat::M3u => m3u::parse(&text),
+        PlaylistFormat::Pls => pls::parse(&text),
+        PlaylistFormat::Xspf => xspf::parse(&text)?,
+    };
+
+    let base_dir = path.parent().unwrap_or(Path::new(""));
+    let library = LibraryIndex::load().map_err(|e| e.to_string())?;
+
+    let mut paths = Vec::new();
+    let mut missing = Vec::new();
+    for entry in &parsed.entries {
+        match library.resolve(entry, base_dir) {
+            Some(path) => paths.push(path),
+            None => missing.push(entry.describe()),
+        }
+    }
+
+    let name = parsed.name.unwrap_or_else(|| {
+        path.file_stem()
+            .map(|s| s.to_string_lossy().to_string())
+            .unwrap_or_default()
+    });
+    let imported = paths.len();
+    let id = Playlist::create(Playlist {
+        id: None,
+        name,
+        image: None,
+        title: None,
+        artist: None,
+        paths,
+        rules: None,
+    })
+    .map_err(|e| e.to_string())?;
+
+    crate::info!(
+        "Imported playlist {} with {} tracks, {} missing",
+        path.display(),
+        imported,
+        missing.len()
+    );
+    Ok(PlaylistImport {
+        id,
+        imported,
+        missing,
+    })
+}
+
+/// Write a stored playlist to `path`, with track paths relative to the playlist file
+/// when `relative` is set and the track is on the same drive.
+pub fn export(id: i64, path: &Path, relative: bool) -> Result<(), String> {
+    let format = PlaylistFormat::from_path(path)
+        .ok_or_else(|| format!("Unsupported playlist file: {}", path.display()))?;
+    let playlist = Playlist::get_all()
+        .into_iter()
+        .find(|p| p.id == Some(id))
+        .ok_or_else(|| format!("Playlist {} not found", id))?;
+
+    let base_dir = path.parent().unwrap_or(Path::new(""));
+    let entries: Vec<PlaylistFileEntry> = playlist
+        .paths
+        .iter()
+        .map(|music_path| {
+            let music = get_music_from_db(music_path);
+            let path = relative
+                .then(|| relative_path(base_dir, Path::new(music_path)))
+                .flatten()
+                .map(|p| p.to_string_lossy().to_string())
+                .unwrap_or_else(|| music_path.clone());
+
+            match music {
+                Some(music) => PlaylistFileEntry {
+                    path,
+                    title: music.title,
+                    artist: music.artist,
+                    album: music.album,
+                    duration: music.duration.map(|d| d as f64 / 1000.0),
+                },
+                None => PlaylistFileEntry {
+                    path,
+                    title: None,
+                    artist: None,
+                    album: None,
+                    duration: None,
+                },
+            }
+        })
+        .collect();
+
+    // M3U files are written as UTF-8 too, which every current player reads
+    let content = match format {
+        PlaylistFormat::M3u => m3u::write(&playlist.name, &entries),
+        PlaylistFormat::Pls => pls::write(&entries),
+        PlaylistFormat::Xspf => xspf::write(&playlist.name, &entries),
+    };
+    std::fs::write(path, content).map_err(|e| e.to_string())
+}
+
+pub async fn pick_import_path() -> Result<PathBuf, String> {
+    let file = tokio::task::spawn_blocking(|| {
+        app_handle()
+            .dialog()
+            .file()
+            .add_filter("Playlist", &["m3u", "m3u8", "pls", "xspf"])
+            .blocking_pick_file()
+    })
+    .await
+    .map_err(|e| e.to_string())?;
+
+    file.and_then(|f| f.into_path().ok())
+        .ok_or_else(|| "No file selected".to_string())
+}
+
+pub async fn pick_export_path(name: String) -> Result<PathBuf, String> {
+    let file = tokio::task::spawn_blocking(move || {
+        app_handle()
+            .dialog()
+            .file()
+            .set_file_name(format!("{}.m3u8", name))
+            .add_filter("M3U8", &["m3u8"])
+            .add_filter("M3U", &["m3u"])
+            .add_filter("PLS", &["pls"])
+            .add_filter("XSPF", &["xspf"])
+            .blocking_save_file()
+    })
+    .await
+    .map_err(|e| e.to_string())?;
+
+    file.and_then(|f| f.into_path().ok())
+        .ok_or_else(|| "No file selected".to_string())
+}
+
+/// Playlist files are UTF-8 in practice, but old `.m3u` files are often Latin-1.
+fn decode_text(bytes: &[u8]) -> String {
+    let bytes = bytes.strip_prefix(b"\xEF\xBB\xBF").unwrap_or(bytes);
+    match std::str::from_utf8(bytes) {
+        Ok(text) => text.to_string(),
+        Err(_) => bytes.iter().map(|b| *b as char).collect(),
+    }
+}
+
+/// Turn a playlist entry into a filesystem path, `None` for remote streams.
+fn entry_path(entry: &str, base_dir: &Path) -> Option<PathBuf> {
+    if entry.is_empty() {
+        return None;
+    }
+    let entry = match entry.strip_prefix("file://") {
+        Some(uri) => {
+            let decoded = urlencoding::decode(uri).ok()?.to_string();
+            // file:///C:/Music on Windows
+            if cfg!(windows) {
+                decoded.trim_start_matches('/').to_string()
+            } else {
+                decoded
+            }
+        }
+        None if entry.contains("://") => return None,
+        None => entry.to_string(),
+    };
+
+    // Playlists written on Windows use backslashes
+    let entry = if cfg!(windows) {
+        entry
+    } else {
+        entry.replace('\\', "/")
+    };
+
+    let path = Path::new(&entry);
+    if path.is_absolute() {
+        Some(normalize(path))
+    } else {
+        Some(normalize(&base_dir.join(path)))
+    }
+}
+
+/// Resolve `.` and `..` without touching the filesystem, so the result matches scanned paths.
+fn normalize(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                normalized.pop();
+            }
+            other => normalized.push(other),
+        }
+    }
+    normalized
+}
+
+fn relative_path(from_dir: &Path, to: &Path) -> Option<PathBuf> {
+    let from_buf = normalize(from_dir);
+    let to_buf = normalize(to);
+    let from: Vec<_> = from_buf.components().collect();
+    let to: Vec<_> = to_buf.components().collect();
+
+    // Different drives or roots can't be reached relatively
+    if from.first() != to.first() {
+        return None;
+    }
+
+    let common = from.iter().zip(&to).take_while(|(a, b)| a == b).count();
+    let mut relative = PathBuf::new();
+    for _ in common..from.len() {
+        relative.push("..");
+    }
+    for component in &to[common..] {
+        relative.push(component);
+    }
+    Some(relative)
+}
+
+struct LibraryTrack {
+    path: String,
+    /// Title, artists and album as compared by `normalize_string`.
+    title: Option<String>,
+    artist: String,
+    primary_artist: String,
+    album: Option<String>,
+    /// Duration in seconds.
+    duration: Option<f64>,
+}
+
+/// Library paths indexed for matching playlist entries that moved or came from another machine.
+pub struct LibraryIndex {
+    paths: HashSet<String>,
+    by_filename: HashMap<String, Vec<usize>>,
+    by_id: HashMap<i64, usize>,
+    tracks: Vec<LibraryTrack>,
+}
+
+impl LibraryIndex {
+    pub fn load() -> Result<Self, rusqlite::Error> {
+        let conn_guard = GLOBAL_DATABASE.lock().unwrap();
+        let conn = conn_guard.as_ref().unwrap();
+
+        let mut stmt =
+            conn.prepare("SELECT id, path, title, artist, album, duration FROM musics")?;
+        let (ids, tracks): (Vec<i64>, Vec<LibraryTrack>) = stmt
+            .query_map([], |row| {
+                let artist = row.get::<_, Option<String>>(3)?.unwrap_or_default();
+                let primary_artist = artist
+                    .split(MusicMetadata::artist_separator())
+                    .next()
+                    .unwrap_or_default();
+
+                let track = LibraryTrack {
+                    path: row.get(1)?,
+                    title: row
+                        .get::<_, Option<String>>(2)?
+                        .map(|t| normalize_string(&t)),
+                    primary_artist: normalize_string(primary_artist),
+                    artist: normalize_string(&artist),
+                    album: row
+                        .get::<_, Option<String>>(4)?
+                        .map(|a| normalize_string(&a)),
+                    duration: row.get::<_, Option<i64>>(5)?.map(|d| d as f64 / 1000.0),
+                };
+                Ok((row.get::<_, i64>(0)?, track))
+            })?
+            .filter_map(|r| r.ok())
+            .collect();
+
+        let mut by_filename: HashMap<String, Vec<usize>> = HashMap::new();
+        for (i, track) in tracks.iter().enumerate() {
+            if let Some(name) = file_name_key(&track.path) {
+                by_filename.entry(name).or_default().push(i);
+            }
+        }
+
+        Ok(LibraryIndex {
+            paths: tracks.iter().map(|t| t.path.clone()).collect(),
+            by_filename,
+            by_id: ids.into_iter().enumerate().map(|(i, id)| (id, i)).collect(),
+            tracks,
+        })
+    }
+
+    /// Library path for a playlist entry: the path itself, the library track with the same
+    /// filename and most matching parent folders, or the track whose tags match best.
+    pub fn resolve(&self, entry: &PlaylistFileEntry, base_dir: &Path) -> Option<String> {
+        // Remote streams have no local file, but their tags may still match the library
+        if let Some(path) = entry_path(&entry.path, base_dir) {
+            let path_str = path.display().to_string();
+            if self.paths.contains(&path_str) || path.is_file() {
+                return Some(path_str);
+            }
+            if let Some(path) = self.match_filename(&entry.path) {
+                return Some(path);
+            }
+        }
+
+        self.match_metadata(entry)
+    }
+
+    fn match_filename(&self, entry_path: &str) -> Option<String> {
+        let entry_path = entry_path.replace('\\', "/").to_lowercase();
+        let candidates = self.by_filename.get(&file_name_key(&entry_path)?)?;
+
+        let entry_parts: Vec<&str> = entry_path.rsplit('/').collect();
+        candidates
+            .iter()
+            .map(|i| &self.tracks[*i].path)
+            .max_by_key(|path| {
+                let path = path.replace('\\', "/").to_lowercase();
+                path.rsplit('/')
+                    .zip(&entry_parts)
+                    .take_while(|(a, b)| a == *b)
+                    .count()
+            })
+            .cloned()
+    }
+
+    /// Fuzzy match on title, artist and album, rejecting tracks whose duration is too far off.
+    fn match_metadata(&self, entry: &PlaylistFileEntry) -> Option<String> {
+        let title = normalize_string(entry.title.as_ref()?);
+        if title.is_empty() {
+            return None;
+        }
+        let artist = entry.artist.as_deref().map(normalize_string);
+        let album = entry.album.as_deref().map(normalize_string);
+
+        let candidates = match self.candidates(entry, &title) {
+            Ok(candidates) => candidates,
+            Err(e) => {
+                crate::error!("Failed to look up tracks matching {}: {}", title, e);
+                return None;
+            }
+        };
+
+        candidates
+            .into_iter()
+            .filter_map(|track| {
+                if let (Some(a), Some(b)) = (entry.duration, track.duration) {
+                    if (a - b).abs() > MATCH_DURATION_TOLERANCE {
+                        return None;
+                    }
+                }
+                let track_title = track.title.as_ref()?;
+
+                let score = match &artist {
+                    Some(artist) => {
+                        let title_similarity = calculate_similarity(&title, track_title);
+                        // Skip the remaining comparisons for clearly different titles
+                        if title_similarity < 0.5 {
+                            return None;
+                        }
+                        let artist_similarity = calculate_similarity(artist, &track.artist)
+                            .max(calculate_similarity(artist, &track.primary_artist));
+
+                        match (&album, &track.album) {
+                            (Some(album), Some(track_album)) => {
+                                title_similarity * 0.5
+                                    + artist_similarity * 0.3
+                                    + calculate_similarity(album, track_album) * 0.2
+                            }
+                            _ => title_similarity * 0.6 + artist_similarity * 0.4,
+                        }
+                    }
+                    // M3U titles usually read "Artist - Title"
+                    None => calculate_similarity(&title, track_title).max(calculate_similarity(
+                        &title,
+                        &format!("{} {}", track.artist, track_title),
+                    )),
+                };
+                Some((score, track))
+            })
+            .filter(|(score, _)| *score >= MATCH_THRESHOLD)
+            .max_by(|a, b| a.0.total_cmp(&b.0))
+            .map(|(_, track)| track.path.clone())
+    }
+
+    /// Tracks worth scoring against `entry`: those of about the same duration, or without a
+    /// duration on either side the best full-text matches of its title.
+    fn candidates(
+        &self,
+        entry: &PlaylistFileEntry,
+        title: &str,
+    ) -> Result<Vec<&LibraryTrack>, rusqlite::Error> {
+        let conn_guard = GLOBAL_DATABASE.lock().unwrap();
+        let conn = conn_guard.as_ref().unwrap();
+
+        let ids: Vec<i64> = match entry.duration {
+            Some(duration) => {
+                let tolerance = MATCH_DURATION_TOLERANCE * 1000.0;
+                let mut stmt = conn.prepare_cached(
+                    "SELECT id FROM musics WHERE duration IS NULL OR duration BETWEEN ?1 AND ?2",
+                )?;
+                let ids = stmt
+                    .query_map(
+                        params![
+                            (duration * 1000.0 - tolerance).floor() as i64,
+                            (duration * 1000.0 + tolerance).ceil() as i64
+                        ],
+                        |row| row.get(0),
+                    )?
+                    .collect::<rusqlite::Result<_>>()?;
+                ids
+            }
+            None => {
+                // Any word may match, as M3U titles usually read "Artist - Title"
+                let words: Vec<String> = title
+                    .split_whitespace()
+                    .map(|word| format!("\"{}\"*", word))
+                    .collect();
+                let mut stmt = conn.prepare_cached(
+                    "SELECT rowid FROM musics_fts WHERE musics_fts MATCH ?1
+                    ORDER BY rank LIMIT ?2",
+                )?;
+                let ids = stmt
+                    .query_map(
+                        params![
+                            format!("{{title artist}} : ({})", words.join(" OR ")),
+                            MATCH_SEARCH_LIMIT
+                        ],
+                        |row| row.get(0),
+                    )?
+                    .collect::<rusqlite::Result<_>>()?;
+                ids
+            }
+        };
+
+        Ok(ids
+            .iter()
+            .filter_map(|id| self.by_id.get(id))
+            .map(|i| &self.tracks[*i])
+            .collect())
+    }
+}
+
+fn file_name_key(path: &str) -> Option<String> {
+    path.replace('\\', "/")
+        .rsplit('/')
+        .next()
+        .filter(|name| !name.is_empty())
+        .map(|name| name.to_lowercase())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn relative_path_walks_up_to_the_common_parent() {
+        let relative = relative_path(
+            Path::new("/music/playlists/./mixes"),
+            Path::new("/music/Artist/../Artist/Song.flac"),
+        );
+        assert_eq!(relative, Some(PathBuf::from("../../Artist/Song.flac")));
+
+        let relative = relative_path(Path::new("/music"), Path::new("/music/Song.flac"));
+        assert_eq!(relative, Some(PathBuf::from("Song.flac")));
+    }
+
+    #[test]
+    fn relative_path_needs_a_shared_root() {
+        assert_eq!(
+            relative_path(Path::new("music"), Path::new("/music/a.mp3")),
+            None
+        );
+    }
+
+    #[cfg(not(windows))]
+    #[test]
+    fn entry_paths_resolve_against_the_playlist() {
+        let base = Path::new("/music/playlists");
+        assert_eq!(
+            entry_path("../Artist/Song%20One.flac", base),
+            Some(PathBuf::from("/music/Artist/Song%20One.flac"))
+        );
+        assert_eq!(
+            entry_path("file:///music/Song%20One.flac", base),
+            Some(PathBuf::from("/music/Song One.flac"))
+        );
+        assert_eq!(
+            entry_path("..\\Artist\\Song.flac", base),
+            Some(PathBuf::from("/music/Artist/Song.flac"))
+        );
+        assert_eq!(entry_path("http://radio.example/stream", base), None);
+    }
+}
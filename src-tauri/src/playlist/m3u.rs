@@ -0,0 +1,109 @@
+use crate::playlist::file::{ParsedPlaylist, PlaylistFileEntry};
+
+pub fn parse(text: &str) -> ParsedPlaylist {
+    let mut playlist = ParsedPlaylist::default();
+    let mut title = None;
+    let mut duration = None;
+
+    for line in text.lines() {
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+
+        if let Some(info) = line.strip_prefix("#EXTINF:") {
+            // #EXTINF:<seconds>[ key="value"...],<display title>
+            let (head, display) = info.split_once(',').unwrap_or((info, ""));
+            duration = head
+                .split_whitespace()
+                .next()
+                .and_then(|d| d.parse::<f64>().ok())
+                .filter(|d| *d >= 0.0);
+            title = Some(display.trim().to_string()).filter(|t| !t.is_empty());
+            continue;
+        }
+        if let Some(name) = line.strip_prefix("#PLAYLIST:") {
+            playlist.name = Some(name.trim().to_string()).filter(|n| !n.is_empty());
+            continue;
+        }
+        if line.starts_with('#') {
+            continue;
+        }
+
+        playlist.entries.push(PlaylistFileEntry {
+            path: line.to_string(),
+            title: title.take(),
+            artist: None,
+            album: None,
+            duration: duration.take(),
+        });
+    }
+
+    playlist
+}
+
+pub fn write(name: &str, entries: &[PlaylistFileEntry]) -> String {
+    let mut out = String::from("#EXTM3U\n");
+    out.push_str(&format!("#PLAYLIST:{}\n", name));
+
+    for entry in entries {
+        let title = entry.display_title();
+        if title.is_some() || entry.duration.is_some() {
+            out.push_str(&format!(
+                "#EXTINF:{},{}\n",
+                entry.duration.map(|d| d.round() as i64).unwrap_or(-1),
+                title.unwrap_or_default()
+            ));
+        }
+        out.push_str(&entry.path);
+        out.push('\n');
+    }
+
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(path: &str, title: Option<&str>, duration: Option<f64>) -> PlaylistFileEntry {
+        PlaylistFileEntry {
+            path: path.to_string(),
+            title: title.map(str::to_string),
+            artist: None,
+            album: None,
+            duration,
+        }
+    }
+
+    #[test]
+    fn round_trip() {
+        let entries = [
+            entry("Artist/Song.flac", Some("Artist - Song"), Some(215.0)),
+            entry("/music/No Info.mp3", None, None),
+            entry("../Untimed.ogg", Some("Untimed"), None),
+        ];
+
+        let parsed = parse(&write("Mix", &entries));
+        assert_eq!(parsed.name.as_deref(), Some("Mix"));
+        assert_eq!(parsed.entries.len(), entries.len());
+        for (parsed, entry) in parsed.entries.iter().zip(&entries) {
+            assert_eq!(parsed.path, entry.path);
+            assert_eq!(parsed.title, entry.title);
+            assert_eq!(parsed.duration, entry.duration);
+        }
+    }
+
+    #[test]
+    fn extinf_applies_to_the_next_path_only() {
+        let parsed = parse(
+            "#EXTM3U\n#EXTINF:120 tvg-id=\"x\",First\r\nfirst.mp3\n\n# comment\nsecond.mp3\n",
+        );
+        assert_eq!(parsed.name, None);
+        assert_eq!(parsed.entries.len(), 2);
+        assert_eq!(parsed.entries[0].title.as_deref(), Some("First"));
+        assert_eq!(parsed.entries[0].duration, Some(120.0));
+        assert_eq!(parsed.entries[1].path, "second.mp3");
+        assert_eq!(parsed.entries[1].title, None);
+    }
+}
@@ -1,2 +1,7 @@
 pub mod commands;
+pub mod file;
+pub mod m3u;
 pub mod playlist;
+pub mod pls;
+pub mod smart;
+pub mod xspf;
@@ -1,6 +1,7 @@
 use crate::database::database::GLOBAL_DATABASE;
+use crate::playlist::smart::SmartPlaylistRules;
 use crate::state::app_handle;
-use rusqlite::params;
+use rusqlite::{params, OptionalExtension, Transaction};
 use serde::{Deserialize, Serialize};
 use tauri::Manager;
 use tauri_plugin_dialog::DialogExt;
@@ -14,6 +15,18 @@ pub struct Playlist {
     pub title: Option<String>,
     pub artist: Option<String>,
     pub paths: Vec<String>,
+    /// Set for smart playlists, whose `paths` are re-evaluated from these rules.
+    #[serde(default)]
+    pub rules: Option<SmartPlaylistRules>,
+}
+
+/// Optional playlist field that an update can clear.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum PlaylistField {
+    Image,
+    Title,
+    Artist,
 }
 
 impl Playlist {
@@ -23,7 +36,7 @@ impl Playlist {
         let tx = conn.transaction().unwrap();
 
         let mut stmt = tx
-            .prepare("SELECT id, name, image, title, artist FROM playlists")
+            .prepare("SELECT id, name, image, title, artist, rules FROM playlists")
             .unwrap();
 
         let playlist_rows = stmt
@@ -34,6 +47,7 @@ impl Playlist {
                     row.get::<_, Option<String>>(2)?,
                     row.get::<_, Option<String>>(3)?,
                     row.get::<_, Option<String>>(4)?,
+                    row.get::<_, Option<String>>(5)?,
                 ))
             })
             .unwrap()
@@ -42,7 +56,7 @@ impl Playlist {
 
         let mut playlists = Vec::new();
 
-        for (id, name, image, title, artist) in playlist_rows {
+        for (id, name, image, title, artist, rules) in playlist_rows {
             let mut music_stmt = tx
                 .prepare(
                     "SELECT path FROM playlist_musics
@@ -64,6 +78,7 @@ impl Playlist {
                 title,
                 artist,
                 paths,
+                rules: rules.and_then(|rules| Self::parse_rules(id, &rules)),
             });
         }
 
@@ -75,28 +90,94 @@ impl Playlist {
         let conn = conn_guard.as_mut().unwrap();
         let tx = conn.transaction()?;
 
+        let rules = playlist
+            .rules
+            .as_ref()
+            .map(|rules| serde_json::to_string(rules).unwrap_or_default());
+
         tx.execute(
-            "INSERT INTO playlists (name, image, title, artist) VALUES (?1, ?2, ?3, ?4)",
+            "INSERT INTO playlists (name, image, title, artist, rules)
+             VALUES (?1, ?2, ?3, ?4, ?5)",
             params![
                 playlist.name,
                 playlist.image,
                 playlist.title,
-                playlist.artist
+                playlist.artist,
+                rules
             ],
         )?;
 
         let playlist_id = tx.last_insert_rowid();
 
-        for (position, path) in playlist.paths.iter().enumerate() {
+        let paths = match &playlist.rules {
+            Some(rules) => rules.evaluate(&tx)?,
+            None => playlist.paths,
+        };
+        Self::insert_paths(&tx, playlist_id, &paths)?;
+
+        tx.commit()?;
+        Ok(playlist_id)
+    }
+
+    pub fn preview_smart(rules: &SmartPlaylistRules) -> Result<Vec<String>, rusqlite::Error> {
+        let conn_guard = GLOBAL_DATABASE.lock().unwrap();
+        let conn = conn_guard.as_ref().unwrap();
+        rules.evaluate(conn)
+    }
+
+    /// Re-evaluate every smart playlist against the current library.
+    pub fn refresh_smart() -> Result<(), rusqlite::Error> {
+        let mut conn_guard = GLOBAL_DATABASE.lock().unwrap();
+        let conn = conn_guard.as_mut().unwrap();
+        let tx = conn.transaction()?;
+
+        let mut stmt = tx.prepare("SELECT id, rules FROM playlists WHERE rules IS NOT NULL")?;
+        let smart_rows = stmt
+            .query_map(params![], |row| {
+                Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?))
+            })?
+            .filter_map(|r| r.ok())
+            .collect::<Vec<_>>();
+        drop(stmt);
+
+        for (id, rules) in smart_rows {
+            let Some(rules) = Self::parse_rules(id, &rules) else {
+                continue;
+            };
+            let paths = rules.evaluate(&tx)?;
+            tx.execute(
+                "DELETE FROM playlist_musics WHERE playlist_id = ?1",
+                params![id],
+            )?;
+            Self::insert_paths(&tx, id, &paths)?;
+        }
+
+        tx.commit()
+    }
+
+    fn insert_paths(
+        tx: &Transaction,
+        playlist_id: i64,
+        paths: &[String],
+    ) -> Result<(), rusqlite::Error> {
+        for (position, path) in paths.iter().enumerate() {
             tx.execute(
                 "INSERT INTO playlist_musics (playlist_id, path, position)
                  VALUES (?1, ?2, ?3)",
                 params![playlist_id, path, position as i64],
             )?;
         }
+        Ok(())
+    }
 
-        tx.commit()?;
-        Ok(playlist_id)
+    fn parse_rules(id: i64, rules: &str) -> Option<SmartPlaylistRules> {
+        match serde_json::from_str(rules) {
+            Ok(rules) => Some(rules),
+            Err(e) => {
+                crate::warn!("Invalid smart playlist rules for id {}: {}", id, e);
+                None
+            }
+        }
     }
 
     pub fn delete(id: i64) -> Result<(), rusqlite::Error> {
@@ -109,6 +190,219 @@ impl Playlist {
         Ok(())
     }
 
+    /// Update the fields that are given and clear the ones listed in `clear`, leaving the
+    /// others as they are.
+    pub fn update(
+        id: i64,
+        name: Option<String>,
+        image: Option<String>,
+        title: Option<String>,
+        artist: Option<String>,
+        clear: &[PlaylistField],
+    ) -> Result<(), String> {
+        let conn_guard = GLOBAL_DATABASE.lock().unwrap();
+        let conn = conn_guard.as_ref().unwrap();
+
+        let updated = conn
+            .execute(
+                "UPDATE playlists SET
+                    name = COALESCE(?1, name),
+                    image = CASE WHEN ?5 THEN NULL ELSE COALESCE(?2, image) END,
+                    title = CASE WHEN ?6 THEN NULL ELSE COALESCE(?3, title) END,
+                    artist = CASE WHEN ?7 THEN NULL ELSE COALESCE(?4, artist) END
+                 WHERE id = ?8",
+                params![
+                    name,
+                    image,
+                    title,
+                    artist,
+                    clear.contains(&PlaylistField::Image),
+                    clear.contains(&PlaylistField::Title),
+                    clear.contains(&PlaylistField::Artist),
+                    id
+                ],
+            )
+            .map_err(|e| e.to_string())?;
+
+        if updated == 0 {
+            return Err(format!("Playlist {} not found", id));
+        }
+        Ok(())
+    }
+
+    /// Insert `paths` before `position`, or append them when `position` is `None`.
+    pub fn insert_musics(
+        id: i64,
+        paths: Vec<String>,
+        position: Option<usize>,
+    ) -> Result<(), String> {
+        Self::edit_musics(id, |tx, count| {
+            Self::insert_positions(tx, id, &paths, position, count)
+        })
+    }
+
+    pub fn remove_musics(id: i64, positions: Vec<usize>) -> Result<(), String> {
+        Self::edit_musics(id, |tx, count| {
+            Self::remove_positions(tx, id, &positions, count)
+        })
+    }
+
+    pub fn move_music(id: i64, from: usize, to: usize) -> Result<(), String> {
+        Self::edit_musics(id, |tx, count| Self::move_position(tx, id, from, to, count))
+    }
+
+    fn insert_positions(
+        tx: &Transaction,
+        id: i64,
+        paths: &[String],
+        position: Option<usize>,
+        count: usize,
+    ) -> Result<(), String> {
+        let position = position.unwrap_or(count);
+        if position > count {
+            return Err(format!("Position {} is out of range", position));
+        }
+
+        tx.execute(
+            "UPDATE playlist_musics SET position = position + ?1
+             WHERE playlist_id = ?2 AND position >= ?3",
+            params![paths.len() as i64, id, position as i64],
+        )
+        .map_err(|e| e.to_string())?;
+
+        for (offset, path) in paths.iter().enumerate() {
+            tx.execute(
+                "INSERT INTO playlist_musics (playlist_id, path, position)
+                 VALUES (?1, ?2, ?3)",
+                params![id, path, (position + offset) as i64],
+            )
+            .map_err(|e| e.to_string())?;
+        }
+        Ok(())
+    }
+
+    fn remove_positions(
+        tx: &Transaction,
+        id: i64,
+        positions: &[usize],
+        count: usize,
+    ) -> Result<(), String> {
+        if let Some(position) = positions.iter().find(|p| **p >= count) {
+            return Err(format!("Position {} is out of range", position));
+        }
+
+        for position in positions {
+            tx.execute(
+                "DELETE FROM playlist_musics WHERE playlist_id = ?1 AND position = ?2",
+                params![id, *position as i64],
+            )
+            .map_err(|e| e.to_string())?;
+        }
+
+        // Close the gaps left by the removed tracks. The new positions are numbered before
+        // any row changes, so rows updated first can't shift the ones after them.
+        tx.execute(
+            "UPDATE playlist_musics SET position = ordered.position
+             FROM (
+                SELECT id, ROW_NUMBER() OVER (ORDER BY position) - 1 AS position
+                FROM playlist_musics WHERE playlist_id = ?1
+             ) AS ordered
+             WHERE playlist_musics.id = ordered.id",
+            params![id],
+        )
+        .map_err(|e| e.to_string())?;
+        Ok(())
+    }
+
+    fn move_position(
+        tx: &Transaction,
+        id: i64,
+        from: usize,
+        to: usize,
+        count: usize,
+    ) -> Result<(), String> {
+        if from >= count || to >= count {
+            return Err(format!("Cannot move from {} to {}", from, to));
+        }
+        if from == to {
+            return Ok(());
+        }
+
+        // Park the moved track outside the range while the others shift over
+        tx.execute(
+            "UPDATE playlist_musics SET position = -1
+             WHERE playlist_id = ?1 AND position = ?2",
+            params![id, from as i64],
+        )
+        .map_err(|e| e.to_string())?;
+
+        let shifted = if from < to {
+            tx.execute(
+                "UPDATE playlist_musics SET position = position - 1
+                 WHERE playlist_id = ?1 AND position > ?2 AND position <= ?3",
+                params![id, from as i64, to as i64],
+            )
+        } else {
+            tx.execute(
+                "UPDATE playlist_musics SET position = position + 1
+                 WHERE playlist_id = ?1 AND position >= ?2 AND position < ?3",
+                params![id, to as i64, from as i64],
+            )
+        };
+        shifted.map_err(|e| e.to_string())?;
+
+        tx.execute(
+            "UPDATE playlist_musics SET position = ?2
+             WHERE playlist_id = ?1 AND position = -1",
+            params![id, to as i64],
+        )
+        .map_err(|e| e.to_string())?;
+        Ok(())
+    }
+
+    /// Run a track edit in a transaction, passing the current track count. Smart playlists
+    /// are rejected since their tracks come from their rules.
+    fn edit_musics(
+        id: i64,
+        edit: impl FnOnce(&Transaction, usize) -> Result<(), String>,
+    ) -> Result<(), String> {
+        let mut conn_guard = GLOBAL_DATABASE.lock().unwrap();
+        let conn = conn_guard.as_mut().unwrap();
+        let tx = conn.transaction().map_err(|e| e.to_string())?;
+
+        let is_smart: bool = tx
+            .query_row(
+                "SELECT rules IS NOT NULL FROM playlists WHERE id = ?1",
+                params![id],
+                |row| row.get(0),
+            )
+            .optional()
+            .map_err(|e| e.to_string())?
+            .ok_or_else(|| format!("Playlist {} not found", id))?;
+        if is_smart {
+            return Err("Tracks of a smart playlist can't be edited".to_string());
+        }
+
+        let count: i64 = tx
+            .query_row(
+                "SELECT COUNT(*) FROM playlist_musics WHERE playlist_id = ?1",
+                params![id],
+                |row| row.get(0),
+            )
+            .map_err(|e| e.to_string())?;
+
+        edit(&tx, count as usize)?;
+
+        // Track edits don't touch the playlist row, so bump its timestamp here
+        tx.execute(
+            "UPDATE playlists SET updated_at = CURRENT_TIMESTAMP WHERE id = ?1",
+            params![id],
+        )
+        .map_err(|e| e.to_string())?;
+
+        tx.commit().map_err(|e| e.to_string())
+    }
+
     pub async fn read_image(id: u8) -> Result<Vec<u8>, String> {
         let path = {
             let conn_guard = GLOBAL_DATABASE.lock().unwrap();
@@ -162,3 +456,110 @@ impl Playlist {
             .to_string())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::migrations::DATABASE_MIGRATIONS;
+    use rusqlite::Connection;
+
+    /// A database with one playlist of the tracks "A" to "F".
+    fn playlist() -> (Connection, i64) {
+        let mut conn = Connection::open_in_memory().unwrap();
+        DATABASE_MIGRATIONS.to_latest(&mut conn).unwrap();
+        conn.execute("INSERT INTO playlists (name) VALUES ('test')", [])
+            .unwrap();
+        let id = conn.last_insert_rowid();
+
+        let tx = conn.transaction().unwrap();
+        let paths = ["A", "B", "C", "D", "E", "F"].map(String::from);
+        Playlist::insert_paths(&tx, id, &paths).unwrap();
+        tx.commit().unwrap();
+        (conn, id)
+    }
+
+    /// Run an edit like `edit_musics` does, without the global database.
+    fn edit(
+        conn: &mut Connection,
+        id: i64,
+        edit: impl FnOnce(&Transaction, usize) -> Result<(), String>,
+    ) {
+        let tx = conn.transaction().unwrap();
+        let count: i64 = tx
+            .query_row(
+                "SELECT COUNT(*) FROM playlist_musics WHERE playlist_id = ?1",
+                params![id],
+                |row| row.get(0),
+            )
+            .unwrap();
+        edit(&tx, count as usize).unwrap();
+        tx.commit().unwrap();
+    }
+
+    /// Tracks with their stored positions, in order.
+    fn tracks(conn: &Connection, id: i64) -> Vec<(String, i64)> {
+        let mut stmt = conn
+            .prepare(
+                "SELECT path, position FROM playlist_musics
+                 WHERE playlist_id = ?1 ORDER BY position",
+            )
+            .unwrap();
+        stmt.query_map(params![id], |row| Ok((row.get(0)?, row.get(1)?)))
+            .unwrap()
+            .map(|r| r.unwrap())
+            .collect()
+    }
+
+    fn expected(paths: &[&str]) -> Vec<(String, i64)> {
+        paths
+            .iter()
+            .enumerate()
+            .map(|(i, path)| (path.to_string(), i as i64))
+            .collect()
+    }
+
+    #[test]
+    fn insert_shifts_following_tracks() {
+        let (mut conn, id) = playlist();
+        let paths = ["X", "Y"].map(String::from);
+        edit(&mut conn, id, |tx, count| {
+            Playlist::insert_positions(tx, id, &paths, Some(1), count)
+        });
+        assert_eq!(
+            tracks(&conn, id),
+            expected(&["A", "X", "Y", "B", "C", "D", "E", "F"])
+        );
+    }
+
+    #[test]
+    fn move_then_remove_keeps_positions_unique() {
+        let (mut conn, id) = playlist();
+        edit(&mut conn, id, |tx, count| {
+            Playlist::move_position(tx, id, 0, 5, count)
+        });
+        assert_eq!(tracks(&conn, id), expected(&["B", "C", "D", "E", "F", "A"]));
+
+        edit(&mut conn, id, |tx, count| {
+            Playlist::remove_positions(tx, id, &[0, 1], count)
+        });
+        assert_eq!(tracks(&conn, id), expected(&["D", "E", "F", "A"]));
+    }
+
+    #[test]
+    fn move_backwards() {
+        let (mut conn, id) = playlist();
+        edit(&mut conn, id, |tx, count| {
+            Playlist::move_position(tx, id, 4, 1, count)
+        });
+        assert_eq!(tracks(&conn, id), expected(&["A", "E", "B", "C", "D", "F"]));
+    }
+
+    #[test]
+    fn out_of_range_positions_are_rejected() {
+        let (mut conn, id) = playlist();
+        let tx = conn.transaction().unwrap();
+        assert!(Playlist::remove_positions(&tx, id, &[6], 6).is_err());
+        assert!(Playlist::move_position(&tx, id, 0, 6, 6).is_err());
+        assert!(Playlist::insert_positions(&tx, id, &[], Some(7), 6).is_err());
+    }
+}
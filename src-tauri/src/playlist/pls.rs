@@ -0,0 +1,111 @@
+use std::collections::BTreeMap;
+
+use crate::playlist::file::{ParsedPlaylist, PlaylistFileEntry};
+
+pub fn parse(text: &str) -> ParsedPlaylist {
+    // Entries are numbered as File1, Title1, Length1... and may come in any order
+    let mut entries: BTreeMap<u32, PlaylistFileEntry> = BTreeMap::new();
+
+    for line in text.lines() {
+        let Some((key, value)) = line.trim().split_once('=') else {
+            continue;
+        };
+        let key = key.trim().to_lowercase();
+        let value = value.trim();
+
+        let (field, number) = match key.find(|c: char| c.is_ascii_digit()) {
+            Some(i) => (&key[..i], key[i..].parse::<u32>().ok()),
+            None => continue,
+        };
+        let Some(number) = number else {
+            continue;
+        };
+
+        let entry = entries.entry(number).or_insert_with(|| PlaylistFileEntry {
+            path: String::new(),
+            title: None,
+            artist: None,
+            album: None,
+            duration: None,
+        });
+        match field {
+            "file" => entry.path = value.to_string(),
+            "title" => entry.title = Some(value.to_string()).filter(|t| !t.is_empty()),
+            "length" => {
+                entry.duration = value.parse::<f64>().ok().filter(|d| *d >= 0.0);
+            }
+            _ => {}
+        }
+    }
+
+    ParsedPlaylist {
+        name: None,
+        entries: entries
+            .into_values()
+            .filter(|entry| !entry.path.is_empty())
+            .collect(),
+    }
+}
+
+pub fn write(entries: &[PlaylistFileEntry]) -> String {
+    let mut out = String::from("[playlist]\n");
+
+    for (i, entry) in entries.iter().enumerate() {
+        let number = i + 1;
+        out.push_str(&format!("File{}={}\n", number, entry.path));
+        if let Some(title) = entry.display_title() {
+            out.push_str(&format!("Title{}={}\n", number, title));
+        }
+        out.push_str(&format!(
+            "Length{}={}\n",
+            number,
+            entry.duration.map(|d| d.round() as i64).unwrap_or(-1)
+        ));
+    }
+
+    out.push_str(&format!("NumberOfEntries={}\nVersion=2\n", entries.len()));
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        let entries = [
+            PlaylistFileEntry {
+                path: "C:\\Music\\Song.flac".to_string(),
+                title: Some("Song".to_string()),
+                artist: None,
+                album: None,
+                duration: Some(215.0),
+            },
+            PlaylistFileEntry {
+                path: "other.mp3".to_string(),
+                title: None,
+                artist: None,
+                album: None,
+                duration: None,
+            },
+        ];
+
+        let parsed = parse(&write(&entries));
+        assert_eq!(parsed.entries.len(), entries.len());
+        for (parsed, entry) in parsed.entries.iter().zip(&entries) {
+            assert_eq!(parsed.path, entry.path);
+            assert_eq!(parsed.title, entry.title);
+            assert_eq!(parsed.duration, entry.duration);
+        }
+    }
+
+    #[test]
+    fn entries_are_ordered_by_number() {
+        let parsed =
+            parse("[playlist]\nTitle2=Second\nFile2=b.mp3\nfile1 = a.mp3\nLength1=30\nFile3=\n");
+        let paths: Vec<_> = parsed.entries.iter().map(|e| e.path.as_str()).collect();
+        assert_eq!(paths, ["a.mp3", "b.mp3"]);
+        assert_eq!(parsed.entries[0].duration, Some(30.0));
+        assert_eq!(parsed.entries[1].title.as_deref(), Some("Second"));
+    }
+}
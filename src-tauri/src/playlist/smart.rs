@@ -0,0 +1,457 @@
+use crate::music::metadata::MusicMetadata;
+use rusqlite::types::Value;
+use rusqlite::{params_from_iter, Connection};
+use serde::{Deserialize, Serialize};
+
+/// Rules of a smart playlist, stored as JSON in `playlists.rules`.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct SmartPlaylistRules {
+    pub rule: SmartRule,
+    pub sort: Option<SmartSort>,
+    pub limit: Option<u32>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum SmartRule {
+    All {
+        rules: Vec<SmartRule>,
+    },
+    Any {
+        rules: Vec<SmartRule>,
+    },
+    Not {
+        rule: Box<SmartRule>,
+    },
+    Condition {
+        field: SmartField,
+        operator: SmartOperator,
+        value: serde_json::Value,
+    },
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum SmartField {
+    Path,
+    Title,
+    Artist,
+    Album,
+    AlbumArtist,
+    Genre,
+    Date,
+    TrackNumber,
+    DiscNumber,
+    Composer,
+    Codec,
+    Duration,
+    Bitrate,
+    BitsPerSample,
+    SampleRate,
+    PlayCount,
+    LastPlayedAt,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum SmartOperator {
+    Equals,
+    NotEquals,
+    Contains,
+    NotContains,
+    StartsWith,
+    EndsWith,
+    LessThan,
+    LessThanOrEqual,
+    GreaterThan,
+    GreaterThanOrEqual,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(tag = "by", rename_all = "camelCase")]
+pub enum SmartSort {
+    Random,
+    Field {
+        field: SmartField,
+        #[serde(default)]
+        descending: bool,
+    },
+}
+
+impl SmartField {
+    pub fn column(self) -> &'static str {
+        match self {
+            SmartField::Path => "musics.path",
+            SmartField::Title => "musics.title",
+            SmartField::Artist => "musics.artist",
+            SmartField::Album => "musics.album",
+            SmartField::AlbumArtist => "musics.album_artist",
+            SmartField::Genre => "musics.genre",
+            SmartField::Date => "musics.date",
+            SmartField::TrackNumber => "CAST(musics.track_number AS INTEGER)",
+            SmartField::DiscNumber => "musics.disc_number",
+            SmartField::Composer => "musics.composer",
+            SmartField::Codec => "musics.codec",
+            SmartField::Duration => "musics.duration",
+            SmartField::Bitrate => "musics.bitrate",
+            SmartField::BitsPerSample => "musics.bits_per_sample",
+            SmartField::SampleRate => "musics.sample_rate",
+            SmartField::PlayCount => "(SELECT COUNT(*) FROM plays WHERE plays.path = musics.path)",
+            SmartField::LastPlayedAt => {
+                "(SELECT MAX(plays.played_at) FROM plays WHERE plays.path = musics.path)"
+            }
+        }
+    }
+
+    /// Whether the field may hold several values joined by the artist separator.
+    fn is_multi_value(self) -> bool {
+        matches!(self, SmartField::Artist | SmartField::Genre)
+    }
+
+    pub fn is_text(self) -> bool {
+        matches!(
+            self,
+            SmartField::Path
+                | SmartField::Title
+                | SmartField::Artist
+                | SmartField::Album
+                | SmartField::AlbumArtist
+                | SmartField::Genre
+                | SmartField::Date
+                | SmartField::Composer
+                | SmartField::Codec
+        )
+    }
+}
+
+impl SmartRule {
+    /// Build a `WHERE` expression, pushing the bound values onto `params` in order.
+    fn to_sql(&self, params: &mut Vec<Value>) -> String {
+        match self {
+            SmartRule::All { rules } => Self::join(rules, " AND ", "1", params),
+            SmartRule::Any { rules } => Self::join(rules, " OR ", "0", params),
+            SmartRule::Not { rule } => format!("NOT ({})", rule.to_sql(params)),
+            SmartRule::Condition {
+                field,
+                operator,
+                value,
+            } => Self::condition_to_sql(*field, *operator, value, params),
+        }
+    }
+
+    fn join(rules: &[SmartRule], separator: &str, empty: &str, params: &mut Vec<Value>) -> String {
+        if rules.is_empty() {
+            return empty.to_string();
+        }
+        rules
+            .iter()
+            .map(|rule| format!("({})", rule.to_sql(params)))
+            .collect::<Vec<_>>()
+            .join(separator)
+    }
+
+    fn condition_to_sql(
+        field: SmartField,
+        operator: SmartOperator,
+        value: &serde_json::Value,
+        params: &mut Vec<Value>,
+    ) -> String {
+        if field.is_multi_value()
+            && matches!(operator, SmartOperator::Equals | SmartOperator::NotEquals)
+        {
+            return Self::multi_value_condition_to_sql(field, operator, value, params);
+        }
+
+        let column = field.column();
+        // Missing tags should still match negative conditions such as "artist doesn't contain X"
+        let nullable_column = if field.is_text() {
+            format!("IFNULL({}, '')", column)
+        } else {
+            column.to_string()
+        };
+
+        match operator {
+            SmartOperator::Contains
+            | SmartOperator::NotContains
+            | SmartOperator::StartsWith
+            | SmartOperator::EndsWith => {
+                let text = escape_like(&value_to_text(value));
+                let pattern = match operator {
+                    SmartOperator::StartsWith => format!("{}%", text),
+                    SmartOperator::EndsWith => format!("%{}", text),
+                    _ => format!("%{}%", text),
+                };
+                params.push(Value::Text(pattern));
+
+                if operator == SmartOperator::NotContains {
+                    format!("{} NOT LIKE ? ESCAPE '\\'", nullable_column)
+                } else {
+                    format!("{} LIKE ? ESCAPE '\\'", column)
+                }
+            }
+            SmartOperator::Equals | SmartOperator::NotEquals => {
+                params.push(json_to_sql(value));
+                let collate = if field.is_text() {
+                    " COLLATE NOCASE"
+                } else {
+                    ""
+                };
+                if operator == SmartOperator::NotEquals {
+                    format!("{} != ?{}", nullable_column, collate)
+                } else {
+                    format!("{} = ?{}", column, collate)
+                }
+            }
+            SmartOperator::LessThan
+            | SmartOperator::LessThanOrEqual
+            | SmartOperator::GreaterThan
+            | SmartOperator::GreaterThanOrEqual => {
+                params.push(json_to_sql(value));
+                let sign = match operator {
+                    SmartOperator::LessThan => "<",
+                    SmartOperator::LessThanOrEqual => "<=",
+                    SmartOperator::GreaterThan => ">",
+                    _ => ">=",
+                };
+                format!("{} {} ?", column, sign)
+            }
+        }
+    }
+
+    /// Equality on one of the values of a multi-value tag such as "A; B", ignoring case like
+    /// the other text fields.
+    fn multi_value_condition_to_sql(
+        field: SmartField,
+        operator: SmartOperator,
+        value: &serde_json::Value,
+        params: &mut Vec<Value>,
+    ) -> String {
+        let separator = MusicMetadata::artist_separator();
+        params.push(Value::Text(format!(
+            "%{0}{1}{0}%",
+            separator,
+            escape_like(value_to_text(value).trim())
+        )));
+
+        // Separators are compared without the spaces around them, and wrapped around the
+        // whole tag so the first and last values match like the others
+        let values = format!(
+            "'{0}' || replace(replace(IFNULL({1}, ''), ' {0}', '{0}'), '{0} ', '{0}') || '{0}'",
+            separator,
+            field.column()
+        );
+        if operator == SmartOperator::NotEquals {
+            format!("{} NOT LIKE ? ESCAPE '\\'", values)
+        } else {
+            format!("{} LIKE ? ESCAPE '\\'", values)
+        }
+    }
+}
+
+impl SmartPlaylistRules {
+    /// Paths of the library tracks matching the rules, in playlist order.
+    pub fn evaluate(&self, conn: &Connection) -> Result<Vec<String>, rusqlite::Error> {
+        let mut params = Vec::new();
+        let filter = self.rule.to_sql(&mut params);
+
+        let order = match &self.sort {
+            Some(SmartSort::Random) => "RANDOM()".to_string(),
+            Some(SmartSort::Field { field, descending }) => format!(
+                "{} {}, musics.path",
+                field.column(),
+                if *descending { "DESC" } else { "ASC" }
+            ),
+            None => "musics.path".to_string(),
+        };
+
+        let mut query = format!(
+            "SELECT musics.path FROM musics WHERE {} ORDER BY {}",
+            filter, order
+        );
+        if let Some(limit) = self.limit {
+            query.push_str(" LIMIT ?");
+            params.push(Value::Integer(limit as i64));
+        }
+
+        let mut stmt = conn.prepare(&query)?;
+        let paths = stmt
+            .query_map(params_from_iter(params), |row| row.get::<_, String>(0))?
+            .filter_map(|r| r.ok())
+            .collect();
+        Ok(paths)
+    }
+}
+
+fn json_to_sql(value: &serde_json::Value) -> Value {
+    match value {
+        serde_json::Value::Null => Value::Null,
+        serde_json::Value::Bool(b) => Value::Integer(*b as i64),
+        serde_json::Value::Number(n) => match n.as_i64() {
+            Some(i) => Value::Integer(i),
+            None => Value::Real(n.as_f64().unwrap_or_default()),
+        },
+        _ => Value::Text(value_to_text(value)),
+    }
+}
+
+fn value_to_text(value: &serde_json::Value) -> String {
+    match value {
+        serde_json::Value::String(s) => s.clone(),
+        serde_json::Value::Null => String::new(),
+        other => other.to_string(),
+    }
+}
+
+pub fn escape_like(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace('%', "\\%")
+        .replace('_', "\\_")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::migrations::DATABASE_MIGRATIONS;
+    use serde_json::json;
+
+    /// An in-memory library with the tracks as (path, artist, genre, duration).
+    fn library(tracks: &[(&str, Option<&str>, Option<&str>, i64)]) -> Connection {
+        let mut conn = Connection::open_in_memory().unwrap();
+        DATABASE_MIGRATIONS.to_latest(&mut conn).unwrap();
+        for (path, artist, genre, duration) in tracks {
+            conn.execute(
+                "INSERT INTO musics (path, artist, genre, duration, modified_at)
+                VALUES (?1, ?2, ?3, ?4, '')",
+                rusqlite::params![path, artist, genre, duration],
+            )
+            .unwrap();
+        }
+        conn
+    }
+
+    fn condition(
+        field: SmartField,
+        operator: SmartOperator,
+        value: serde_json::Value,
+    ) -> SmartRule {
+        SmartRule::Condition {
+            field,
+            operator,
+            value,
+        }
+    }
+
+    fn evaluate(conn: &Connection, rule: SmartRule) -> Vec<String> {
+        SmartPlaylistRules {
+            rule,
+            sort: None,
+            limit: None,
+        }
+        .evaluate(conn)
+        .unwrap()
+    }
+
+    #[test]
+    fn artist_equality_matches_each_value() {
+        let conn = library(&[
+            ("a", Some("Alice; Bob"), None, 0),
+            ("b", Some("Bob ;Carol"), None, 0),
+            ("c", Some("Bobby"), None, 0),
+            ("d", None, None, 0),
+        ]);
+
+        let equals = condition(SmartField::Artist, SmartOperator::Equals, json!(" bob "));
+        assert_eq!(evaluate(&conn, equals), ["a", "b"]);
+
+        let not_equals = condition(SmartField::Artist, SmartOperator::NotEquals, json!("Bob"));
+        assert_eq!(evaluate(&conn, not_equals), ["c", "d"]);
+    }
+
+    #[test]
+    fn like_patterns_are_escaped() {
+        let conn = library(&[
+            ("a", None, Some("100% Rock"), 0),
+            ("b", None, Some("1000 Rock"), 0),
+            ("c", None, Some("Rock_Pop"), 0),
+        ]);
+
+        let percent = condition(SmartField::Genre, SmartOperator::Contains, json!("0%"));
+        assert_eq!(evaluate(&conn, percent), ["a"]);
+
+        let underscore = condition(SmartField::Genre, SmartOperator::Equals, json!("rock_pop"));
+        assert_eq!(evaluate(&conn, underscore), ["c"]);
+    }
+
+    #[test]
+    fn negative_conditions_match_missing_tags() {
+        let conn = library(&[("a", Some("Alice"), None, 0), ("b", None, None, 0)]);
+
+        let rule = condition(SmartField::Artist, SmartOperator::NotContains, json!("ali"));
+        assert_eq!(evaluate(&conn, rule), ["b"]);
+    }
+
+    #[test]
+    fn groups_combine_their_rules() {
+        let conn = library(&[
+            ("a", Some("Alice"), Some("Rock"), 100),
+            ("b", Some("Bob"), Some("Rock"), 300),
+            ("c", Some("Carol"), Some("Jazz"), 300),
+        ]);
+
+        let rule = SmartRule::All {
+            rules: vec![
+                condition(SmartField::Genre, SmartOperator::Equals, json!("rock")),
+                SmartRule::Not {
+                    rule: Box::new(condition(
+                        SmartField::Duration,
+                        SmartOperator::LessThan,
+                        json!(200),
+                    )),
+                },
+            ],
+        };
+        assert_eq!(evaluate(&conn, rule), ["b"]);
+
+        let rule = SmartRule::Any {
+            rules: vec![
+                condition(SmartField::Artist, SmartOperator::StartsWith, json!("car")),
+                condition(
+                    SmartField::Duration,
+                    SmartOperator::LessThanOrEqual,
+                    json!(100),
+                ),
+            ],
+        };
+        assert_eq!(evaluate(&conn, rule), ["a", "c"]);
+
+        assert_eq!(
+            evaluate(&conn, SmartRule::Any { rules: vec![] }),
+            Vec::<String>::new()
+        );
+        assert_eq!(evaluate(&conn, SmartRule::All { rules: vec![] }).len(), 3);
+    }
+
+    #[test]
+    fn sorts_and_limits_by_play_count() {
+        let conn = library(&[
+            ("a", None, None, 0),
+            ("b", None, None, 0),
+            ("c", None, None, 0),
+        ]);
+        for path in ["b", "b", "c"] {
+            conn.execute("INSERT INTO plays (path, played_at) VALUES (?1, 0)", [path])
+                .unwrap();
+        }
+
+        let rules = SmartPlaylistRules {
+            rule: condition(SmartField::PlayCount, SmartOperator::GreaterThan, json!(0)),
+            sort: Some(SmartSort::Field {
+                field: SmartField::PlayCount,
+                descending: true,
+            }),
+            limit: Some(1),
+        };
+        assert_eq!(rules.evaluate(&conn).unwrap(), ["b"]);
+    }
+}
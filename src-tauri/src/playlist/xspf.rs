@@ -0,0 +1,198 @@
+use std::path::Path;
+
+use quick_xml::escape::escape;
+use serde::Deserialize;
+
+use crate::playlist::file::{ParsedPlaylist, PlaylistFileEntry};
+
+#[derive(Debug, Deserialize)]
+struct XspfPlaylist {
+    title: Option<String>,
+    #[serde(rename = "trackList", default)]
+    track_list: XspfTrackList,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct XspfTrackList {
+    #[serde(rename = "track", default)]
+    tracks: Vec<XspfTrack>,
+}
+
+#[derive(Debug, Deserialize)]
+struct XspfTrack {
+    #[serde(default)]
+    location: Vec<String>,
+    title: Option<String>,
+    creator: Option<String>,
+    album: Option<String>,
+    /// Duration in milliseconds, kept as text since some writers leave it empty.
+    duration: Option<String>,
+}
+
+pub fn parse(text: &str) -> Result<ParsedPlaylist, String> {
+    let playlist: XspfPlaylist =
+        quick_xml::de::from_str(text).map_err(|e| format!("Invalid XSPF playlist: {}", e))?;
+
+    let entries = playlist
+        .track_list
+        .tracks
+        .into_iter()
+        .map(|track| PlaylistFileEntry {
+            path: track
+                .location
+                .first()
+                .map(|location| location_to_path(location.trim()))
+                .unwrap_or_default(),
+            title: non_empty(track.title),
+            artist: non_empty(track.creator),
+            album: non_empty(track.album),
+            duration: track
+                .duration
+                .and_then(|d| d.trim().parse::<f64>().ok())
+                .filter(|d| *d >= 0.0)
+                .map(|d| d / 1000.0),
+        })
+        .collect();
+
+    Ok(ParsedPlaylist {
+        name: non_empty(playlist.title),
+        entries,
+    })
+}
+
+pub fn write(name: &str, entries: &[PlaylistFileEntry]) -> String {
+    let mut out = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    out.push_str("<playlist version=\"1\" xmlns=\"http://xspf.org/ns/0/\">\n");
+    out.push_str(&format!("  <title>{}</title>\n", escape(name)));
+    out.push_str("  <trackList>\n");
+
+    for entry in entries {
+        out.push_str("    <track>\n");
+        out.push_str(&format!(
+            "      <location>{}</location>\n",
+            escape(path_to_location(&entry.path))
+        ));
+        for (tag, value) in [
+            ("title", &entry.title),
+            ("creator", &entry.artist),
+            ("album", &entry.album),
+        ] {
+            if let Some(value) = value {
+                out.push_str(&format!("      <{0}>{1}</{0}>\n", tag, escape(value)));
+            }
+        }
+        if let Some(duration) = entry.duration {
+            out.push_str(&format!(
+                "      <duration>{}</duration>\n",
+                (duration * 1000.0).round() as u64
+            ));
+        }
+        out.push_str("    </track>\n");
+    }
+
+    out.push_str("  </trackList>\n</playlist>\n");
+    out
+}
+
+fn non_empty(value: Option<String>) -> Option<String> {
+    value
+        .map(|v| v.trim().to_string())
+        .filter(|v| !v.is_empty())
+}
+
+/// Relative locations are percent-encoded URI references, while `file://` and remote URIs
+/// are left for the importer to handle.
+fn location_to_path(location: &str) -> String {
+    if location.contains("://") {
+        return location.to_string();
+    }
+    urlencoding::decode(location)
+        .map(|decoded| decoded.to_string())
+        .unwrap_or_else(|_| location.to_string())
+}
+
+fn path_to_location(path: &str) -> String {
+    let is_absolute = Path::new(path).is_absolute();
+    let path = path.replace('\\', "/");
+
+    let encoded = path
+        .split('/')
+        .map(|segment| {
+            // Keep Windows drive letters such as `C:` readable
+            if segment.len() == 2 && segment.ends_with(':') {
+                segment.to_string()
+            } else {
+                urlencoding::encode(segment).to_string()
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("/");
+
+    match (is_absolute, encoded.starts_with('/')) {
+        (true, true) => format!("file://{}", encoded),
+        (true, false) => format!("file:///{}", encoded),
+        (false, _) => encoded,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        let entries = [
+            PlaylistFileEntry {
+                path: "Artist/Rock & Roll #1.flac".to_string(),
+                title: Some("Rock & Roll <Live>".to_string()),
+                artist: Some("Artist".to_string()),
+                album: Some("Album".to_string()),
+                duration: Some(215.5),
+            },
+            PlaylistFileEntry {
+                path: String::new(),
+                title: Some("Tags only".to_string()),
+                artist: None,
+                album: None,
+                duration: None,
+            },
+        ];
+
+        let parsed = parse(&write("Live & Loud", &entries)).unwrap();
+        assert_eq!(parsed.name.as_deref(), Some("Live & Loud"));
+        assert_eq!(parsed.entries.len(), entries.len());
+        for (parsed, entry) in parsed.entries.iter().zip(&entries) {
+            assert_eq!(parsed.path, entry.path);
+            assert_eq!(parsed.title, entry.title);
+            assert_eq!(parsed.artist, entry.artist);
+            assert_eq!(parsed.album, entry.album);
+            assert_eq!(parsed.duration, entry.duration);
+        }
+    }
+
+    #[cfg(not(windows))]
+    #[test]
+    fn absolute_paths_are_file_uris() {
+        assert_eq!(
+            path_to_location("/music/Song One.flac"),
+            "file:///music/Song%20One.flac"
+        );
+        assert_eq!(
+            location_to_path("file:///music/Song%20One.flac"),
+            "file:///music/Song%20One.flac"
+        );
+    }
+
+    #[test]
+    fn windows_paths_keep_their_drive_letter() {
+        assert_eq!(
+            path_to_location("Music\\Song One.flac"),
+            "Music/Song%20One.flac"
+        );
+        #[cfg(not(windows))]
+        assert_eq!(
+            path_to_location("C:\\Music\\Song One.flac"),
+            "C:/Music/Song%20One.flac"
+        );
+    }
+}
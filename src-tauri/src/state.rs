@@ -67,6 +67,10 @@ pub fn initialize_on_ready(app_handle: &AppHandle) {
     initialize_globals(app_handle);
 
     crate::database::database::initialize_database();
+    app_handle
+        .state::<AppState>()
+        .music_player
+        .restore_session();
     crate::music::metadata::MusicMetadata::initialize_ffmpeg_paths();
 
     log_directory_paths(app_handle);
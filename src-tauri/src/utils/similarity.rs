@@ -0,0 +1,53 @@
+/// Normalize string for comparison (lowercase, no punctuation, normalized whitespace)
+pub fn normalize_string(s: &str) -> String {
+    s.to_lowercase()
+        .chars()
+        .filter(|c| c.is_alphanumeric() || c.is_whitespace())
+        .collect::<String>()
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Calculate Levenshtein distance between two strings, keeping only the previous row of the
+/// distance matrix
+fn levenshtein_distance(s1: &str, s2: &str) -> usize {
+    let s1_chars: Vec<char> = s1.chars().collect();
+    let s2_chars: Vec<char> = s2.chars().collect();
+
+    if s1_chars.is_empty() {
+        return s2_chars.len();
+    }
+    if s2_chars.is_empty() {
+        return s1_chars.len();
+    }
+
+    let mut previous: Vec<usize> = (0..=s1_chars.len()).collect();
+    let mut current = vec![0usize; s1_chars.len() + 1];
+
+    for (j, c2) in s2_chars.iter().enumerate() {
+        current[0] = j + 1;
+        for (i, c1) in s1_chars.iter().enumerate() {
+            let indicator = usize::from(c1 != c2);
+            current[i + 1] = (current[i] + 1)
+                .min(previous[i + 1] + 1)
+                .min(previous[i] + indicator);
+        }
+        std::mem::swap(&mut previous, &mut current);
+    }
+
+    previous[s1_chars.len()]
+}
+
+/// Calculate string similarity (0.0 to 1.0)
+pub fn calculate_similarity(s1: &str, s2: &str) -> f64 {
+    let longer = if s1.len() > s2.len() { s1 } else { s2 };
+    let shorter = if s1.len() > s2.len() { s2 } else { s1 };
+
+    if longer.is_empty() {
+        return 1.0;
+    }
+
+    let distance = levenshtein_distance(longer, shorter);
+    (longer.len() - distance) as f64 / longer.len() as f64
+}
@@ -5,9 +5,23 @@ export const TauriCommands = {
 	MUSIC_PREVIOUS: 'music_previous',
 	MUSIC_CLEAR: 'music_clear',
 	MUSIC_REPEAT_MODE_SET: 'music_repeat_mode_set',
+	MUSIC_SHUFFLE_SET: 'music_shuffle_set',
+	MUSIC_QUEUE_GET: 'music_queue_get',
 	MUSIC_PLAYER_SYNC: 'music_player_sync',
 	MUSIC_PLAYER_REQUEST_SYNC: 'music_player_request_sync',
 	MUSIC_ALL_GET: 'music_all_get',
+	MUSIC_LIBRARY_CHANGED: 'music_library_changed',
+	MUSIC_SCAN_CANCEL: 'music_scan_cancel',
+	MUSIC_SCAN_SUMMARY_GET: 'music_scan_summary_get',
+	MUSIC_SCAN_RULES_GET: 'music_scan_rules_get',
+	MUSIC_SCAN_RULES_SET: 'music_scan_rules_set',
+	MUSIC_ROOTS_GET: 'music_roots_get',
+	MUSIC_ROOT_ADD: 'music_root_add',
+	MUSIC_ROOT_REMOVE: 'music_root_remove',
+	MUSIC_ROOT_ENABLED_SET: 'music_root_enabled_set',
+	MUSIC_ROOT_LABEL_SET: 'music_root_label_set',
+	MUSIC_SCAN_PROGRESS: 'music_scan_progress',
+	MUSIC_SCAN_FINISHED: 'music_scan_finished',
 	MUSIC_INFO_GET: 'music_info_get',
 	MUSIC_POSITION_SET: 'music_position_set',
 	MUSIC_QUEUE_ADD: 'music_queue_add',
@@ -19,18 +33,50 @@ export const TauriCommands = {
 	MUSIC_VOLUME_SET: 'music_volume_set',
 	MUSIC_EQUALIZER: 'music_equalizer',
 	MUSIC_EQUALIZER_RESET: 'music_equalizer_reset',
+	MUSIC_EQUALIZER_GET: 'music_equalizer_get',
+	MUSIC_EQUALIZER_SET: 'music_equalizer_set',
+	MUSIC_EQUALIZER_PRESET_ALL_GET: 'music_equalizer_preset_all_get',
+	MUSIC_EQUALIZER_PRESET_SAVE: 'music_equalizer_preset_save',
+	MUSIC_EQUALIZER_PRESET_APPLY: 'music_equalizer_preset_apply',
+	MUSIC_EQUALIZER_PRESET_DELETE: 'music_equalizer_preset_delete',
 	MUSIC_IMAGE_GET: 'music_image_get',
+	MUSIC_TAGS_WRITE: 'music_tags_write',
 	MUSIC_DEFAULT_COVER_ART_GET: 'music_default_cover_art_get',
 	MUSIC_VISUALIZER_BUFFER_GET: 'music_visualizer_buffer_get',
 	MUSIC_CURRENT_DURATION_GET: 'music_current_duration_get',
 	MUSIC_LYRICS_GET: 'music_lyrics_get',
 	MUSIC_BIT_PERFECT_TOGGLE: 'music_bit_perfect_toggle',
+	MUSIC_REPLAY_GAIN_GET: 'music_replay_gain_get',
+	MUSIC_REPLAY_GAIN_SET: 'music_replay_gain_set',
+	MUSIC_CROSSFADE_GET: 'music_crossfade_get',
+	MUSIC_CROSSFADE_SET: 'music_crossfade_set',
+	MUSIC_OUTPUT_DEVICES_GET: 'music_output_devices_get',
+	MUSIC_OUTPUT_DEVICE_SET: 'music_output_device_set',
 
 	PLAYLIST_ALL_GET: 'playlist_all_get',
 	PLAYLIST_CREATE: 'playlist_create',
+	PLAYLIST_SMART_PREVIEW: 'playlist_smart_preview',
 	PLAYLIST_DELETE: 'playlist_delete',
+	PLAYLIST_UPDATE: 'playlist_update',
+	PLAYLIST_MUSICS_INSERT: 'playlist_musics_insert',
+	PLAYLIST_MUSICS_REMOVE: 'playlist_musics_remove',
+	PLAYLIST_MUSICS_MOVE: 'playlist_musics_move',
 	PLAYLIST_IMAGE_UPLOAD: 'playlist_image_upload',
 	PLAYLIST_IMAGE_READ: 'playlist_image_read',
+	PLAYLIST_IMPORT: 'playlist_import',
+	PLAYLIST_EXPORT: 'playlist_export',
+
+	MUSIC_PLAY_COUNTS_GET: 'music_play_counts_get',
+	MUSIC_TOP_TRACKS_GET: 'music_top_tracks_get',
+	MUSIC_TOP_ARTISTS_GET: 'music_top_artists_get',
+	MUSIC_TOP_ALBUMS_GET: 'music_top_albums_get',
+	MUSIC_RECENTLY_PLAYED_GET: 'music_recently_played_get',
+
+	MUSIC_ARTISTS_GET: 'music_artists_get',
+	MUSIC_ALBUMS_GET: 'music_albums_get',
+	MUSIC_GENRES_GET: 'music_genres_get',
+	MUSIC_QUERY: 'music_query',
+	MUSIC_SEARCH: 'music_search',
 
 	FOLDER_ITEMS_GET: 'folder_items_get',
 	FOLDER_FIRST_MUSIC_PATH_GET: 'folder_first_music_path_get',
@@ -48,6 +94,11 @@ export const TauriCommands = {
 	LOG: 'log',
 
 	COVER_ART_GET: 'cover_art_get',
+	COVER_ART_LOOKUP_GET: 'cover_art_lookup_get',
+	COVER_ART_LOOKUP_SET: 'cover_art_lookup_set',
+	CACHE_STATS_GET: 'cache_stats_get',
+	CACHE_CLEAR: 'cache_clear',
+	CACHE_MAX_SIZE_SET: 'cache_max_size_set',
 	LYRIC_GET: 'lyric_get',
 	ANIMATED_BACKGROUND_UPDATE: 'animated_background_update',
 	ANIMATED_BACKGROUND_RESTORE: 'animated_background_restore',
@@ -1,6 +1,6 @@
 import { isAndroid } from '$lib/platform';
 import TauriIntroAPI from '$lib/tauri/TauriIntroAPI';
-import PersistentStoreService from '$lib/services/PersistentStoreService.svelte';
+import TauriLibraryAPI from '$lib/tauri/TauriLibraryAPI';
 import LibraryService from '$lib/services/LibraryService.svelte';
 
 export function useIntro() {
@@ -16,7 +16,7 @@ export function useIntro() {
 
 	async function requestDirectoryPath() {
 		const path = await TauriIntroAPI.requestDirectoryPath();
-		if (isAndroid()) await PersistentStoreService.musicPath.set([path]);
+		if (isAndroid()) await TauriLibraryAPI.addMusicRoot(path);
 
 		animatedClasses = 'animate__fadeOut';
 	}
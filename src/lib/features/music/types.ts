@@ -9,11 +9,31 @@ export interface MusicData {
 	album?: string;
 	albumArtist?: string;
 	trackNumber?: string;
+	trackTotal?: number;
+	discNumber?: number;
+	discTotal?: number;
 	image?: string;
 	bitsPerSample?: number;
 	sampleRate?: number;
 	genre?: string;
 	date?: string;
+	replayGainTrackGain?: number;
+	replayGainTrackPeak?: number;
+	replayGainAlbumGain?: number;
+	replayGainAlbumPeak?: number;
+	loudness?: number;
+	composer?: string;
+	conductor?: string;
+	label?: string;
+	isrc?: string;
+	musicbrainzRecordingId?: string;
+	musicbrainzAlbumId?: string;
+	musicbrainzArtistId?: string;
+	musicbrainzReleaseGroupId?: string;
+	codec?: string;
+	bitrate?: number;
+	channels?: number;
+	fileSize?: number;
 }
 
 export interface AlbumData {
@@ -34,6 +54,8 @@ export interface MusicPlayerSync {
 	isPlaying: boolean;
 	duration: number;
 	repeatMode: RepeatMode;
+	isShuffle: boolean;
+	isBitPerfect: boolean;
 }
 
 export enum RepeatMode {
@@ -57,4 +79,169 @@ export interface PlaylistData {
 	title?: string;
 	artist?: string;
 	paths: string[];
+	rules?: SmartPlaylistRules;
+}
+
+export type SmartField =
+	| 'path'
+	| 'title'
+	| 'artist'
+	| 'album'
+	| 'albumArtist'
+	| 'genre'
+	| 'date'
+	| 'trackNumber'
+	| 'discNumber'
+	| 'composer'
+	| 'codec'
+	| 'duration'
+	| 'bitrate'
+	| 'bitsPerSample'
+	| 'sampleRate'
+	| 'playCount'
+	| 'lastPlayedAt';
+
+export type SmartOperator =
+	| 'equals'
+	| 'notEquals'
+	| 'contains'
+	| 'notContains'
+	| 'startsWith'
+	| 'endsWith'
+	| 'lessThan'
+	| 'lessThanOrEqual'
+	| 'greaterThan'
+	| 'greaterThanOrEqual';
+
+export type SmartRule =
+	| { type: 'all'; rules: SmartRule[] }
+	| { type: 'any'; rules: SmartRule[] }
+	| { type: 'not'; rule: SmartRule }
+	| SmartCondition;
+
+export interface SmartCondition {
+	type: 'condition';
+	field: SmartField;
+	operator: SmartOperator;
+	value: string | number | null;
+}
+
+export type SmartSort = { by: 'random' } | { by: 'field'; field: SmartField; descending?: boolean };
+
+export interface SmartPlaylistRules {
+	rule: SmartRule;
+	sort?: SmartSort;
+	limit?: number;
+}
+
+export interface ScanProgress {
+	found: number;
+	processed: number;
+	failed: number;
+	currentPath: string | null;
+	eta: number | null;
+}
+
+export interface ScanFailure {
+	path: string;
+	error: string;
+}
+
+export interface ScanSummary {
+	found: number;
+	processed: number;
+	failed: ScanFailure[];
+	updated: boolean;
+	cancelled: boolean;
+	elapsed: number;
+}
+
+export interface ArtistSummary {
+	id: number;
+	name: string;
+	trackCount: number;
+	duration: number;
+}
+
+export interface AlbumSummary {
+	id: number;
+	name: string;
+	artist: string | null;
+	trackCount: number;
+	duration: number;
+	date: string | null;
+	path: string;
+}
+
+export interface GenreSummary {
+	id: number;
+	name: string;
+	trackCount: number;
+	duration: number;
+}
+
+export interface MusicFilter {
+	artistId?: number;
+	albumId?: number;
+	genreId?: number;
+	folder?: string;
+	yearFrom?: number;
+	yearTo?: number;
+	formats?: string[];
+}
+
+export interface MusicSortKey {
+	field: SmartField;
+	descending?: boolean;
+}
+
+export interface MusicQuery {
+	filter?: MusicFilter;
+	sort?: MusicSortKey[];
+	offset?: number;
+	limit?: number;
+}
+
+export interface MusicPage {
+	musics: MusicData[];
+	total: number;
+}
+
+export interface TagEdit {
+	title?: string;
+	artist?: string;
+	album?: string;
+	albumArtist?: string;
+	genre?: string;
+	date?: string;
+	trackNumber?: number;
+	trackTotal?: number;
+	discNumber?: number;
+	discTotal?: number;
+	image?: string;
+}
+
+export interface TagWriteFailure {
+	path: string;
+	error: string;
+}
+
+export interface TagWriteResult {
+	musics: MusicData[];
+	failed: TagWriteFailure[];
+}
+
+export interface MusicRoot {
+	id: number;
+	path: string;
+	label: string | null;
+	enabled: boolean;
+	lastScannedAt: number | null;
+	trackCount: number;
+}
+
+export interface RootRemoval {
+	tracks: number;
+	playlistEntries: number;
+	playlists: string[];
 }
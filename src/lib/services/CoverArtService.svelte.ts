@@ -5,6 +5,18 @@ export interface CoverArtCacheQuery {
 	artist: string;
 	album?: string;
 	title?: string;
+	path?: string;
+}
+
+export type CoverArtSource = 'embedded' | 'folder' | 'online';
+
+export type CoverArtProvider = 'musicBrainz' | 'iTunes' | 'deezer' | 'lastFm';
+
+export interface CoverArtLookup {
+	sources: CoverArtSource[];
+	folderNames: string[];
+	providers: CoverArtProvider[];
+	lastfmApiKey: string | null;
 }
 
 export enum CoverArtSize {
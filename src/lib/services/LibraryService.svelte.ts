@@ -2,14 +2,20 @@ import type { MusicData } from '$lib/features/music/types';
 import musicStore from '$lib/stores/music.svelte';
 import TauriLibraryAPI from '$lib/tauri/TauriLibraryAPI';
 
+let isListeningLibraryChanges = false;
+
 const LibraryService = {
 	initialize: async () => {
+		if (!isListeningLibraryChanges) {
+			isListeningLibraryChanges = true;
+			TauriLibraryAPI.listenLibraryChanged(() => LibraryService.loadMusicList());
+		}
 		await LibraryService.loadMusicList();
 	},
-	loadMusicList: async () => {
+	loadMusicList: async (force = false) => {
 		const now = performance.now();
 
-		musicStore.list = await TauriLibraryAPI.getMusicList();
+		musicStore.list = await TauriLibraryAPI.getMusicList(force);
 
 		console.log(`Getting music list took ${performance.now() - now} ms`);
 
@@ -50,6 +56,7 @@ const LibraryService = {
 			return {
 				original,
 				album: original.album || '',
+				disc: original.discNumber ?? 1,
 				track: isNaN(track) ? Infinity : track, // Sort items without a valid track number last
 				filename: original.filename
 			};
@@ -60,6 +67,10 @@ const LibraryService = {
 				return a.album.localeCompare(b.album);
 			}
 
+			if (a.disc !== b.disc) {
+				return a.disc - b.disc;
+			}
+
 			if (a.track !== b.track) {
 				return a.track - b.track;
 			}
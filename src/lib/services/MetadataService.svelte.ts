@@ -3,7 +3,6 @@ import { MusicConfig } from '$lib/constants/MusicConfig';
 import musicStore from '$lib/stores/music.svelte';
 import TauriMetadataAPI from '$lib/tauri/TauriMetadataAPI';
 import FolderService from './FolderService.svelte';
-import CoverArtService from './CoverArtService.svelte';
 
 let defaultCoverArt: string | null = null;
 
@@ -33,16 +32,8 @@ const MetadataService = {
 				return URL.createObjectURL(blob);
 			}
 		} catch (e) {}
-		if (music.title == null || music.artist == null) return defaultCoverArt;
-		const coverArt = await CoverArtService.getByQuery(
-			{
-				artist: music.artist!,
-				title: music.album ? undefined : music.title!,
-				album: music.album ?? undefined
-			},
-			size
-		);
-		return coverArt ?? defaultCoverArt;
+		// Online art is part of the lookup done by the backend
+		return defaultCoverArt;
 	},
 	getFolderCoverArt: async (folderPath: string) => {
 		const path = await TauriMetadataAPI.getFolderCoverArtPath(
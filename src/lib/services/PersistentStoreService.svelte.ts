@@ -44,21 +44,11 @@ const PersistentStoreService = {
 		]);
 	},
 
+	// Kept in sync with the enabled music roots by the backend
 	musicPath: {
 		key: 'music-path',
 		separator: '||',
-		get: async () => (await makeGetter<string>('music-path')())?.split('||') ?? [],
-		set: async (value: string[]) => (await getStore()).set('music-path', value.join('||')),
-		add: async (value: string) => {
-			const paths = await PersistentStoreService.musicPath.get();
-			paths.push(value);
-			await PersistentStoreService.musicPath.set(paths);
-		},
-		remove: async (index: number) => {
-			const paths = await PersistentStoreService.musicPath.get();
-			paths.splice(index, 1);
-			await PersistentStoreService.musicPath.set(paths);
-		}
+		get: async () => (await makeGetter<string>('music-path')())?.split('||') ?? []
 	},
 
 	volume: makeBinding<number>('volume', 1, (value) => (musicStore.volume = value)),
@@ -0,0 +1,30 @@
+import { invoke } from '@tauri-apps/api/core';
+import { TauriCommands } from '$lib/constants/TauriCommands';
+
+export type CacheKind = 'cover' | 'onlineCover' | 'lyrics';
+
+export interface CacheKindStats {
+	kind: CacheKind;
+	entries: number;
+	size: number;
+}
+
+export interface CacheStats {
+	kinds: CacheKindStats[];
+	size: number;
+	maxSize: number;
+}
+
+const TauriCacheAPI = {
+	getStats: () => {
+		return invoke<CacheStats>(TauriCommands.CACHE_STATS_GET);
+	},
+	clear: (kind?: CacheKind) => {
+		return invoke(TauriCommands.CACHE_CLEAR, { kind });
+	},
+	setMaxSize: (maxSize: number) => {
+		return invoke(TauriCommands.CACHE_MAX_SIZE_SET, { maxSize });
+	}
+};
+
+export default TauriCacheAPI;
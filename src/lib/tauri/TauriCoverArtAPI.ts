@@ -1,9 +1,16 @@
 import { invoke } from '@tauri-apps/api/core';
 import { TauriCommands } from '$lib/constants/TauriCommands';
+import type { CoverArtCacheQuery, CoverArtLookup } from '$lib/services/CoverArtService.svelte';
 
 const TauriCoverArtAPI = {
-	getCoverArt: (query: { artist: string; album?: string; title?: string }, size?: number) => {
+	getCoverArt: (query: CoverArtCacheQuery, size?: number) => {
 		return invoke<number[]>(TauriCommands.COVER_ART_GET, { query, size });
+	},
+	getLookup: () => {
+		return invoke<CoverArtLookup>(TauriCommands.COVER_ART_LOOKUP_GET);
+	},
+	setLookup: (lookup: CoverArtLookup) => {
+		return invoke(TauriCommands.COVER_ART_LOOKUP_SET, { lookup });
 	}
 };
 
@@ -1,10 +1,72 @@
 import { invoke } from '@tauri-apps/api/core';
+import { listen } from '@tauri-apps/api/event';
 import { TauriCommands } from '$lib/constants/TauriCommands';
-import type { MusicData } from '$lib/features/music/types';
+import type {
+	AlbumSummary,
+	ArtistSummary,
+	GenreSummary,
+	MusicData,
+	MusicPage,
+	MusicQuery,
+	MusicRoot,
+	RootRemoval,
+	ScanProgress,
+	ScanSummary,
+	TagEdit,
+	TagWriteResult
+} from '$lib/features/music/types';
 
 const TauriLibraryAPI = {
-	getMusicList: async () => {
-		return invoke<MusicData[] | null>(TauriCommands.MUSIC_ALL_GET);
+	getMusicList: async (force = false) => {
+		return invoke<MusicData[] | null>(TauriCommands.MUSIC_ALL_GET, { force });
+	},
+	listenLibraryChanged: (callback: () => void) => {
+		return listen(TauriCommands.MUSIC_LIBRARY_CHANGED, callback);
+	},
+	cancelScan: () => {
+		return invoke(TauriCommands.MUSIC_SCAN_CANCEL);
+	},
+	getScanSummary: () => {
+		return invoke<ScanSummary | null>(TauriCommands.MUSIC_SCAN_SUMMARY_GET);
+	},
+	listenScanProgress: (callback: (event: { payload: ScanProgress }) => void) => {
+		return listen<ScanProgress>(TauriCommands.MUSIC_SCAN_PROGRESS, callback);
+	},
+	listenScanFinished: (callback: (event: { payload: ScanSummary }) => void) => {
+		return listen<ScanSummary>(TauriCommands.MUSIC_SCAN_FINISHED, callback);
+	},
+	getArtists: () => {
+		return invoke<ArtistSummary[]>(TauriCommands.MUSIC_ARTISTS_GET);
+	},
+	getAlbums: () => {
+		return invoke<AlbumSummary[]>(TauriCommands.MUSIC_ALBUMS_GET);
+	},
+	getGenres: () => {
+		return invoke<GenreSummary[]>(TauriCommands.MUSIC_GENRES_GET);
+	},
+	queryMusic: (query: MusicQuery) => {
+		return invoke<MusicPage>(TauriCommands.MUSIC_QUERY, { query });
+	},
+	searchMusic: (query: string, limit?: number) => {
+		return invoke<MusicData[]>(TauriCommands.MUSIC_SEARCH, { query, limit });
+	},
+	writeTags: (paths: string[], edit: TagEdit) => {
+		return invoke<TagWriteResult>(TauriCommands.MUSIC_TAGS_WRITE, { paths, edit });
+	},
+	getMusicRoots: () => {
+		return invoke<MusicRoot[]>(TauriCommands.MUSIC_ROOTS_GET);
+	},
+	addMusicRoot: (path: string, label?: string) => {
+		return invoke<MusicRoot>(TauriCommands.MUSIC_ROOT_ADD, { path, label });
+	},
+	removeMusicRoot: (id: number) => {
+		return invoke<RootRemoval>(TauriCommands.MUSIC_ROOT_REMOVE, { id });
+	},
+	setMusicRootEnabled: (id: number, enabled: boolean) => {
+		return invoke(TauriCommands.MUSIC_ROOT_ENABLED_SET, { id, enabled });
+	},
+	setMusicRootLabel: (id: number, label: string | null) => {
+		return invoke(TauriCommands.MUSIC_ROOT_LABEL_SET, { id, label });
 	}
 };
 